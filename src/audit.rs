@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::types::{AuditEntry, AuditQuery};
+
+/// Default size an audit file may reach before it is rotated (10 MiB)
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Most entries kept in memory; older ones are only in the file
+const MAX_ENTRIES: usize = 10_000;
+
+/// Append-only trail of changes made to mappings
+///
+/// Entries are kept in memory for querying and, when opened with a path,
+/// appended to a JSONL file that is read back on the next start. A file
+/// about to outgrow its size cap is moved to `<path>.1`, replacing the
+/// previous one, and a new file started.
+#[derive(Clone)]
+pub struct AuditLog {
+    entries: Arc<Mutex<Vec<AuditEntry>>>,
+    path: Option<PathBuf>,
+    max_file_bytes: u64,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self {
+            entries: Arc::default(),
+            path: None,
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+        }
+    }
+}
+
+impl AuditLog {
+    /// An audit log that is only kept in memory
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open (or create) a JSONL audit file, loading its existing entries
+    ///
+    /// Lines that don't parse, such as one cut short by a crash, are skipped
+    /// with a warning.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => parse_entries(&contents, &path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        entries.drain(..entries.len().saturating_sub(MAX_ENTRIES));
+
+        Ok(Self {
+            entries: Arc::new(Mutex::new(entries)),
+            path: Some(path),
+            ..Self::default()
+        })
+    }
+
+    /// Rotate the file before it grows past `max` bytes
+    pub fn with_max_file_bytes(mut self, max: u64) -> Self {
+        self.max_file_bytes = max;
+        self
+    }
+
+    /// Append an entry, persisting it first when backed by a file
+    pub async fn record(&self, entry: AuditEntry) {
+        // Holding the lock across the write keeps file and memory in the same order
+        let mut entries = self.entries.lock().await;
+        if let Some(path) = &self.path {
+            if let Err(e) = append_line(path, &entry, self.max_file_bytes).await {
+                error!("Failed to write audit entry to {}: {:#}", path.display(), e);
+            }
+        }
+        if entries.len() >= MAX_ENTRIES {
+            entries.remove(0);
+        }
+        entries.push(entry);
+    }
+
+    /// Entries matching the query, oldest first
+    pub async fn query(&self, query: &AuditQuery) -> Vec<AuditEntry> {
+        let entries = self.entries.lock().await;
+        let matching: Vec<&AuditEntry> = entries
+            .iter()
+            .filter(|e| query.mapping_id.is_none_or(|id| e.mapping_id == id))
+            .collect();
+        let skip = query
+            .limit
+            .map_or(0, |limit| matching.len().saturating_sub(limit));
+        matching.into_iter().skip(skip).cloned().collect()
+    }
+}
+
+/// Entries in a JSONL file's contents, skipping lines that don't parse
+fn parse_entries(contents: &str, path: &Path) -> Vec<AuditEntry> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(n, line)| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                warn!(
+                    "Skipping invalid line {} of audit log {}: {}",
+                    n + 1,
+                    path.display(),
+                    e
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// `<path>.1`, where a full audit file is moved
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+async fn append_line(path: &Path, entry: &AuditEntry, max_file_bytes: u64) -> Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let size = match tokio::fs::metadata(path).await {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(e.into()),
+    };
+    if size > 0 && size + line.len() as u64 > max_file_bytes {
+        let rotated = rotated_path(path);
+        tokio::fs::rename(path, &rotated).await?;
+        info!("Rotated audit log to {}", rotated.display());
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AuditAction, MappingStatus};
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn entry(mapping_id: Uuid, action: AuditAction) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now(),
+            action,
+            mapping_id,
+            actor: None,
+            before: Some(MappingStatus::Active),
+            after: Some(MappingStatus::Paused),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entries_survive_reopen() {
+        let path = std::env::temp_dir().join(format!("s3-buddy-audit-{}.jsonl", Uuid::new_v4()));
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let audit = AuditLog::open(&path).await.unwrap();
+        audit.record(entry(a, AuditAction::Pause)).await;
+        audit.record(entry(b, AuditAction::Delete)).await;
+        audit.record(entry(a, AuditAction::Resume)).await;
+
+        let reopened = AuditLog::open(&path).await.unwrap();
+        let all = reopened.query(&AuditQuery::default()).await;
+        let actions: Vec<AuditAction> = all.iter().map(|e| e.action).collect();
+        assert_eq!(
+            actions,
+            vec![AuditAction::Pause, AuditAction::Delete, AuditAction::Resume]
+        );
+
+        let query = AuditQuery {
+            mapping_id: Some(a),
+            limit: Some(1),
+        };
+        let latest = reopened.query(&query).await;
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].action, AuditAction::Resume);
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_truncated_line_is_skipped() {
+        let path = std::env::temp_dir().join(format!("s3-buddy-audit-{}.jsonl", Uuid::new_v4()));
+        let line = serde_json::to_string(&entry(Uuid::new_v4(), AuditAction::Pause)).unwrap();
+        // A crash cut the last write short
+        let contents = format!("{}\n{}", line, &line[..line.len() / 2]);
+        tokio::fs::write(&path, contents).await.unwrap();
+
+        let audit = AuditLog::open(&path).await.unwrap();
+        assert_eq!(audit.query(&AuditQuery::default()).await.len(), 1);
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_full_file_is_rotated() {
+        let path = std::env::temp_dir().join(format!("s3-buddy-audit-{}.jsonl", Uuid::new_v4()));
+        let line_len = serde_json::to_string(&entry(Uuid::new_v4(), AuditAction::Pause))
+            .unwrap()
+            .len() as u64
+            + 1;
+
+        // Room for two entries per file
+        let audit = AuditLog::open(&path)
+            .await
+            .unwrap()
+            .with_max_file_bytes(2 * line_len);
+        for _ in 0..3 {
+            audit
+                .record(entry(Uuid::new_v4(), AuditAction::Pause))
+                .await;
+        }
+
+        let lines = |path: PathBuf| async move {
+            tokio::fs::read_to_string(path)
+                .await
+                .unwrap()
+                .lines()
+                .count()
+        };
+        assert_eq!(lines(rotated_path(&path)).await, 2);
+        assert_eq!(lines(path.clone()).await, 1);
+        // Memory still has everything
+        assert_eq!(audit.query(&AuditQuery::default()).await.len(), 3);
+
+        tokio::fs::remove_file(rotated_path(&path)).await.unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
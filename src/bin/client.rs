@@ -1,14 +1,189 @@
 use anyhow::Result;
+use clap::{Parser, Subcommand};
+use s3_buddy::client::{mappings_table, ApiClient};
+use s3_buddy::tui::{parse_columns, TuiOptions};
+use s3_buddy::CreateMappingRequest;
+use serde::Serialize;
 use std::env;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Manage S3 Buddy mappings; opens the dashboard when run without a command
+#[derive(Parser)]
+#[command(name = "s3-buddy-client", version)]
+struct Cli {
+    /// Don't capture the mouse in the dashboard, keeping terminal text selection
+    #[arg(long, global = true)]
+    no_mouse: bool,
+    /// Print JSON instead of human-readable output
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List all mappings
+    List,
+    /// Create a mapping
+    Add {
+        #[arg(long)]
+        s3_url: String,
+        #[arg(long)]
+        short_url: String,
+        #[arg(long)]
+        hosted_zone_id: String,
+        /// Presigned URL lifetime; the server's default if omitted
+        #[arg(long)]
+        presign_hours: Option<u64>,
+        /// Refresh interval; derived from the presign duration if omitted
+        #[arg(long)]
+        refresh_hours: Option<u64>,
+        /// Create the mapping without checking its S3 object and hosted zone
+        #[arg(long)]
+        skip_validation: bool,
+    },
+    /// Delete a mapping
+    Delete { id: Uuid },
+    /// Stop refreshing a mapping
+    Pause { id: Uuid },
+    /// Start refreshing a paused mapping again
+    Resume { id: Uuid },
+    /// Check every mapping's DNS record and fix drifted ones
+    Reconcile,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Get server URL from environment or use default
     let server_url =
         env::var("S3_BUDDY_SERVER").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let api = ApiClient::new(server_url.clone());
 
-    // Run the TUI
-    s3_buddy::tui::run_tui(server_url).await?;
+    let Some(command) = cli.command else {
+        let mut options = TuiOptions::from_args(&[], env::var("S3_BUDDY_NO_MOUSE").ok().as_deref())
+            .with_stale_after(
+                env::var("S3_BUDDY_STALE_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .map(Duration::from_secs),
+            )
+            .with_columns(
+                env::var("S3_BUDDY_COLUMNS")
+                    .ok()
+                    .map(|v| parse_columns(&v))
+                    .transpose()?,
+            );
+        // 0 turns auto-refresh off
+        if let Some(secs) = env::var("S3_BUDDY_AUTO_REFRESH_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            options = options.with_auto_refresh((secs > 0).then(|| Duration::from_secs(secs)));
+        }
+        options.mouse_capture &= !cli.no_mouse;
+
+        // Run the TUI
+        return s3_buddy::tui::run_tui(server_url, options).await;
+    };
+
+    match command {
+        Command::List => {
+            let mappings = api.list_mappings().await?;
+            if cli.json {
+                print_json(&mappings)?;
+            } else {
+                print!("{}", mappings_table(&mappings));
+            }
+        }
+        Command::Add {
+            s3_url,
+            short_url,
+            hosted_zone_id,
+            presign_hours,
+            refresh_hours,
+            skip_validation,
+        } => {
+            let request = CreateMappingRequest {
+                s3_url,
+                short_url,
+                hosted_zone_id,
+                slug: None,
+                presign_duration_secs: presign_hours.map(|h| h * 3600),
+                refresh_interval_secs: refresh_hours.map(|h| h * 3600),
+                max_presign_duration_secs: None,
+                credentials_profile: None,
+                requester_pays: false,
+                use_accelerate: false,
+                extra_query_params: Default::default(),
+                access_point_arn: None,
+                addressing_style: Default::default(),
+                manage_dns: true,
+                operation: Default::default(),
+                dns_record_type: Default::default(),
+                dns_ttl_secs: None,
+                allowed_window: None,
+                force_content_type: None,
+                download_filename: None,
+                fallback_s3_urls: Vec::new(),
+                initial_refresh_delay_secs: None,
+                skip_validation,
+                notes: None,
+            };
+            let mapping = api.create_mapping(&request).await?;
+            if cli.json {
+                print_json(&mapping)?;
+            } else {
+                println!(
+                    "Created {}: {} -> {}",
+                    mapping.id, mapping.short_url, mapping.s3_url
+                );
+            }
+        }
+        Command::Delete { id } => {
+            api.delete_mapping(id).await?;
+            if cli.json {
+                print_json(&serde_json::json!({ "id": id, "deleted": true }))?;
+            } else {
+                println!("Deleted {}", id);
+            }
+        }
+        Command::Pause { id } => {
+            let mapping = api.pause_mapping(id).await?;
+            if cli.json {
+                print_json(&mapping)?;
+            } else {
+                println!("Paused {}", mapping.short_url);
+            }
+        }
+        Command::Resume { id } => {
+            let mapping = api.resume_mapping(id).await?;
+            if cli.json {
+                print_json(&mapping)?;
+            } else {
+                println!("Resumed {}", mapping.short_url);
+            }
+        }
+        Command::Reconcile => {
+            let report = api.reconcile().await?;
+            if cli.json {
+                print_json(&report)?;
+            } else {
+                println!(
+                    "Checked {} records: {} fixed, {} failed",
+                    report.checked, report.fixed, report.failed
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
 
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
     Ok(())
 }
@@ -1,44 +1,177 @@
-use anyhow::Result;
-use s3_buddy::{MappingManager, Route53Client, S3Client};
+use anyhow::{Context, Result};
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
+use s3_buddy::audit::AuditLog;
+use s3_buddy::config::ServerConfig;
+use s3_buddy::metrics::{self, CloudWatchClient};
+use s3_buddy::notify::{
+    EmailNotifier, Notifier, Notifiers, ReqwestTransport, SesTransport, SlackNotifier,
+    WebhookNotifier,
+};
+use s3_buddy::registry::load_aws_config;
+use s3_buddy::{ClientRegistry, Config, Mapping, MappingDefaults, MappingManager};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing::info;
-use tracing_subscriber::EnvFilter;
+use std::time::Duration;
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
-        )
-        .init();
+    // Initialize logging, as JSON with LOG_FORMAT=json
+    s3_buddy::logging::init_from_env()?;
 
     info!("Starting S3 Buddy Server");
 
-    // Load AWS configuration
-    let aws_config = aws_config::load_from_env().await;
-
-    // Create AWS clients
-    let s3_client = S3Client::new(aws_sdk_s3::Client::new(&aws_config));
-    let route53_client = Route53Client::new(aws_sdk_route53::Client::new(&aws_config));
+    // Load AWS clients for the default credentials and any named profiles
+    let aws_config = load_aws_config(None).await;
+    let clients = ClientRegistry::from_config(&aws_config).await;
 
     // Create mapping manager
-    let (manager, mut log_rx) = MappingManager::new(s3_client, route53_client);
-    let manager = Arc::new(manager);
+    let (manager, mut log_rx) = MappingManager::with_registry(clients);
+    let grace_secs = std::env::var("FIRST_REFRESH_GRACE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let recycle_ttl = std::env::var("DELETED_RETENTION_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(s3_buddy::manager::DEFAULT_RECYCLE_TTL);
+    let defaults = MappingDefaults::from_lookup(|name| std::env::var(name).ok());
+    let refresh_debounce = std::env::var("REFRESH_DEBOUNCE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(s3_buddy::manager::DEFAULT_REFRESH_DEBOUNCE);
+    let initial_refresh_delay = std::env::var("INITIAL_REFRESH_DELAY_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_default();
+    let presign_concurrency = std::env::var("PRESIGN_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&limit| limit > 0)
+        .unwrap_or(s3_buddy::manager::DEFAULT_PRESIGN_CONCURRENCY);
+    let presign_wait = std::env::var("PRESIGN_WAIT_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(s3_buddy::manager::DEFAULT_PRESIGN_WAIT);
+    // Comma-separated seconds, e.g. "10,30,60"; empty disables fast retries
+    let initial_retry_backoff = std::env::var("INITIAL_RETRY_BACKOFF_SECS")
+        .ok()
+        .map(|v| seconds_list(&v))
+        .unwrap_or_else(|| s3_buddy::manager::DEFAULT_INITIAL_RETRY_BACKOFF.to_vec());
+    let failure_retry_backoff = std::env::var("FAILURE_RETRY_BACKOFF_SECS")
+        .ok()
+        .map(|v| seconds_list(&v))
+        .unwrap_or_else(|| s3_buddy::manager::DEFAULT_FAILURE_RETRY_BACKOFF.to_vec());
+    let audit = match std::env::var("AUDIT_LOG_PATH") {
+        Ok(path) => AuditLog::open(&path)
+            .await
+            .context("Failed to open audit log")?
+            .with_max_file_bytes(
+                std::env::var("AUDIT_LOG_MAX_BYTES")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(s3_buddy::audit::DEFAULT_MAX_FILE_BYTES),
+            ),
+        Err(_) => AuditLog::in_memory(),
+    };
+    // Alert on failures and recoveries through every configured notifier
+    let mut notifiers: Vec<Arc<dyn Notifier>> = Vec::new();
+    let http = Arc::new(ReqwestTransport::default());
+    if let Ok(url) = std::env::var("SLACK_WEBHOOK_URL") {
+        notifiers.push(Arc::new(SlackNotifier::new(url, http.clone())));
+    }
+    if let Ok(url) = std::env::var("NOTIFY_WEBHOOK_URL") {
+        notifiers.push(Arc::new(WebhookNotifier::new(url, http.clone())));
+    }
+    if let (Ok(from), Ok(to)) = (
+        std::env::var("NOTIFY_EMAIL_FROM"),
+        std::env::var("NOTIFY_EMAIL_TO"),
+    ) {
+        let ses = SesTransport::new(aws_sdk_sesv2::Client::new(&aws_config));
+        let to = to.split(',').map(|s| s.trim().to_string()).collect();
+        notifiers.push(Arc::new(EmailNotifier::new(from, to, Arc::new(ses))));
+    }
+
+    let manager = Arc::new(
+        manager
+            .with_notifiers(Notifiers::new(notifiers))
+            .with_grace_period(Duration::from_secs(grace_secs))
+            .with_recycle_ttl(recycle_ttl)
+            .with_defaults(defaults)
+            .with_refresh_debounce(refresh_debounce)
+            .with_initial_retry_backoff(initial_retry_backoff)
+            .with_failure_retry_backoff(failure_retry_backoff)
+            .with_initial_refresh_delay(initial_refresh_delay)
+            .with_presign_limit(presign_concurrency, presign_wait)
+            .with_audit_log(audit)
+            .with_motd(std::env::var("S3_BUDDY_MOTD").ok())
+            .with_probe_bucket(std::env::var("READY_PROBE_BUCKET").ok()),
+    );
 
     // Spawn task to handle refresh logs
     tokio::spawn(async move {
         while let Some(log) = log_rx.recv().await {
             if log.success {
-                info!("[{}] {}", log.mapping_id, log.message);
+                info!(mapping_id = %log.mapping_id, "[{}] {}", log.mapping_id, log.message);
             } else {
-                tracing::error!("[{}] {}", log.mapping_id, log.message);
+                tracing::error!(mapping_id = %log.mapping_id, "[{}] {}", log.mapping_id, log.message);
             }
         }
     });
 
+    // Optionally push refresh counts to CloudWatch
+    if let Ok(namespace) = std::env::var("CLOUDWATCH_NAMESPACE") {
+        let cloudwatch = CloudWatchClient::new(aws_sdk_cloudwatch::Client::new(&aws_config));
+        metrics::spawn_publisher(
+            Arc::new(cloudwatch),
+            namespace,
+            manager.refresh_counters(),
+            metrics::DEFAULT_PUBLISH_INTERVAL,
+        );
+    }
+
+    // Seed a mapping from the legacy single-mapping environment variables
+    if let Some(config) = Config::from_legacy_env(|k| std::env::var(k).ok())
+        .context("Invalid legacy mapping configuration")?
+    {
+        let mapping = Mapping::from_request(config.to_create_request());
+        info!(
+            "Importing legacy mapping: {} -> {}",
+            mapping.s3_url, mapping.short_url
+        );
+        manager.load_mappings(vec![mapping]).await;
+    }
+
+    // Create the mappings declared in the server config file, skipping any
+    // that fail validation
+    if let Ok(path) = std::env::var("S3_BUDDY_CONFIG") {
+        let config = ServerConfig::from_file(&path)?;
+        info!(
+            "Creating {} mapping(s) from {}",
+            config.mappings.len(),
+            path
+        );
+        for (mapping, options) in config.to_mappings(manager.defaults()) {
+            let short_url = mapping.short_url.clone();
+            if let Err(e) = manager.add_mapping_with(mapping, options).await {
+                warn!("Skipping configured mapping {}: {:#}", short_url, e);
+            }
+        }
+    }
+
     // Create HTTP server
-    let app = s3_buddy::server::create_router(manager);
+    let mut app = s3_buddy::server::create_router(Arc::clone(&manager));
+    if let Ok(origins) = std::env::var("S3_BUDDY_CORS_ORIGINS") {
+        if let Some(cors) = s3_buddy::server::cors_layer(&origins)? {
+            info!("Allowing cross-origin requests from {}", origins);
+            app = app.layer(cors);
+        }
+    }
 
     // Get port from environment or use default
     let port = std::env::var("PORT")
@@ -46,12 +179,96 @@ async fn main() -> Result<()> {
         .and_then(|p| p.parse().ok())
         .unwrap_or(3000);
 
-    let addr = format!("0.0.0.0:{}", port);
-    info!("Server listening on {}", addr);
+    // Terminate HTTPS here when given a certificate, e.g. to run without a
+    // reverse proxy
+    let tls = match (
+        std::env::var("TLS_CERT_PATH"),
+        std::env::var("TLS_KEY_PATH"),
+    ) {
+        (Ok(cert_path), Ok(key_path)) => Some(
+            RustlsConfig::from_pem_file(&cert_path, &key_path)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to load TLS certificate {} and key {}",
+                        cert_path, key_path
+                    )
+                })?,
+        ),
+        (Err(_), Err(_)) => None,
+        _ => anyhow::bail!("TLS_CERT_PATH and TLS_KEY_PATH must be set together"),
+    };
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
 
     // Start server
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    match tls {
+        Some(tls) => {
+            info!("Server listening on {} (HTTPS)", addr);
+            let handle = Handle::new();
+            tokio::spawn({
+                let handle = handle.clone();
+                async move {
+                    shutdown_signal().await;
+                    handle.graceful_shutdown(None);
+                }
+            });
+            axum_server::bind_rustls(addr, tls)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        None => {
+            info!("Server listening on {}", addr);
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown_signal())
+                .await?;
+        }
+    }
+
+    manager.shutdown().await;
 
     Ok(())
 }
+
+/// Durations from comma-separated seconds, skipping unparseable entries
+fn seconds_list(value: &str) -> Vec<Duration> {
+    value
+        .split(',')
+        .filter_map(|s| s.trim().parse().ok())
+        .map(Duration::from_secs)
+        .collect()
+}
+
+/// Resolve on Ctrl+C or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+
+    info!("Shutdown signal received");
+}
@@ -0,0 +1,227 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+use crate::types::{
+    BulkStatusReport, CreateMappingRequest, ListLogsResponse, ListMappingsResponse, ManualRefresh,
+    Mapping, ReconcileReport, RefreshLog, ServerInfo, UpdateMappingRequest, ValidationReport,
+};
+
+/// HTTP client for the server's API, shared by the TUI and the CLI
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    server_url: String,
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn new(server_url: impl Into<String>) -> Self {
+        Self {
+            server_url: server_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn server_url(&self) -> &str {
+        &self.server_url
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.server_url, path)
+    }
+
+    /// Server version and message of the day
+    pub async fn info(&self) -> Result<ServerInfo> {
+        let response = self.http.get(self.url("/info")).send().await?;
+        json(response, "fetch server info").await
+    }
+
+    /// Every mapping on the server
+    pub async fn list_mappings(&self) -> Result<Vec<Mapping>> {
+        let response = self.http.get(self.url("/mappings")).send().await?;
+        let data: ListMappingsResponse = json(response, "fetch mappings").await?;
+        Ok(data.mappings)
+    }
+
+    /// Recent refresh attempts of a mapping
+    pub async fn mapping_logs(&self, id: Uuid) -> Result<Vec<RefreshLog>> {
+        let url = self.url(&format!("/mappings/{}/logs", id));
+        let response = self.http.get(url).send().await?;
+        let data: ListLogsResponse = json(response, "fetch logs").await?;
+        Ok(data.logs)
+    }
+
+    pub async fn create_mapping(&self, request: &CreateMappingRequest) -> Result<Mapping> {
+        let url = self.url("/mappings");
+        let response = self.http.post(url).json(request).send().await?;
+        json(response, "create mapping").await
+    }
+
+    /// Check a prospective mapping's object and presigning without storing it
+    pub async fn validate_mapping(
+        &self,
+        request: &CreateMappingRequest,
+    ) -> Result<ValidationReport> {
+        let url = self.url("/mappings/validate");
+        let response = self.http.post(url).json(request).send().await?;
+        json(response, "validate mapping").await
+    }
+
+    pub async fn update_mapping(
+        &self,
+        id: Uuid,
+        request: &UpdateMappingRequest,
+    ) -> Result<Mapping> {
+        let url = self.url(&format!("/mappings/{}", id));
+        let response = self.http.put(url).json(request).send().await?;
+        json(response, "update mapping").await
+    }
+
+    pub async fn delete_mapping(&self, id: Uuid) -> Result<()> {
+        let url = self.url(&format!("/mappings/{}", id));
+        let response = self.http.delete(url).send().await?;
+        check(response, "delete mapping").await?;
+        Ok(())
+    }
+
+    pub async fn pause_mapping(&self, id: Uuid) -> Result<Mapping> {
+        let url = self.url(&format!("/mappings/{}/pause", id));
+        let response = self.http.post(url).send().await?;
+        json(response, "pause mapping").await
+    }
+
+    pub async fn resume_mapping(&self, id: Uuid) -> Result<Mapping> {
+        let url = self.url(&format!("/mappings/{}/resume", id));
+        let response = self.http.post(url).send().await?;
+        json(response, "resume mapping").await
+    }
+
+    /// Pause every refreshing mapping
+    pub async fn pause_all(&self) -> Result<BulkStatusReport> {
+        let response = self
+            .http
+            .post(self.url("/mappings/pause-all"))
+            .send()
+            .await?;
+        json(response, "pause all mappings").await
+    }
+
+    /// Resume every paused mapping
+    pub async fn resume_all(&self) -> Result<BulkStatusReport> {
+        let response = self
+            .http
+            .post(self.url("/mappings/resume-all"))
+            .send()
+            .await?;
+        json(response, "resume all mappings").await
+    }
+
+    /// Refresh a mapping's presigned URL now, leaving its schedule alone
+    pub async fn refresh_mapping(&self, id: Uuid) -> Result<ManualRefresh> {
+        let url = self.url(&format!("/mappings/{}/refresh", id));
+        let response = self.http.post(url).send().await?;
+        json(response, "refresh mapping").await
+    }
+
+    /// Trigger a Route53 reconciliation pass on the server
+    pub async fn reconcile(&self) -> Result<ReconcileReport> {
+        let response = self.http.post(self.url("/admin/reconcile")).send().await?;
+        json(response, "reconcile").await
+    }
+}
+
+/// Fail with the server's error message, or its status if it sent none
+async fn check(response: reqwest::Response, action: &str) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    if body.trim().is_empty() {
+        anyhow::bail!("Failed to {}: {}", action, status);
+    }
+    anyhow::bail!("Failed to {}: {}", action, body.trim())
+}
+
+async fn json<T: DeserializeOwned>(response: reqwest::Response, action: &str) -> Result<T> {
+    Ok(check(response, action).await?.json().await?)
+}
+
+/// Mappings as a plain-text table, one row per mapping
+pub fn mappings_table(mappings: &[Mapping]) -> String {
+    let header = ["ID", "SHORT URL", "STATUS", "LAST REFRESH", "S3 URL"].map(String::from);
+    let rows: Vec<[String; 5]> = mappings
+        .iter()
+        .map(|m| {
+            [
+                m.id.to_string(),
+                m.short_url.clone(),
+                m.status.to_string(),
+                m.last_refresh
+                    .map_or_else(|| "never".to_string(), format_time),
+                m.s3_url.clone(),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        table.push_str(cells.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mappings_table() {
+        let mut mapping = Mapping::new(
+            "s3://my-bucket/report.pdf".to_string(),
+            "report.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        mapping.last_refresh = Some("2024-05-01T12:00:00Z".parse().unwrap());
+
+        let table = mappings_table(&[mapping.clone()]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("ID"));
+        assert_eq!(
+            lines[1],
+            format!(
+                "{}  report.example.com  Pending  2024-05-01 12:00:00  s3://my-bucket/report.pdf",
+                mapping.id
+            )
+        );
+        // Columns line up
+        assert_eq!(lines[0].find("SHORT URL"), lines[1].find("report."));
+
+        assert_eq!(mappings_table(&[]).lines().count(), 1);
+    }
+
+    #[test]
+    fn test_server_url_trailing_slash() {
+        let client = ApiClient::new("http://localhost:3000/");
+        assert_eq!(client.url("/mappings"), "http://localhost:3000/mappings");
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// Result shared with every caller waiting on the same computation; errors
+/// are kept as their formatted chain since `anyhow::Error` isn't `Clone`
+type Shared<V> = Arc<OnceCell<Result<V, String>>>;
+
+/// Singleflight: concurrent calls with the same key share one in-flight
+/// computation and all receive its result
+///
+/// Nothing is cached; once a computation finishes, the next call with its
+/// key starts a new one.
+pub struct Coalescer<K, V> {
+    in_flight: Mutex<HashMap<K, Shared<V>>>,
+}
+
+impl<K, V> Default for Coalescer<K, V> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Coalescer<K, V> {
+    /// Run `compute` for `key`, or wait for the call already running it
+    ///
+    /// If the caller running the computation is cancelled, a waiting caller
+    /// takes over.
+    pub async fn run<F, Fut>(&self, key: K, compute: F) -> Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V>>,
+    {
+        let shared = Arc::clone(
+            self.in_flight
+                .lock()
+                .unwrap()
+                .entry(key.clone())
+                .or_default(),
+        );
+
+        let result = shared
+            .get_or_init(|| async { compute().await.map_err(|e| format!("{:#}", e)) })
+            .await
+            .clone();
+
+        // Later calls start afresh rather than reusing this result
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &shared))
+        {
+            in_flight.remove(&key);
+        }
+
+        result.map_err(anyhow::Error::msg)
+    }
+
+    /// Number of keys with a computation in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_computation() {
+        let coalescer = Arc::new(Coalescer::<&str, u32>::default());
+        let calls = Arc::new(AtomicU32::new(0));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let coalescer = Arc::clone(&coalescer);
+                let calls = Arc::clone(&calls);
+                tokio::spawn(async move {
+                    coalescer
+                        .run("key", || async move {
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok(calls.fetch_add(1, Ordering::SeqCst) + 1)
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap(), 1);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(coalescer.in_flight(), 0);
+
+        // Once finished, the next call computes again, errors included
+        let err = coalescer
+            .run("key", || async { anyhow::bail!("AccessDenied") })
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "AccessDenied");
+        assert_eq!(coalescer.run("key", || async { Ok(2) }).await.unwrap(), 2);
+    }
+}
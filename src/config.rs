@@ -1,6 +1,10 @@
 use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
 use std::time::Duration;
 
+use crate::types::{AddOptions, CreateMappingRequest, Mapping, MappingDefaults};
+
 /// Configuration for S3 Buddy
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -32,18 +36,138 @@ impl Config {
     }
 
     /// Parse S3 URL into bucket and key
+    ///
+    /// Everything after the bucket is the key, verbatim: keys are opaque, so
+    /// `://`, colons, percent-escapes and repeated slashes are kept as is.
+    /// Only a trailing `?versionId=...` is split off; see [`Config::version_id`].
     pub fn parse_s3_url(&self) -> Result<(String, String)> {
-        let url = self
-            .s3_url
-            .strip_prefix("s3://")
-            .context("Invalid S3 URL format")?;
-
-        let parts: Vec<&str> = url.splitn(2, '/').collect();
-        if parts.len() != 2 {
-            anyhow::bail!("S3 URL must include bucket and key: s3://bucket/key");
+        let (url, _) = split_version_id(&self.s3_url);
+        let url = url.strip_prefix("s3://").context("Invalid S3 URL format")?;
+
+        match url.split_once('/') {
+            Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => {
+                Ok((bucket.to_string(), key.to_string()))
+            }
+            _ => anyhow::bail!("S3 URL must include bucket and key: s3://bucket/key"),
         }
+    }
+
+    /// Object version pinned with a `?versionId=...` suffix on the S3 URL;
+    /// `None` for the latest version
+    pub fn version_id(&self) -> Option<String> {
+        split_version_id(&self.s3_url).1.map(str::to_string)
+    }
+
+    /// Load a legacy single-mapping configuration from environment variables
+    ///
+    /// Reads `LEGACY_S3_URL`, `LEGACY_SHORT_URL` and `LEGACY_HOSTED_ZONE_ID`
+    /// through `lookup`. Returns `None` if none of them are set.
+    pub fn from_legacy_env(lookup: impl Fn(&str) -> Option<String>) -> Result<Option<Self>> {
+        let s3_url = lookup("LEGACY_S3_URL");
+        let short_url = lookup("LEGACY_SHORT_URL");
+        let hosted_zone_id = lookup("LEGACY_HOSTED_ZONE_ID");
 
-        Ok((parts[0].to_string(), parts[1].to_string()))
+        match (s3_url, short_url, hosted_zone_id) {
+            (None, None, None) => Ok(None),
+            (Some(s3_url), Some(short_url), Some(hosted_zone_id)) => {
+                Self::new(s3_url, short_url, hosted_zone_id).map(Some)
+            }
+            _ => anyhow::bail!(
+                "LEGACY_S3_URL, LEGACY_SHORT_URL and LEGACY_HOSTED_ZONE_ID must all be set"
+            ),
+        }
+    }
+
+    /// Convert into a server mapping request, keeping this config's durations
+    pub fn to_create_request(&self) -> CreateMappingRequest {
+        CreateMappingRequest {
+            s3_url: self.s3_url.clone(),
+            short_url: self.short_url.clone(),
+            hosted_zone_id: self.hosted_zone_id.clone(),
+            slug: None,
+            presign_duration_secs: Some(self.presign_duration.as_secs()),
+            refresh_interval_secs: Some(self.refresh_interval.as_secs()),
+            max_presign_duration_secs: None,
+            credentials_profile: None,
+            requester_pays: false,
+            use_accelerate: false,
+            extra_query_params: Default::default(),
+            access_point_arn: None,
+            addressing_style: Default::default(),
+            manage_dns: true,
+            operation: Default::default(),
+            dns_record_type: Default::default(),
+            dns_ttl_secs: None,
+            allowed_window: None,
+            force_content_type: None,
+            download_filename: None,
+            fallback_s3_urls: Vec::new(),
+            initial_refresh_delay_secs: None,
+            skip_validation: false,
+            notes: None,
+        }
+    }
+}
+
+/// Mappings to create when the server starts, declared in a TOML file:
+///
+/// ```toml
+/// [[mappings]]
+/// s3_url = "s3://my-bucket/report.pdf"
+/// short_url = "report.example.com"
+/// hosted_zone_id = "Z1234567890ABC"
+/// presign_duration_secs = 43200
+/// refresh_interval_secs = 39600
+/// ```
+///
+/// Each entry takes the same fields as a create request.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    #[serde(default)]
+    pub mappings: Vec<CreateMappingRequest>,
+}
+
+impl ServerConfig {
+    /// Parse a TOML server config
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        toml::from_str(contents).context("Invalid server config")
+    }
+
+    /// Read and parse a TOML server config file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read server config {}", path.display()))?;
+        Self::from_toml(&contents)
+    }
+
+    /// The configured mappings, with omitted durations taken from `defaults`,
+    /// and how to add each
+    pub fn to_mappings(&self, defaults: &MappingDefaults) -> Vec<(Mapping, AddOptions)> {
+        self.mappings
+            .iter()
+            .map(|req| {
+                (
+                    Mapping::from_request_with(req.clone(), defaults),
+                    req.add_options(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Split an S3 URL ending in `?versionId=<id>` into the URL without it and
+/// the version ID
+///
+/// Keys may themselves contain `?`, so only a non-empty version ID with no
+/// `/`, `?`, `&` or `=` at the very end counts.
+pub fn split_version_id(s3_url: &str) -> (&str, Option<&str>) {
+    match s3_url.rsplit_once("?versionId=") {
+        Some((url, version)) if !version.is_empty() && !version.contains(['/', '?', '&', '=']) => {
+            (url, Some(version))
+        }
+        _ => (s3_url, None),
     }
 }
 
@@ -65,6 +189,87 @@ mod tests {
         assert_eq!(key, "path/to/file.txt");
     }
 
+    #[test]
+    fn test_parse_s3_url_with_version_id() {
+        let config = |s3_url: &str| {
+            Config::new(
+                s3_url.to_string(),
+                "short.example.com".to_string(),
+                "Z1234567890ABC".to_string(),
+            )
+            .unwrap()
+        };
+
+        let pinned =
+            config("s3://my-bucket/path/to/file.txt?versionId=3HL4kqtJlcpXroDTDmJ.rmSpXd3dIbrHY");
+        let (bucket, key) = pinned.parse_s3_url().unwrap();
+        assert_eq!(bucket, "my-bucket");
+        assert_eq!(key, "path/to/file.txt");
+        assert_eq!(
+            pinned.version_id().as_deref(),
+            Some("3HL4kqtJlcpXroDTDmJ.rmSpXd3dIbrHY")
+        );
+
+        let latest = config("s3://my-bucket/path/to/file.txt");
+        assert_eq!(latest.parse_s3_url().unwrap().1, "path/to/file.txt");
+        assert_eq!(latest.version_id(), None);
+
+        // A `?` that isn't a version suffix is part of the key
+        for s3_url in [
+            "s3://my-bucket/search?q=1",
+            "s3://my-bucket/file.txt?versionId=",
+            "s3://my-bucket/file.txt?versionId=a/b",
+        ] {
+            let config = config(s3_url);
+            assert_eq!(config.version_id(), None, "{}", s3_url);
+            assert_eq!(
+                config.parse_s3_url().unwrap().1,
+                s3_url.strip_prefix("s3://my-bucket/").unwrap()
+            );
+        }
+
+        // A version on its own names no key
+        assert!(config("s3://my-bucket?versionId=abc")
+            .parse_s3_url()
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_s3_url_keeps_key_verbatim() {
+        let parse = |s3_url: &str| {
+            Config::new(
+                s3_url.to_string(),
+                "short.example.com".to_string(),
+                "Z1234567890ABC".to_string(),
+            )
+            .unwrap()
+            .parse_s3_url()
+        };
+
+        let cases = [
+            (
+                "s3://my-bucket/redirects/https://example.com",
+                "redirects/https://example.com",
+            ),
+            ("s3://my-bucket/a%2Fb/c.txt", "a%2Fb/c.txt"),
+            (
+                "s3://my-bucket/reports/2024-01-01T00:00:00Z.csv",
+                "reports/2024-01-01T00:00:00Z.csv",
+            ),
+            ("s3://my-bucket/a//b/../c", "a//b/../c"),
+        ];
+        for (s3_url, expected) in cases {
+            let (bucket, key) = parse(s3_url).unwrap();
+            assert_eq!(bucket, "my-bucket");
+            assert_eq!(key, expected);
+        }
+
+        // Bucket and key are both required
+        assert!(parse("s3://my-bucket").is_err());
+        assert!(parse("s3://my-bucket/").is_err());
+        assert!(parse("s3:///file.pdf").is_err());
+    }
+
     #[test]
     fn test_invalid_s3_url() {
         let result = Config::new(
@@ -74,4 +279,79 @@ mod tests {
         );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_legacy_env_to_create_request() {
+        let vars = std::collections::HashMap::from([
+            ("LEGACY_S3_URL", "s3://my-bucket/file.pdf"),
+            ("LEGACY_SHORT_URL", "short.example.com"),
+            ("LEGACY_HOSTED_ZONE_ID", "Z1234567890ABC"),
+        ]);
+        let config = Config::from_legacy_env(|k| vars.get(k).map(|v| v.to_string()))
+            .unwrap()
+            .unwrap();
+
+        let req = config.to_create_request();
+        assert_eq!(req.s3_url, "s3://my-bucket/file.pdf");
+        assert_eq!(req.short_url, "short.example.com");
+        assert_eq!(req.hosted_zone_id, "Z1234567890ABC");
+        assert_eq!(req.presign_duration_secs, Some(12 * 60 * 60));
+        assert_eq!(req.refresh_interval_secs, Some(11 * 60 * 60));
+    }
+
+    #[test]
+    fn test_legacy_env_unset_or_partial() {
+        assert!(Config::from_legacy_env(|_| None).unwrap().is_none());
+
+        let partial = Config::from_legacy_env(|k| {
+            (k == "LEGACY_S3_URL").then(|| "s3://my-bucket/file.pdf".to_string())
+        });
+        assert!(partial.is_err());
+    }
+
+    #[test]
+    fn test_server_config_to_mappings() {
+        let config = ServerConfig::from_toml(
+            r#"
+            [[mappings]]
+            s3_url = "s3://my-bucket/report.pdf"
+            short_url = "report.example.com"
+            hosted_zone_id = "Z1234567890ABC"
+            presign_duration_secs = 7200
+            refresh_interval_secs = 3600
+
+            [[mappings]]
+            s3_url = "s3://my-bucket/logo.png"
+            short_url = "logo.example.com"
+            hosted_zone_id = "Z1234567890ABC"
+            skip_validation = true
+            "#,
+        )
+        .unwrap();
+
+        let defaults = MappingDefaults {
+            presign_duration_secs: 86_400,
+            refresh_interval_secs: None,
+        };
+        let mappings = config.to_mappings(&defaults);
+        assert_eq!(mappings.len(), 2);
+
+        let (report, options) = &mappings[0];
+        assert_eq!(report.s3_url, "s3://my-bucket/report.pdf");
+        assert_eq!(report.short_url, "report.example.com");
+        assert_eq!(report.hosted_zone_id, "Z1234567890ABC");
+        assert_eq!(report.presign_duration_secs, 7200);
+        assert_eq!(report.refresh_interval_secs, 3600);
+        assert!(!options.skip_validation);
+
+        // Omitted durations come from the server defaults
+        let (logo, options) = &mappings[1];
+        assert_eq!(logo.presign_duration_secs, 86_400);
+        assert_eq!(logo.refresh_interval_secs, 77_760);
+        assert!(options.skip_validation);
+
+        assert!(ServerConfig::from_toml("").unwrap().mappings.is_empty());
+        assert!(ServerConfig::from_toml("[[mappings]]\ns3_url = \"s3://b/k\"").is_err());
+        assert!(ServerConfig::from_toml("mapings = []").is_err());
+    }
 }
@@ -1,5 +1,15 @@
+pub mod audit;
+pub mod client;
+pub mod coalesce;
 pub mod config;
+pub mod logging;
+pub mod logs;
 pub mod manager;
+pub mod metrics;
+pub mod notify;
+pub mod pagination;
+pub mod registry;
+pub mod retry;
 pub mod route53;
 pub mod s3;
 pub mod scheduler;
@@ -9,7 +19,8 @@ pub mod types;
 
 pub use config::Config;
 pub use manager::MappingManager;
-pub use route53::Route53Client;
-pub use s3::S3Client;
+pub use registry::ClientRegistry;
+pub use route53::{DnsBackend, Route53Client};
+pub use s3::{S3Client, StorageBackend};
 pub use scheduler::Scheduler;
 pub use types::*;
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use uuid::Uuid;
+
+use crate::metrics::{RefreshCounters, RefreshLatencies};
+use crate::notify::Notifiers;
+use crate::pagination::{page_before, parse_cursor, Cursor};
+use crate::types::{ListLogsResponse, LogQuery, RefreshLog};
+
+/// Number of log entries retained per mapping
+pub const MAX_LOGS_PER_MAPPING: usize = 100;
+
+/// Maximum number of entries returned by a single query
+pub const MAX_LOGS_RETURNED: usize = 1000;
+
+/// Per-mapping ring buffers of refresh logs
+///
+/// Every recorded entry is also forwarded to the log channel and counted.
+#[derive(Clone)]
+pub struct LogStore {
+    buffers: Arc<RwLock<HashMap<Uuid, VecDeque<RefreshLog>>>>,
+    log_tx: mpsc::UnboundedSender<RefreshLog>,
+    counters: RefreshCounters,
+    latencies: RefreshLatencies,
+    notifiers: Notifiers,
+}
+
+impl LogStore {
+    pub fn new(log_tx: mpsc::UnboundedSender<RefreshLog>) -> Self {
+        Self {
+            buffers: Arc::new(RwLock::new(HashMap::new())),
+            log_tx,
+            counters: RefreshCounters::default(),
+            latencies: RefreshLatencies::default(),
+            notifiers: Notifiers::default(),
+        }
+    }
+
+    /// Send status transition alerts to `notifiers`
+    pub fn with_notifiers(mut self, notifiers: Notifiers) -> Self {
+        self.notifiers = notifiers;
+        self
+    }
+
+    /// Alert destinations for status transitions
+    pub fn notifiers(&self) -> &Notifiers {
+        &self.notifiers
+    }
+
+    /// Refresh outcome counters fed by `record`
+    pub fn counters(&self) -> RefreshCounters {
+        self.counters.clone()
+    }
+
+    /// Refresh durations, recorded alongside each refresh's log entry
+    pub fn latencies(&self) -> &RefreshLatencies {
+        &self.latencies
+    }
+
+    /// Record a log entry, evicting the oldest once the mapping's buffer is full
+    pub async fn record(&self, log: RefreshLog) {
+        {
+            let mut buffers = self.buffers.write().await;
+            let buffer = buffers.entry(log.mapping_id).or_default();
+            if buffer.len() == MAX_LOGS_PER_MAPPING {
+                buffer.pop_front();
+            }
+            buffer.push_back(log.clone());
+        }
+
+        self.counters.record(log.success);
+        let _ = self.log_tx.send(log);
+    }
+
+    /// Drop all entries and latencies for a mapping
+    pub async fn remove(&self, id: &Uuid) {
+        self.buffers.write().await.remove(id);
+        self.latencies.remove(id);
+    }
+
+    /// Retained entries for one mapping, oldest first
+    pub async fn for_mapping(&self, id: &Uuid) -> Vec<RefreshLog> {
+        self.buffers
+            .read()
+            .await
+            .get(id)
+            .map(|buffer| buffer.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Most recent entries across all mappings matching the query, oldest first
+    ///
+    /// Entries before the query's cursor are returned when one is given.
+    pub async fn query(&self, query: &LogQuery) -> Result<ListLogsResponse> {
+        let before = parse_cursor(query.cursor.as_deref())?;
+
+        let buffers = self.buffers.read().await;
+        let logs: Vec<RefreshLog> = buffers
+            .values()
+            .flatten()
+            .filter(|log| query.since.is_none_or(|since| log.timestamp >= since))
+            .filter(|log| query.success.is_none_or(|success| log.success == success))
+            .cloned()
+            .collect();
+
+        let limit = query
+            .limit
+            .unwrap_or(MAX_LOGS_RETURNED)
+            .clamp(1, MAX_LOGS_RETURNED);
+        let (logs, next_cursor) = page_before(
+            logs,
+            |log| Cursor::new(log.timestamp, log.mapping_id),
+            before,
+            limit,
+        );
+
+        Ok(ListLogsResponse { logs, next_cursor })
+    }
+}
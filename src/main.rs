@@ -2,16 +2,11 @@ use anyhow::{Context, Result};
 use s3_buddy::{Config, Route53Client, S3Client, Scheduler};
 use std::env;
 use tracing::info;
-use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
-        )
-        .init();
+    // Initialize logging, as JSON with LOG_FORMAT=json
+    s3_buddy::logging::init_from_env()?;
 
     info!("Starting S3 Buddy");
 
@@ -36,8 +31,8 @@ async fn main() -> Result<()> {
 
     info!("Configuration loaded: {:?}", config);
 
-    // Load AWS configuration from environment/credentials
-    let aws_config = aws_config::load_from_env().await;
+    // Load AWS configuration, honoring AWS_PROFILE and AWS_REGION
+    let aws_config = s3_buddy::registry::load_aws_config(None).await;
 
     // Create AWS clients
     let s3_client = S3Client::new(aws_sdk_s3::Client::new(&aws_config));
@@ -47,7 +42,10 @@ async fn main() -> Result<()> {
     let scheduler = Scheduler::new(s3_client, route53_client, config);
 
     info!("Starting URL refresh scheduler");
-    scheduler.run().await?;
+    // Boxed so this future doesn't inline the SDK's deeply nested ones; with
+    // axum-server's TLS features compiled in, its layout overflows rustc's
+    // query depth limit otherwise
+    Box::pin(scheduler.run()).await?;
 
     Ok(())
 }
@@ -1,298 +1,2867 @@
 use anyhow::{Context, Result};
-use chrono::Utc;
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex, RwLock, Semaphore};
 use tokio::task::JoinHandle;
-use tokio::time::interval;
-use tracing::{error, info, instrument};
+use tokio::time::{interval_at, Instant};
+use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
 
-use crate::config::Config;
-use crate::route53::Route53Client;
-use crate::s3::S3Client;
-use crate::types::{Mapping, MappingStatus, RefreshLog};
+use crate::audit::AuditLog;
+use crate::coalesce::Coalescer;
+use crate::logs::LogStore;
+use crate::metrics::RefreshCounters;
+use crate::notify::{Notification, Notifiers, Transition};
+use crate::pagination::{page_after, parse_cursor, Cursor};
+use crate::registry::{ClientRegistry, ClientSet};
+use crate::retry::{jittered, TransientError};
+use crate::route53::{
+    expected_target, normalize_hosted_zone_id, points_to, reconcile_record, DnsBackend,
+    ZoneLookupFailed,
+};
+use crate::s3::{
+    attachment_disposition, credentials_warning, ObjectNotFound, PresignMethod, PresignOptions,
+    StorageBackend,
+};
+use crate::types::{
+    derive_slug, normalize_short_url, validate_short_url, validate_slug, AccessCheck, AddOptions,
+    AuditAction, AuditEntry, AuditQuery, AwsReadiness, BulkDurationsRequest, BulkStatusReport,
+    BulkUpdateQuery, BulkUpdateReport, DeletedMapping, DnsStatus, EffectiveConfig, ExportedMapping,
+    GcReport, GroupedMappings, ImportMode, ImportReport, ImportResult, LatencyStats, ListFilter,
+    ListLogsResponse, ListMappingsResponse, LoadReport, LogQuery, ManualRefresh, Mapping,
+    MappingDefaults, MappingExport, MappingHealth, MappingStatus, MappingSummary, MappingUptime,
+    Operation, PresignedUrl, Readiness, ReconcileReport, RefreshEvent, RefreshLog, ServerInfo,
+    Stats, UpdateMappingRequest, ValidationReport, ZoneUsage, MAX_SLUG_LENGTH,
+};
 
 /// Manages multiple URL mappings and their refresh schedulers
 pub struct MappingManager {
     mappings: Arc<RwLock<HashMap<Uuid, Mapping>>>,
+    /// Mapping IDs by slug; only changed while holding the `mappings` write
+    /// lock, and locked after it
+    slugs: Arc<RwLock<HashMap<String, Uuid>>>,
     tasks: Arc<RwLock<HashMap<Uuid, JoinHandle<()>>>>,
-    s3_client: Arc<S3Client>,
-    route53_client: Arc<Route53Client>,
-    log_tx: mpsc::UnboundedSender<RefreshLog>,
+    clients: Arc<RwLock<Arc<ClientRegistry>>>,
+    logs: LogStore,
+    /// How long after creation failures leave a never-refreshed mapping `Pending`
+    grace_period: Duration,
+    /// Mappings `pause_all` paused and nothing has resumed or paused on
+    /// its own since; the ones `resume_all` resumes
+    bulk_paused: Arc<RwLock<HashSet<Uuid>>>,
+    /// Deleted mappings, restorable until purged
+    deleted: Arc<RwLock<HashMap<Uuid, DeletedMapping>>>,
+    /// How long deleted mappings are kept before being purged
+    recycle_ttl: Duration,
+    /// Durations applied when create requests omit them
+    defaults: MappingDefaults,
+    /// Trail of changes made to mappings
+    audit: AuditLog,
+    /// Message of the day shown by clients
+    motd: Option<String>,
+    /// Per-mapping locks serializing scheduled and manual refreshes
+    refresh_gates: Arc<RwLock<HashMap<Uuid, RefreshGate>>>,
+    /// Manual refreshes this soon after the last refresh are skipped
+    refresh_debounce: Duration,
+    /// Delays between retries of a task's first refresh until it succeeds
+    initial_retry_backoff: Vec<Duration>,
+    /// Delays between retries of later failed refreshes, the last repeating
+    failure_retry_backoff: Vec<Duration>,
+    /// Bucket S3 access is probed with; buckets are listed if `None`
+    probe_bucket: Option<String>,
+    /// Latest AWS access probe and when it finished; held while probing so
+    /// concurrent checks share one probe
+    aws_readiness: Arc<Mutex<Option<(Instant, AwsReadiness)>>>,
+    /// Longest random delay before an added mapping's first refresh; zero
+    /// refreshes immediately
+    initial_refresh_delay: Duration,
+    /// On-demand presigns in flight, shared by concurrent identical requests;
+    /// `PresignBusy` is shared too, so everyone waiting on a key gets it
+    presigns: Coalescer<PresignKey, Result<String, PresignBusy>>,
+    /// Permits for on-demand presigns running at once
+    presign_permits: Arc<Semaphore>,
+    /// How long an on-demand presign waits for a permit before `PresignBusy`
+    presign_wait: Duration,
 }
 
+/// What makes two on-demand presigns identical: mapping, method, lifetime and
+/// `Content-Disposition`
+type PresignKey = (Uuid, PresignMethod, Duration, Option<String>);
+
+/// Lock held while a mapping refreshes, recording when its last refresh finished
+type RefreshGate = Arc<tokio::sync::Mutex<Option<Instant>>>;
+
+/// Default window in which repeated manual refreshes are skipped
+pub const DEFAULT_REFRESH_DEBOUNCE: Duration = Duration::from_secs(10);
+
+/// Default delays between retries of a failed first refresh; once they run
+/// out, retries wait the full refresh interval
+pub const DEFAULT_INITIAL_RETRY_BACKOFF: [Duration; 3] = [
+    Duration::from_secs(10),
+    Duration::from_secs(30),
+    Duration::from_secs(60),
+];
+
+/// Default delays between retries of a refresh failing after an earlier
+/// success; the last repeats until a refresh succeeds again
+pub const DEFAULT_FAILURE_RETRY_BACKOFF: [Duration; 3] = [
+    Duration::from_secs(30),
+    Duration::from_secs(60),
+    Duration::from_secs(120),
+];
+
+/// Default limit on on-demand presigns running at once
+pub const DEFAULT_PRESIGN_CONCURRENCY: usize = 64;
+
+/// Default wait for an on-demand presign permit before giving up
+pub const DEFAULT_PRESIGN_WAIT: Duration = Duration::from_secs(1);
+
+/// Default retention for deleted mappings (24 hours)
+pub const DEFAULT_RECYCLE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long each AWS access probe of `/health/aws` may take before failing
+pub const ACCESS_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long an AWS access probe result is reused before probing again
+pub const AWS_READINESS_TTL: Duration = Duration::from_secs(5);
+
+/// Longest an override may last (30 days)
+pub const MAX_OVERRIDE_DURATION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Floor on a refresh task's interval, so a zero interval that slipped past
+/// validation can't panic the timer or spin
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Another mapping already uses the short URL
+#[derive(Debug)]
+pub struct ShortUrlTaken {
+    pub short_url: String,
+    /// The mapping using it
+    pub id: Uuid,
+}
+
+impl std::fmt::Display for ShortUrlTaken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Short URL {} is already used by mapping {}",
+            self.short_url, self.id
+        )
+    }
+}
+
+impl std::error::Error for ShortUrlTaken {}
+
+/// Another mapping already uses the slug
+#[derive(Debug)]
+pub struct SlugTaken {
+    pub slug: String,
+    /// The mapping using it
+    pub id: Uuid,
+}
+
+impl std::fmt::Display for SlugTaken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Slug {} is already used by mapping {}",
+            self.slug, self.id
+        )
+    }
+}
+
+impl std::error::Error for SlugTaken {}
+
+/// Every on-demand presign permit stayed taken for the whole wait
+#[derive(Debug, Clone)]
+pub struct PresignBusy;
+
+impl std::fmt::Display for PresignBusy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Too many presigns in flight; try again shortly")
+    }
+}
+
+impl std::error::Error for PresignBusy {}
+
+/// An AWS check of a new mapping couldn't be completed, e.g. because S3
+/// denied access, as opposed to finding the mapping invalid
+#[derive(Debug)]
+pub struct AwsCheckFailed {
+    /// Whether trying again later may succeed, e.g. after throttling
+    pub transient: bool,
+}
+
+impl std::fmt::Display for AwsCheckFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Could not check the mapping with AWS; set skip_validation to create it anyway"
+        )
+    }
+}
+
+impl std::error::Error for AwsCheckFailed {}
+
 impl MappingManager {
     pub fn new(
-        s3_client: S3Client,
-        route53_client: Route53Client,
+        s3_client: impl StorageBackend + 'static,
+        route53_client: impl DnsBackend + 'static,
     ) -> (Self, mpsc::UnboundedReceiver<RefreshLog>) {
+        Self::with_registry(ClientRegistry::new(s3_client, route53_client))
+    }
+
+    /// Create a manager that selects AWS clients per mapping from a registry
+    pub fn with_registry(clients: ClientRegistry) -> (Self, mpsc::UnboundedReceiver<RefreshLog>) {
         let (log_tx, log_rx) = mpsc::unbounded_channel();
 
         (
             Self {
                 mappings: Arc::new(RwLock::new(HashMap::new())),
+                slugs: Arc::new(RwLock::new(HashMap::new())),
                 tasks: Arc::new(RwLock::new(HashMap::new())),
-                s3_client: Arc::new(s3_client),
-                route53_client: Arc::new(route53_client),
-                log_tx,
+                clients: Arc::new(RwLock::new(Arc::new(clients))),
+                logs: LogStore::new(log_tx),
+                grace_period: Duration::ZERO,
+                bulk_paused: Arc::new(RwLock::new(HashSet::new())),
+                deleted: Arc::new(RwLock::new(HashMap::new())),
+                recycle_ttl: DEFAULT_RECYCLE_TTL,
+                defaults: MappingDefaults::default(),
+                audit: AuditLog::in_memory(),
+                motd: None,
+                refresh_gates: Arc::new(RwLock::new(HashMap::new())),
+                refresh_debounce: DEFAULT_REFRESH_DEBOUNCE,
+                initial_retry_backoff: DEFAULT_INITIAL_RETRY_BACKOFF.to_vec(),
+                failure_retry_backoff: DEFAULT_FAILURE_RETRY_BACKOFF.to_vec(),
+                initial_refresh_delay: Duration::ZERO,
+                probe_bucket: None,
+                aws_readiness: Arc::new(Mutex::new(None)),
+                presigns: Coalescer::default(),
+                presign_permits: Arc::new(Semaphore::new(DEFAULT_PRESIGN_CONCURRENCY)),
+                presign_wait: DEFAULT_PRESIGN_WAIT,
             },
             log_rx,
         )
     }
 
+    /// Keep new mappings `Pending` rather than `Error` while their first
+    /// refreshes fail within `grace_period` of creation
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Keep deleted mappings restorable for `ttl` before purging them
+    pub fn with_recycle_ttl(mut self, ttl: Duration) -> Self {
+        self.recycle_ttl = ttl;
+        self
+    }
+
+    /// Set the durations applied when create requests omit them
+    pub fn with_defaults(mut self, defaults: MappingDefaults) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    /// Durations applied when create requests omit them
+    pub fn defaults(&self) -> &MappingDefaults {
+        &self.defaults
+    }
+
+    /// Record changes to mappings in the given audit log
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Skip manual refreshes requested within `debounce` of the last refresh
+    pub fn with_refresh_debounce(mut self, debounce: Duration) -> Self {
+        self.refresh_debounce = debounce;
+        self
+    }
+
+    /// Retry a failed first refresh after each of `backoff` in turn, instead
+    /// of waiting a full refresh interval, until it succeeds
+    pub fn with_initial_retry_backoff(mut self, backoff: Vec<Duration>) -> Self {
+        self.initial_retry_backoff = backoff;
+        self
+    }
+
+    /// Once a mapping has refreshed successfully, retry a failed refresh after
+    /// each of `backoff` in turn, repeating the last, instead of waiting a
+    /// full refresh interval; an empty list disables this
+    pub fn with_failure_retry_backoff(mut self, backoff: Vec<Duration>) -> Self {
+        self.failure_retry_backoff = backoff;
+        self
+    }
+
+    /// Spread the first refreshes of added mappings over up to `max_delay`
+    /// instead of refreshing each immediately
+    pub fn with_initial_refresh_delay(mut self, max_delay: Duration) -> Self {
+        self.initial_refresh_delay = max_delay;
+        self
+    }
+
+    /// Alert `notifiers` when a mapping fails or recovers
+    pub fn with_notifiers(mut self, notifiers: Notifiers) -> Self {
+        self.logs = self.logs.with_notifiers(notifiers);
+        self
+    }
+
+    /// Set the message of the day shown by clients
+    pub fn with_motd(mut self, motd: Option<String>) -> Self {
+        self.motd = motd;
+        self
+    }
+
+    /// Run at most `limit` on-demand presigns at once; a presign waiting
+    /// longer than `wait` for its turn fails with `PresignBusy`
+    pub fn with_presign_limit(mut self, limit: usize, wait: Duration) -> Self {
+        self.presign_permits = Arc::new(Semaphore::new(limit));
+        self.presign_wait = wait;
+        self
+    }
+
+    /// Probe S3 access with `HeadBucket` on `bucket` instead of listing
+    /// buckets, for credentials without `s3:ListAllMyBuckets`
+    pub fn with_probe_bucket(mut self, bucket: Option<String>) -> Self {
+        self.probe_bucket = bucket;
+        self
+    }
+
+    /// Server details shown to clients
+    pub fn info(&self) -> ServerInfo {
+        ServerInfo {
+            motd: self.motd.clone(),
+        }
+    }
+
+    /// Audit trail entries matching the query, oldest first
+    pub async fn audit(&self, query: &AuditQuery) -> Vec<AuditEntry> {
+        self.audit.query(query).await
+    }
+
+    /// Append a change to the audit trail
+    async fn record_audit(
+        &self,
+        action: AuditAction,
+        mapping_id: Uuid,
+        before: Option<MappingStatus>,
+        after: Option<MappingStatus>,
+    ) {
+        self.audit
+            .record(AuditEntry {
+                timestamp: Utc::now(),
+                action,
+                mapping_id,
+                actor: None,
+                before,
+                after,
+            })
+            .await;
+    }
+
     /// Add a new mapping and start its refresh scheduler
+    pub async fn add_mapping(&self, mapping: Mapping) -> Result<Uuid> {
+        self.add_mapping_with(mapping, AddOptions::default()).await
+    }
+
+    /// Add a new mapping, rejecting it if its S3 object doesn't exist unless
+    /// `options.skip_validation` is set
+    ///
+    /// The first refresh is delayed by a random duration of up to
+    /// `options.initial_delay` (the server-wide setting if `None`); a delayed
+    /// mapping stays `Pending` until then. With `options.paused` the mapping
+    /// is added `Paused` and not refreshed until resumed.
     #[instrument(skip(self))]
-    pub async fn add_mapping(&self, mut mapping: Mapping) -> Result<Uuid> {
+    pub async fn add_mapping_with(
+        &self,
+        mut mapping: Mapping,
+        options: AddOptions,
+    ) -> Result<Uuid> {
         let id = mapping.id;
         info!(
             "Adding mapping: {} -> {}",
             mapping.s3_url, mapping.short_url
         );
 
-        // Validate S3 URL format
-        if !mapping.s3_url.starts_with("s3://") {
-            anyhow::bail!("S3 URL must start with s3://");
+        mapping.hosted_zone_id = normalize_hosted_zone_id(&mapping.hosted_zone_id)?;
+        mapping.short_url = normalize_short_url(&mapping.short_url)?;
+        if mapping.clamp_presign_duration() {
+            warn!(
+                "Presign duration of {} capped at the AWS maximum of {}s",
+                mapping.short_url, mapping.presign_duration_secs
+            );
+        }
+        self.check_mapping(&mapping).await?;
+        if let Some(delay) = options.initial_delay {
+            mapping.validate_initial_delay(delay)?;
+        }
+        if !options.skip_validation {
+            self.check_aws(&mapping).await?;
         }
 
-        mapping.status = MappingStatus::Active;
+        // Random, so bulk-added mappings don't all refresh at once; the
+        // server-wide delay is capped like a requested one
+        let max_delay = options
+            .initial_delay
+            .unwrap_or(self.initial_refresh_delay)
+            .min(mapping.refresh_interval());
+        let delay = jittered(max_delay);
+        if options.paused {
+            mapping.set_status(MappingStatus::Paused);
+        } else if delay.is_zero() {
+            mapping.set_status(MappingStatus::Active);
+        } else {
+            mapping.set_status(MappingStatus::Pending);
+            mapping.next_refresh = chrono::Duration::from_std(delay)
+                .ok()
+                .map(|delay| Utc::now() + delay);
+        }
         mapping.updated_at = Utc::now();
+        let status = mapping.status;
 
-        // Store the mapping
+        // Store the mapping, checked under the lock so concurrent creates
+        // can't both claim the short URL or slug
         {
             let mut mappings = self.mappings.write().await;
+            let mut slugs = self.slugs.write().await;
+            check_short_url_free(&mappings, &mapping.short_url, id)?;
+            if mapping.slug.is_empty() {
+                mapping.slug = unique_slug(&slugs, &mapping.short_url, id);
+            } else {
+                check_slug_free(&slugs, &mapping.slug, id)?;
+            }
+            slugs.insert(mapping.slug.clone(), id);
             mappings.insert(id, mapping.clone());
         }
 
         // Start the refresh task - if this fails, remove the mapping
-        if let Err(e) = self.start_refresh_task(mapping).await {
-            let mut mappings = self.mappings.write().await;
-            mappings.remove(&id);
-            return Err(e);
+        if status.is_scheduled() {
+            let slug = mapping.slug.clone();
+            if let Err(e) = self.start_refresh_task_after(mapping, delay).await {
+                let mut mappings = self.mappings.write().await;
+                mappings.remove(&id);
+                self.slugs.write().await.remove(&slug);
+                return Err(e);
+            }
         }
 
+        self.record_audit(AuditAction::Create, id, None, Some(status))
+            .await;
+
         Ok(id)
     }
 
-    /// Get a mapping by ID
-    pub async fn get_mapping(&self, id: &Uuid) -> Option<Mapping> {
-        let mappings = self.mappings.read().await;
-        mappings.get(id).cloned()
+    /// Every mapping's configuration, sorted by short URL
+    pub async fn export_all(&self) -> MappingExport {
+        let mut mappings: Vec<ExportedMapping> = self
+            .mappings
+            .read()
+            .await
+            .values()
+            .map(|m| ExportedMapping {
+                request: m.to_create_request(),
+                paused: m.status == MappingStatus::Paused,
+            })
+            .collect();
+        mappings.sort_by(|a, b| a.request.short_url.cmp(&b.request.short_url));
+        MappingExport {
+            exported_at: Utc::now(),
+            mappings,
+        }
     }
 
-    /// List all mappings
-    pub async fn list_mappings(&self) -> Vec<Mapping> {
-        let mappings = self.mappings.read().await;
-        mappings.values().cloned().collect()
-    }
+    /// Create the mappings of an export
+    ///
+    /// Every entry is validated first, including that its short URL and slug
+    /// are unique and, unless the entry sets `skip_validation`, its S3 object
+    /// and hosted zone; if any fails, nothing is changed. Otherwise a
+    /// `Replace` import deletes the existing mappings, and each entry is
+    /// added in turn.
+    #[instrument(skip(self, export))]
+    pub async fn import_all(&self, export: MappingExport, mode: ImportMode) -> ImportReport {
+        let taken = match mode {
+            ImportMode::Merge => Claims::of(self.mappings.read().await.values()),
+            ImportMode::Replace => Claims::default(),
+        };
 
-    /// Update a mapping
-    #[instrument(skip(self))]
-    pub async fn update_mapping(&self, id: &Uuid, updates: Mapping) -> Result<()> {
-        info!("Updating mapping {}", id);
+        let mut seen = Claims::default();
+        let mut entries = Vec::new();
+        let mut results = Vec::new();
+        for entry in export.mappings {
+            let mut options = AddOptions {
+                paused: entry.paused,
+                ..entry.request.add_options()
+            };
+            let mut mapping = Mapping::from_request_with(entry.request, &self.defaults);
+            let checked = self
+                .check_import_entry(&mut mapping, &options, &taken, &mut seen)
+                .await;
+            // Checked above, so not again when adding
+            options.skip_validation = true;
+            results.push(ImportResult {
+                short_url: mapping.short_url.clone(),
+                id: None,
+                error: checked.err().map(|e| format!("{:#}", e)),
+            });
+            entries.push((mapping, options));
+        }
+        if results.iter().any(|r| r.error.is_some()) {
+            warn!("Rejecting import: some entries are invalid");
+            return ImportReport {
+                applied: false,
+                deleted: 0,
+                created: 0,
+                results,
+            };
+        }
 
-        // Stop the existing task
-        self.stop_refresh_task(id).await;
+        let mut deleted = 0;
+        if mode == ImportMode::Replace {
+            let existing: Vec<Uuid> = self.mappings.read().await.keys().copied().collect();
+            for id in existing {
+                match self.delete_mapping(&id).await {
+                    Ok(()) => deleted += 1,
+                    Err(e) => warn!("Failed to delete mapping {} before import: {:#}", id, e),
+                }
+            }
+        }
 
-        // Update the mapping
-        {
-            let mut mappings = self.mappings.write().await;
-            if let Some(mapping) = mappings.get_mut(id) {
-                *mapping = updates.clone();
-                mapping.updated_at = Utc::now();
-            } else {
-                anyhow::bail!("Mapping not found");
+        let mut created = 0;
+        for ((mapping, options), result) in entries.into_iter().zip(&mut results) {
+            match self.add_mapping_with(mapping, options).await {
+                Ok(id) => {
+                    result.id = Some(id);
+                    created += 1;
+                }
+                Err(e) => result.error = Some(format!("{:#}", e)),
             }
         }
+        info!(
+            "Imported {} of {} mapping(s), deleted {}",
+            created,
+            results.len(),
+            deleted
+        );
 
-        // Restart the task if active
-        if updates.status == MappingStatus::Active {
-            self.start_refresh_task(updates).await?;
+        ImportReport {
+            applied: true,
+            deleted,
+            created,
+            results,
         }
+    }
 
+    /// Normalize an imported mapping as adding it would, then validate it
+    /// as adding would; `seen` collects the import's short URLs and slugs
+    async fn check_import_entry(
+        &self,
+        mapping: &mut Mapping,
+        options: &AddOptions,
+        taken: &Claims,
+        seen: &mut Claims,
+    ) -> Result<()> {
+        mapping.hosted_zone_id = normalize_hosted_zone_id(&mapping.hosted_zone_id)?;
+        mapping.short_url = normalize_short_url(&mapping.short_url)?;
+        mapping.clamp_presign_duration();
+        if let Some(id) = taken.short_urls.get(&mapping.short_url) {
+            return Err(ShortUrlTaken {
+                short_url: mapping.short_url.clone(),
+                id: *id,
+            }
+            .into());
+        }
+        if let Some(id) = taken.slugs.get(&mapping.slug) {
+            return Err(SlugTaken {
+                slug: mapping.slug.clone(),
+                id: *id,
+            }
+            .into());
+        }
+        if seen.short_urls.contains_key(&mapping.short_url) {
+            anyhow::bail!(
+                "Short URL {} appears more than once in the import",
+                mapping.short_url
+            );
+        }
+        if seen.slugs.contains_key(&mapping.slug) {
+            anyhow::bail!("Slug {} appears more than once in the import", mapping.slug);
+        }
+        seen.claim(mapping);
+        self.check_mapping(mapping).await?;
+        if let Some(delay) = options.initial_delay {
+            mapping.validate_initial_delay(delay)?;
+        }
+        if !options.skip_validation {
+            self.check_aws(mapping).await?;
+        }
         Ok(())
     }
 
-    /// Delete a mapping
-    #[instrument(skip(self))]
-    pub async fn delete_mapping(&self, id: &Uuid) -> Result<()> {
-        info!("Deleting mapping {}", id);
+    /// Check a mapping's S3 object can be read and, if its DNS is managed,
+    /// that its short URL fits its hosted zone
+    ///
+    /// A check that can't be completed, such as S3 denying access, fails
+    /// with `AwsCheckFailed` rather than as an invalid mapping.
+    async fn check_aws(&self, mapping: &Mapping) -> Result<()> {
+        match self.verify_object(mapping).await {
+            Ok(()) => {}
+            Err(e) if e.is::<ObjectNotFound>() => {
+                return Err(e.context(
+                    "S3 object not found; set skip_validation to create the mapping anyway",
+                ))
+            }
+            Err(e) => return Err(aws_check_failed(e)),
+        }
+        if mapping.manage_dns {
+            self.resolve_clients(mapping.credentials_profile.as_deref())
+                .await?
+                .route53
+                .validate_zone(&mapping.hosted_zone_id, &mapping.short_url)
+                .await
+                .map_err(|e| {
+                    if e.is::<ZoneLookupFailed>() {
+                        aws_check_failed(e)
+                    } else {
+                        e
+                    }
+                })?;
+        }
+        Ok(())
+    }
 
-        // Stop the refresh task
-        self.stop_refresh_task(id).await;
+    /// Validate a mapping's S3 URL, durations, query parameters and
+    /// credentials profile
+    async fn check_mapping(&self, mapping: &Mapping) -> Result<()> {
+        normalize_hosted_zone_id(&mapping.hosted_zone_id)?;
+        validate_short_url(&mapping.short_url)?;
+        mapping.presign_target()?;
+        mapping.validate_access_point()?;
+        mapping.validate_durations()?;
+        mapping.validate_query_params()?;
+        mapping.validate_allowed_window()?;
+        mapping.validate_force_content_type()?;
+        mapping.validate_dns_ttl()?;
+        mapping.validate_operation()?;
+        mapping.validate_fallbacks()?;
+        // Empty until derived when the mapping is stored
+        if !mapping.slug.is_empty() {
+            validate_slug(&mapping.slug)?;
+        }
 
-        // Remove from storage
-        let mut mappings = self.mappings.write().await;
-        mappings.remove(id).context("Mapping not found")?;
+        // Validate the credentials profile is known
+        self.resolve_clients(mapping.credentials_profile.as_deref())
+            .await?;
 
         Ok(())
     }
 
-    /// Pause a mapping (stop refreshing)
-    #[instrument(skip(self))]
-    pub async fn pause_mapping(&self, id: &Uuid) -> Result<()> {
-        info!("Pausing mapping {}", id);
-
-        self.stop_refresh_task(id).await;
-
-        let mut mappings = self.mappings.write().await;
-        if let Some(mapping) = mappings.get_mut(id) {
-            mapping.status = MappingStatus::Paused;
-            mapping.updated_at = Utc::now();
-        } else {
-            anyhow::bail!("Mapping not found");
+    /// Check the mapping's S3 object exists, or for a prefix (a key ending in
+    /// `/`) that at least one object exists under it
+    ///
+    /// Upload mappings are skipped, as their object may not exist yet.
+    async fn verify_object(&self, mapping: &Mapping) -> Result<()> {
+        if mapping.operation == Operation::Put {
+            return Ok(());
         }
-
-        Ok(())
+        let clients = self
+            .resolve_clients(mapping.credentials_profile.as_deref())
+            .await?;
+        check_readable(mapping, clients.s3.as_ref()).await
     }
 
-    /// Resume a paused mapping
-    #[instrument(skip(self))]
-    pub async fn resume_mapping(&self, id: &Uuid) -> Result<()> {
-        info!("Resuming mapping {}", id);
+    /// Load previously stored mappings at startup
+    ///
+    /// Mappings that no longer validate are kept as `Quarantined`, with the
+    /// reason in `last_error`, instead of failing the whole load. Paused
+    /// mappings stay paused; the rest are started.
+    #[instrument(skip(self, mappings))]
+    pub async fn load_mappings(&self, mappings: Vec<Mapping>) -> LoadReport {
+        let mut report = LoadReport::default();
 
-        let mapping = {
-            let mut mappings = self.mappings.write().await;
-            if let Some(mapping) = mappings.get_mut(id) {
-                mapping.status = MappingStatus::Active;
-                mapping.updated_at = Utc::now();
-                mapping.clone()
-            } else {
-                anyhow::bail!("Mapping not found");
+        for mut mapping in mappings {
+            let id = mapping.id;
+            if let Ok(hosted_zone_id) = normalize_hosted_zone_id(&mapping.hosted_zone_id) {
+                mapping.hosted_zone_id = hosted_zone_id;
+            }
+            if let Ok(short_url) = normalize_short_url(&mapping.short_url) {
+                mapping.short_url = short_url;
+            }
+            if let Err(e) = self.check_mapping(&mapping).await {
+                warn!("Quarantining mapping {}: {:#}", id, e);
+                mapping.set_status(MappingStatus::Quarantined);
+                mapping.last_error = Some(format!("{:#}", e));
+                let mut mappings = self.mappings.write().await;
+                claim_slug(&mut *self.slugs.write().await, &mut mapping);
+                mappings.insert(id, mapping);
+                report.quarantined += 1;
+                continue;
             }
-        };
 
-        self.start_refresh_task(mapping).await?;
+            if mapping.status != MappingStatus::Paused {
+                mapping.set_status(MappingStatus::Active);
+            }
+            {
+                // Mappings stored before slugs existed get one here
+                let mut mappings = self.mappings.write().await;
+                claim_slug(&mut *self.slugs.write().await, &mut mapping);
+                mappings.insert(id, mapping.clone());
+            }
+            if mapping.status.is_scheduled() {
+                if let Err(e) = self.start_refresh_task(mapping).await {
+                    error!("Failed to start refresh task for {}: {}", id, e);
+                }
+            }
+            report.loaded += 1;
+        }
 
-        Ok(())
-    }
+        info!(
+            "Loaded {} mapping(s), quarantined {}",
+            report.loaded, report.quarantined
+        );
 
-    /// Start a refresh task for a mapping
-    async fn start_refresh_task(&self, mapping: Mapping) -> Result<()> {
-        let id = mapping.id;
-        let mappings = Arc::clone(&self.mappings);
-        let s3_client = Arc::clone(&self.s3_client);
-        let route53_client = Arc::clone(&self.route53_client);
-        let log_tx = self.log_tx.clone();
+        report
+    }
 
-        let handle = tokio::spawn(async move {
-            let refresh_interval = mapping.refresh_interval();
-            let presign_duration = mapping.presign_duration();
+    /// Dry-run a prospective mapping: validate it, check the object is
+    /// readable and presign a sample URL, without storing anything
+    #[instrument(skip(self))]
+    pub async fn validate_mapping(&self, mapping: &Mapping) -> ValidationReport {
+        let mut errors = Vec::new();
 
-            // Perform initial refresh
-            refresh_url(
-                &mapping,
-                &s3_client,
-                &route53_client,
-                &mappings,
-                presign_duration,
-                &log_tx,
-            )
-            .await;
+        if !mapping.s3_url.starts_with("s3://") {
+            errors.push("S3 URL must start with s3://".to_string());
+        }
+        if let Err(e) = validate_short_url(&mapping.short_url) {
+            errors.push(e.to_string());
+        }
+        if let Err(e) = mapping.validate_durations() {
+            errors.push(e.to_string());
+        }
+        if !errors.is_empty() {
+            return ValidationReport {
+                ok: false,
+                s3_ok: false,
+                presign_ok: false,
+                url_sample: None,
+                url_sample_host: None,
+                errors,
+            };
+        }
 
-            // Set up periodic refresh
-            let mut interval = interval(refresh_interval);
-            interval.tick().await; // First tick completes immediately
+        // Both checks run so the report says which of them failed
+        let s3_checked = self.verify_object(mapping).await;
+        let presigned = async {
+            let clients = self
+                .resolve_clients(mapping.credentials_profile.as_deref())
+                .await?;
+            let (bucket, key) = mapping.presign_target()?;
 
-            loop {
-                interval.tick().await;
-                refresh_url(
-                    &mapping,
-                    &s3_client,
-                    &route53_client,
-                    &mappings,
-                    presign_duration,
-                    &log_tx,
+            let options = mapping.presign_options();
+            clients
+                .s3
+                .generate_presigned_url(
+                    &bucket,
+                    &key,
+                    mapping.effective_presign_duration(),
+                    &options,
                 )
-                .await;
-            }
-        });
+                .await
+        }
+        .await;
 
-        let mut tasks = self.tasks.write().await;
-        tasks.insert(id, handle);
+        let errors: Vec<String> = [s3_checked.as_ref().err(), presigned.as_ref().err()]
+            .into_iter()
+            .flatten()
+            .map(|e| format!("{:#}", e))
+            .collect();
+        let url_sample = presigned.ok();
+        ValidationReport {
+            ok: errors.is_empty(),
+            s3_ok: s3_checked.is_ok(),
+            presign_ok: url_sample.is_some(),
+            url_sample_host: url_sample
+                .as_deref()
+                .and_then(|url| url::Url::parse(url).ok())
+                .and_then(|url| url.host_str().map(str::to_string)),
+            url_sample,
+            errors,
+        }
+    }
 
-        Ok(())
+    /// Get a mapping by ID
+    pub async fn get_mapping(&self, id: &Uuid) -> Option<Mapping> {
+        let mappings = self.mappings.read().await;
+        mappings.get(id).cloned()
     }
 
-    /// Stop a refresh task for a mapping
-    async fn stop_refresh_task(&self, id: &Uuid) {
-        let mut tasks = self.tasks.write().await;
-        if let Some(handle) = tasks.remove(id) {
-            handle.abort();
+    /// Get a mapping by its slug
+    pub async fn get_mapping_by_slug(&self, slug: &str) -> Option<Mapping> {
+        let mappings = self.mappings.read().await;
+        let id = self.slugs.read().await.get(slug).copied()?;
+        mappings.get(&id).cloned()
+    }
+
+    /// ID of the mapping `id_or_slug` names, either by its ID or its slug
+    pub async fn resolve_id(&self, id_or_slug: &str) -> Option<Uuid> {
+        if let Ok(id) = Uuid::parse_str(id_or_slug) {
+            return Some(id);
         }
+        self.get_mapping_by_slug(id_or_slug).await.map(|m| m.id)
     }
-}
 
-/// Refresh the presigned URL and update Route53
-#[instrument(skip(s3_client, route53_client, mappings, log_tx))]
-async fn refresh_url(
-    mapping: &Mapping,
-    s3_client: &S3Client,
-    route53_client: &Route53Client,
-    mappings: &Arc<RwLock<HashMap<Uuid, Mapping>>>,
-    presign_duration: std::time::Duration,
-    log_tx: &mpsc::UnboundedSender<RefreshLog>,
-) {
-    info!("Refreshing presigned URL for {}", mapping.id);
+    /// List all mappings
+    pub async fn list_mappings(&self) -> Vec<Mapping> {
+        let mappings = self.mappings.read().await;
+        mappings.values().cloned().collect()
+    }
 
-    let result = async {
-        // Parse S3 URL
-        let config = Config::new(
-            mapping.s3_url.clone(),
-            mapping.short_url.clone(),
-            mapping.hosted_zone_id.clone(),
-        )?;
-        let (bucket, key) = config.parse_s3_url()?;
+    /// All mappings grouped by status, each group sorted by short URL
+    pub async fn grouped_mappings(&self) -> GroupedMappings {
+        let mut mappings = self.list_mappings().await;
+        mappings.sort_by(|a, b| a.short_url.cmp(&b.short_url).then(a.id.cmp(&b.id)));
 
-        // Generate new presigned URL
-        let presigned_url = s3_client
-            .generate_presigned_url(&bucket, &key, presign_duration)
-            .await?;
+        let mut grouped = GroupedMappings::default();
+        for mapping in mappings {
+            let group = match mapping.status {
+                MappingStatus::Active => &mut grouped.active,
+                MappingStatus::Paused => &mut grouped.paused,
+                MappingStatus::Error => &mut grouped.error,
+                MappingStatus::Pending => &mut grouped.pending,
+                MappingStatus::Quarantined => &mut grouped.quarantined,
+            };
+            group.push(mapping);
+        }
+        grouped
+    }
 
-        // Update Route53 DNS record
-        route53_client
-            .update_dns_record(&mapping.hosted_zone_id, &mapping.short_url, &presigned_url)
-            .await?;
+    /// A page of the mappings matching `filter`
+    ///
+    /// With an `offset`, mappings are ordered by creation, then ID; otherwise
+    /// by last update, then ID, so a cursor stays valid across deletes.
+    pub async fn list_mappings_page(&self, filter: ListFilter) -> Result<ListMappingsResponse> {
+        if filter.offset.is_some() && filter.cursor.is_some() {
+            anyhow::bail!("offset and cursor can't be combined");
+        }
+        let after = parse_cursor(filter.cursor.as_deref())?;
+        let mut mappings: Vec<Mapping> = self
+            .list_mappings()
+            .await
+            .into_iter()
+            .filter(|m| match filter.status {
+                Some(status) => m.status == status,
+                None => m.status != MappingStatus::Quarantined,
+            })
+            .collect();
+        let total = mappings.len();
+        let limit = filter.limit.unwrap_or(total).max(1);
 
-        Ok::<_, anyhow::Error>(())
-    }
-    .await;
+        if let Some(offset) = filter.offset {
+            mappings.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+            let mappings: Vec<Mapping> = mappings.into_iter().skip(offset).take(limit).collect();
+            let next_offset = offset.checked_add(limit).filter(|&next| next < total);
+            return Ok(ListMappingsResponse {
+                mappings,
+                next_cursor: None,
+                next_offset,
+                total,
+            });
+        }
 
-    // Update mapping status
-    let mut mappings = mappings.write().await;
-    if let Some(stored_mapping) = mappings.get_mut(&mapping.id) {
-        match result {
-            Ok(_) => {
-                stored_mapping.last_refresh = Some(Utc::now());
-                stored_mapping.next_refresh = Some(
-                    Utc::now() + chrono::Duration::from_std(mapping.refresh_interval()).unwrap(),
-                );
-                stored_mapping.status = MappingStatus::Active;
-                stored_mapping.last_error = None;
+        let (mappings, next_cursor) =
+            page_after(mappings, |m| Cursor::new(m.updated_at, m.id), after, limit);
 
-                let _ = log_tx.send(RefreshLog {
-                    mapping_id: mapping.id,
-                    timestamp: Utc::now(),
-                    success: true,
-                    message: "Successfully refreshed presigned URL".to_string(),
-                });
+        Ok(ListMappingsResponse {
+            mappings,
+            next_cursor,
+            next_offset: None,
+            total,
+        })
+    }
 
-                info!("Successfully refreshed presigned URL for {}", mapping.id);
-            }
-            Err(e) => {
-                let error_msg = format!("Failed to refresh URL: {}", e);
-                stored_mapping.status = MappingStatus::Error;
-                stored_mapping.last_error = Some(error_msg.clone());
+    /// Health of every mapping, worst first
+    pub async fn health(&self) -> Vec<MappingHealth> {
+        let now = Utc::now();
+        let mut health: Vec<MappingHealth> = self
+            .mappings
+            .read()
+            .await
+            .values()
+            .map(|m| MappingHealth {
+                mapping_id: m.id,
+                short_url: m.short_url.clone(),
+                score: m.health_score(now),
+                consecutive_failures: m.consecutive_failures,
+                last_error: m.last_error.clone(),
+            })
+            .collect();
+        health.sort_by(|a, b| a.score.cmp(&b.score).then(a.short_url.cmp(&b.short_url)));
+        health
+    }
 
-                let _ = log_tx.send(RefreshLog {
-                    mapping_id: mapping.id,
-                    timestamp: Utc::now(),
-                    success: false,
-                    message: error_msg.clone(),
-                });
+    /// Ready once every scheduled (not paused or quarantined) mapping has
+    /// succeeded at least once, i.e. has a `last_refresh`
+    pub async fn readiness(&self) -> Readiness {
+        let mut waiting: Vec<Uuid> = self
+            .mappings
+            .read()
+            .await
+            .values()
+            .filter(|m| m.status.is_scheduled() && m.last_refresh.is_none())
+            .map(|m| m.id)
+            .collect();
+        waiting.sort();
+        Readiness {
+            ready: waiting.is_empty(),
+            waiting,
+        }
+    }
 
-                error!("Failed to refresh presigned URL for {}: {}", mapping.id, e);
+    /// Probe S3 and Route53 with the default credentials, concurrently and
+    /// each within `ACCESS_CHECK_TIMEOUT`, reusing a result younger than
+    /// `AWS_READINESS_TTL`
+    pub async fn aws_readiness(&self) -> AwsReadiness {
+        let mut cached = self.aws_readiness.lock().await;
+        if let Some((checked, readiness)) = cached.as_ref() {
+            if checked.elapsed() < AWS_READINESS_TTL {
+                return readiness.clone();
             }
         }
+        let readiness = self.probe_aws().await;
+        *cached = Some((Instant::now(), readiness.clone()));
+        readiness
+    }
+
+    async fn probe_aws(&self) -> AwsReadiness {
+        let clients = match self.resolve_clients(None).await {
+            Ok(clients) => clients,
+            Err(e) => {
+                let failed = AccessCheck {
+                    ok: false,
+                    error: Some(format!("{:#}", e)),
+                    latency_ms: 0,
+                };
+                return AwsReadiness {
+                    ready: false,
+                    s3: failed.clone(),
+                    route53: failed,
+                };
+            }
+        };
+        let (s3, route53) = tokio::join!(
+            access_check(clients.s3.check_access(self.probe_bucket.as_deref())),
+            access_check(clients.route53.check_access()),
+        );
+        AwsReadiness {
+            ready: s3.ok && route53.ok,
+            s3,
+            route53,
+        }
+    }
+
+    /// Hosted zones referenced by mappings with their mapping counts, sorted
+    /// by zone ID, optionally resolving each zone's name
+    pub async fn zones(&self, resolve: bool) -> Vec<ZoneUsage> {
+        // Zone ID -> (mapping count, credentials profile of the first mapping seen)
+        let mut zones: BTreeMap<String, (usize, Option<String>)> = BTreeMap::new();
+        for mapping in self.mappings.read().await.values() {
+            zones
+                .entry(mapping.hosted_zone_id.clone())
+                .or_insert_with(|| (0, mapping.credentials_profile.clone()))
+                .0 += 1;
+        }
+
+        let mut usage = Vec::with_capacity(zones.len());
+        for (hosted_zone_id, (mapping_count, profile)) in zones {
+            let name = if resolve {
+                let name = async {
+                    self.resolve_clients(profile.as_deref())
+                        .await?
+                        .route53
+                        .get_zone_name(&hosted_zone_id)
+                        .await
+                }
+                .await;
+                name.unwrap_or_else(|e| {
+                    warn!("Failed to resolve hosted zone {}: {:#}", hosted_zone_id, e);
+                    None
+                })
+            } else {
+                None
+            };
+
+            usage.push(ZoneUsage {
+                hosted_zone_id,
+                mapping_count,
+                name,
+            });
+        }
+
+        usage
+    }
+
+    /// Resolve the effective configuration a mapping runs with
+    pub async fn effective_config(&self, id: &Uuid) -> Result<EffectiveConfig> {
+        let mapping = self.get_mapping(id).await.context("Mapping not found")?;
+        let clients = self
+            .resolve_clients(mapping.credentials_profile.as_deref())
+            .await?;
+
+        Ok(EffectiveConfig {
+            mapping_id: mapping.id,
+            presign_duration_secs: mapping.effective_presign_duration().as_secs(),
+            refresh_interval_secs: mapping.refresh_interval_secs,
+            dns_ttl_secs: mapping.dns_ttl_secs,
+            credentials_profile: mapping.credentials_profile,
+            region: clients.s3.region(),
+        })
+    }
+
+    /// Update a mapping
+    #[instrument(skip(self))]
+    pub async fn update_mapping(&self, id: &Uuid, mut updates: Mapping) -> Result<()> {
+        info!("Updating mapping {}", id);
+
+        updates.hosted_zone_id = normalize_hosted_zone_id(&updates.hosted_zone_id)?;
+        updates.short_url = normalize_short_url(&updates.short_url)?;
+        if updates.clamp_presign_duration() {
+            warn!(
+                "Presign duration of {} capped at the AWS maximum of {}s",
+                updates.short_url, updates.presign_duration_secs
+            );
+        }
+        // The same checks as creating it
+        self.check_mapping(&updates).await?;
+        // A record moved to another zone or name has to fit that zone;
+        // a short URL that's already taken fails before asking Route53
+        let current = {
+            let mappings = self.mappings.read().await;
+            check_short_url_free(&mappings, &updates.short_url, *id)?;
+            mappings.get(id).cloned().context("Mapping not found")?
+        };
+        if updates.manage_dns
+            && (!current.manage_dns
+                || current.hosted_zone_id != updates.hosted_zone_id
+                || current.short_url != updates.short_url)
+        {
+            self.resolve_clients(updates.credentials_profile.as_deref())
+                .await?
+                .route53
+                .validate_zone(&updates.hosted_zone_id, &updates.short_url)
+                .await?;
+        }
+
+        // Checked under the lock that stores the update, so concurrent
+        // updates and creates can't both claim the short URL or slug
+        let (before, notes_only) = {
+            let mut mappings = self.mappings.write().await;
+            let mut slugs = self.slugs.write().await;
+            let current = mappings.get(id).context("Mapping not found")?;
+            check_short_url_free(&mappings, &updates.short_url, *id)?;
+            if updates.slug != current.slug {
+                validate_slug(&updates.slug)?;
+                check_slug_free(&slugs, &updates.slug, *id)?;
+                slugs.remove(&current.slug);
+                slugs.insert(updates.slug.clone(), *id);
+            }
+            // Notes don't affect refreshes, so changing only them leaves the
+            // task running
+            let changes = current.diff(&updates).changes;
+            let notes_only =
+                !changes.is_empty() && changes.iter().all(|change| change.field == "notes");
+            let mapping = mappings.get_mut(id).expect("checked above");
+            let before = mapping.status;
+            *mapping = updates.clone();
+            mapping.updated_at = Utc::now();
+            (before, notes_only)
+        };
+        if !notes_only {
+            self.stop_refresh_task(id).await;
+        }
+        self.record_audit(AuditAction::Update, *id, Some(before), Some(updates.status))
+            .await;
+
+        // Restart the task unless paused or quarantined
+        if !notes_only && updates.status.is_scheduled() {
+            self.start_refresh_task(updates).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Set the durations of every mapping in a bucket and/or hosted zone,
+    /// restarting the refresh tasks of those that changed
+    ///
+    /// The updates are checked and stored under one lock, so nothing is
+    /// changed unless the new durations are valid for every match.
+    #[instrument(skip(self))]
+    pub async fn bulk_update_durations(
+        &self,
+        query: &BulkUpdateQuery,
+        durations: &BulkDurationsRequest,
+    ) -> Result<BulkUpdateReport> {
+        if query.bucket.is_none() && query.zone.is_none() {
+            anyhow::bail!("A bucket or zone filter is required");
+        }
+        if durations.presign_duration_secs.is_none() && durations.refresh_interval_secs.is_none() {
+            anyhow::bail!("No presign_duration_secs or refresh_interval_secs given");
+        }
+        let zone = query
+            .zone
+            .as_deref()
+            .map(normalize_hosted_zone_id)
+            .transpose()?;
+
+        let mut report = BulkUpdateReport::default();
+        let mut changed = Vec::new();
+        {
+            let mut mappings = self.mappings.write().await;
+            let matches = mappings
+                .values()
+                .filter(|m| zone.as_ref().is_none_or(|zone| m.hosted_zone_id == *zone))
+                .filter(|m| {
+                    query
+                        .bucket
+                        .as_ref()
+                        .is_none_or(|bucket| m.bucket().as_ref() == Some(bucket))
+                });
+            for mapping in matches {
+                report.matched += 1;
+
+                let mut updated = mapping.clone();
+                updated.apply_update(UpdateMappingRequest {
+                    presign_duration_secs: durations.presign_duration_secs,
+                    refresh_interval_secs: durations.refresh_interval_secs,
+                    ..Default::default()
+                });
+                if updated.clamp_presign_duration() {
+                    warn!(
+                        "Presign duration of {} capped at the AWS maximum of {}s",
+                        updated.short_url, updated.presign_duration_secs
+                    );
+                }
+                // The checks of `check_mapping` that depend on the durations
+                updated
+                    .validate_durations()
+                    .and_then(|()| updated.validate_allowed_window())
+                    .with_context(|| format!("Mapping {}", mapping.id))?;
+                if !mapping.diff(&updated).changes.is_empty() {
+                    changed.push((mapping.status, updated));
+                }
+            }
+
+            let now = Utc::now();
+            for (_, updated) in &changed {
+                let mapping = mappings.get_mut(&updated.id).expect("matched above");
+                *mapping = updated.clone();
+                mapping.updated_at = now;
+            }
+        }
+
+        for (before, updated) in changed {
+            let id = updated.id;
+            self.stop_refresh_task(&id).await;
+            self.record_audit(AuditAction::Update, id, Some(before), Some(updated.status))
+                .await;
+            if updated.status.is_scheduled() {
+                if let Err(e) = self.start_refresh_task(updated).await {
+                    error!("Failed to restart refresh task for {}: {:#}", id, e);
+                }
+            }
+            report.updated_ids.push(id);
+        }
+        report.updated = report.updated_ids.len();
+        Ok(report)
+    }
+
+    /// Delete a mapping
+    #[instrument(skip(self))]
+    pub async fn delete_mapping(&self, id: &Uuid) -> Result<()> {
+        info!("Deleting mapping {}", id);
+
+        // Worked out first, so a failure leaves the mapping where it is
+        let deleted_at = Utc::now();
+        let purge_at = chrono::Duration::from_std(self.recycle_ttl)
+            .ok()
+            .and_then(|ttl| deleted_at.checked_add_signed(ttl))
+            .context("Deleted mapping retention is too long")?;
+
+        // Move to the recycle bin; the DNS record is left in place. The mapping
+        // is removed before its task is stopped so a task being started
+        // concurrently sees it gone.
+        let mapping = {
+            let mut mappings = self.mappings.write().await;
+            let mapping = mappings.remove(id).context("Mapping not found")?;
+            self.slugs.write().await.remove(&mapping.slug);
+            mapping
+        };
+
+        // Stop the refresh task; one mid-refresh finds the mapping gone and
+        // leaves no trace
+        self.stop_refresh_task(id).await;
+        self.record_audit(AuditAction::Delete, *id, Some(mapping.status), None)
+            .await;
+        self.deleted.write().await.insert(
+            *id,
+            DeletedMapping {
+                mapping,
+                deleted_at,
+                purge_at,
+            },
+        );
+
+        self.purge_deleted().await;
+
+        Ok(())
+    }
+
+    /// Deleted mappings that can still be restored, most recently deleted first
+    pub async fn list_deleted(&self) -> Vec<DeletedMapping> {
+        self.purge_deleted().await;
+
+        let mut deleted: Vec<DeletedMapping> =
+            self.deleted.read().await.values().cloned().collect();
+        deleted.sort_by_key(|d| std::cmp::Reverse(d.deleted_at));
+        deleted
+    }
+
+    /// Bring a deleted mapping back, restarting its refresh task unless it was
+    /// paused or quarantined
+    #[instrument(skip(self))]
+    pub async fn restore_mapping(&self, id: &Uuid) -> Result<Mapping> {
+        info!("Restoring mapping {}", id);
+
+        self.purge_deleted().await;
+        let mapping = {
+            // Checked under both locks, so a create can't claim the short URL
+            // between the check and the insert; a restore that fails leaves the
+            // mapping deleted
+            let mut deleted = self.deleted.write().await;
+            let mut mappings = self.mappings.write().await;
+            let mut slugs = self.slugs.write().await;
+            let short_url = &deleted
+                .get(id)
+                .context("Deleted mapping not found")?
+                .mapping
+                .short_url;
+            check_short_url_free(&mappings, short_url, *id)?;
+            let mut mapping = deleted.remove(id).expect("checked above").mapping;
+            mapping.updated_at = Utc::now();
+            // The slug may have been taken while the mapping was deleted
+            claim_slug(&mut slugs, &mut mapping);
+            mappings.insert(*id, mapping.clone());
+            mapping
+        };
+        self.record_audit(AuditAction::Restore, *id, None, Some(mapping.status))
+            .await;
+        if mapping.status.is_scheduled() {
+            self.start_refresh_task(mapping.clone()).await?;
+        }
+
+        Ok(mapping)
+    }
+
+    /// Permanently remove deleted mappings past their retention, returning how many
+    pub async fn purge_deleted(&self) -> usize {
+        let now = Utc::now();
+        let expired: Vec<Uuid> = {
+            let mut deleted = self.deleted.write().await;
+            let expired: Vec<Uuid> = deleted
+                .values()
+                .filter(|d| d.purge_at <= now)
+                .map(|d| d.mapping.id)
+                .collect();
+            for id in &expired {
+                deleted.remove(id);
+            }
+            expired
+        };
+
+        for id in &expired {
+            info!("Purging deleted mapping {}", id);
+            self.logs.remove(id).await;
+            self.refresh_gates.write().await.remove(id);
+        }
+
+        expired.len()
+    }
+
+    /// Recent refresh logs across all mappings, oldest first
+    pub async fn logs(&self, query: &LogQuery) -> Result<ListLogsResponse> {
+        self.logs.query(query).await
+    }
+
+    /// Retained refresh logs of one mapping, oldest first
+    pub async fn mapping_logs(&self, id: &Uuid) -> Result<Vec<RefreshLog>> {
+        self.get_mapping(id).await.context("Mapping not found")?;
+        Ok(self.logs.for_mapping(id).await)
+    }
+
+    /// Health and time in the current status of every mapping, by short URL
+    pub async fn uptimes(&self) -> Vec<MappingUptime> {
+        let now = Utc::now();
+        let mut uptimes: Vec<MappingUptime> = self
+            .mappings
+            .read()
+            .await
+            .values()
+            .map(|m| m.uptime(now))
+            .collect();
+        uptimes.sort_by(|a, b| a.short_url.cmp(&b.short_url));
+        uptimes
+    }
+
+    /// Running refresh tasks against scheduled mappings, with each mapping's uptime
+    pub async fn stats(&self) -> Stats {
+        // Same lock order as starting a task: tasks, then mappings
+        let tasks = self.tasks.read().await;
+        let mappings = self.mappings.read().await;
+        let active_tasks = tasks
+            .values()
+            .filter(|handle| !handle.is_finished())
+            .count();
+        let active_mappings = mappings
+            .values()
+            .filter(|m| m.status.is_scheduled())
+            .count();
+        drop(mappings);
+        drop(tasks);
+
+        Stats {
+            active_tasks,
+            active_mappings,
+            mappings: self.uptimes().await,
+        }
+    }
+
+    /// Refresh duration percentiles across all mappings and per mapping
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.logs.latencies().stats()
+    }
+
+    /// Counters of refresh outcomes, for metrics publishing
+    pub fn refresh_counters(&self) -> RefreshCounters {
+        self.logs.counters()
+    }
+
+    /// Refresh a mapping now, unless it finished a refresh within the
+    /// debounce window, in which case its current state is returned
+    ///
+    /// Waits for any refresh already in progress for the mapping.
+    #[instrument(skip(self))]
+    pub async fn refresh_now(&self, id: &Uuid) -> Result<ManualRefresh> {
+        let mapping = self.get_mapping(id).await.context("Mapping not found")?;
+        if !mapping.status.is_scheduled() {
+            anyhow::bail!("Mapping is {}", mapping.status);
+        }
+
+        let gate = refresh_gate(&self.refresh_gates, *id).await;
+        let mut last_refresh = gate.lock().await;
+        let refreshed = if last_refresh.is_some_and(|t| t.elapsed() < self.refresh_debounce) {
+            info!("Skipping refresh of {}: refreshed moments ago", id);
+            false
+        } else {
+            let clients = self
+                .resolve_clients(mapping.credentials_profile.as_deref())
+                .await?;
+            refresh_url(
+                &mapping,
+                clients.s3.as_ref(),
+                clients.route53.as_ref(),
+                &self.mappings,
+                mapping.effective_presign_duration(),
+                self.grace_period,
+                &self.logs,
+            )
+            .await;
+            *last_refresh = Some(Instant::now());
+
+            // The periodic task keeps its schedule, so keep reporting it
+            if mapping.next_refresh.is_some() {
+                if let Some(stored) = self.mappings.write().await.get_mut(id) {
+                    stored.next_refresh = mapping.next_refresh;
+                }
+            }
+            true
+        };
+        drop(last_refresh);
+
+        let mapping = self.get_mapping(id).await.context("Mapping not found")?;
+        Ok(ManualRefresh { refreshed, mapping })
+    }
+
+    /// Presign a fresh URL for a mapping's object with the given method,
+    /// without touching its DNS record
+    ///
+    /// `download` replaces the mapping's `download_filename`.
+    #[instrument(skip(self))]
+    pub async fn presign_now(
+        &self,
+        id: &Uuid,
+        method: PresignMethod,
+        ttl: Option<u64>,
+        download: Option<&str>,
+    ) -> Result<PresignedUrl> {
+        let mapping = self.get_mapping(id).await.context("Mapping not found")?;
+        let clients = self
+            .resolve_clients(mapping.credentials_profile.as_deref())
+            .await?;
+        // After a failover, the replica that's being served
+        let target = mapping.serving_target();
+        let (bucket, key) = target.presign_target()?;
+        let mut options = PresignOptions {
+            method,
+            ..target.presign_options()
+        };
+        if let Some(filename) = download {
+            options.response_content_disposition =
+                Some(attachment_disposition(filename).context("Download filename is empty")?);
+        }
+
+        let duration = mapping.presign_duration_for(ttl);
+
+        // A burst of identical requests makes one call to S3, and a burst of
+        // distinct ones at most `presign_permits` at a time
+        let disposition = options.response_content_disposition.clone();
+        let url = self
+            .presigns
+            .run((*id, method, duration, disposition), || async {
+                let permit = self.presign_permits.acquire();
+                let Ok(Ok(_permit)) = tokio::time::timeout(self.presign_wait, permit).await else {
+                    return Ok(Err(PresignBusy));
+                };
+                clients
+                    .s3
+                    .generate_presigned_url(&bucket, &key, duration, &options)
+                    .await
+                    .map(Ok)
+            })
+            .await??;
+        Ok(PresignedUrl {
+            method,
+            url,
+            expires_in_secs: duration.as_secs(),
+        })
+    }
+
+    /// Fetch the live Route53 record of a mapping's short URL and check it
+    /// points where a fresh presigned URL would
+    #[instrument(skip(self))]
+    pub async fn dns_status(&self, id: &Uuid) -> Result<DnsStatus> {
+        let mapping = self.get_mapping(id).await.context("Mapping not found")?;
+        let clients = self
+            .resolve_clients(mapping.credentials_profile.as_deref())
+            .await?;
+        let target = mapping.serving_target();
+        let (bucket, key) = target.presign_target()?;
+
+        let presigned_url = clients
+            .s3
+            .generate_presigned_url(
+                &bucket,
+                &key,
+                mapping.effective_presign_duration(),
+                &target.presign_options(),
+            )
+            .await?;
+        let expected = expected_target(&presigned_url, mapping.dns_record_type)?;
+        let record = clients
+            .route53
+            .get_record(&mapping.hosted_zone_id, &mapping.short_url)
+            .await
+            .context("Failed to fetch DNS record")?;
+
+        Ok(DnsStatus {
+            mapping_id: mapping.id,
+            short_url: mapping.short_url,
+            matches: points_to(record.as_ref(), &expected, mapping.dns_record_type),
+            record,
+            expected,
+        })
+    }
+
+    /// Pause a mapping (stop refreshing)
+    #[instrument(skip(self))]
+    pub async fn pause_mapping(&self, id: &Uuid) -> Result<()> {
+        info!("Pausing mapping {}", id);
+        // Paused on its own, so `resume_all` leaves it paused
+        self.bulk_paused.write().await.remove(id);
+
+        self.stop_refresh_task(id).await;
+
+        let before = {
+            let mut mappings = self.mappings.write().await;
+            if let Some(mapping) = mappings.get_mut(id) {
+                let before = mapping.status;
+                mapping.set_status(MappingStatus::Paused);
+                mapping.updated_at = Utc::now();
+                before
+            } else {
+                anyhow::bail!("Mapping not found");
+            }
+        };
+        self.record_audit(
+            AuditAction::Pause,
+            *id,
+            Some(before),
+            Some(MappingStatus::Paused),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// Resume a paused mapping
+    #[instrument(skip(self))]
+    pub async fn resume_mapping(&self, id: &Uuid) -> Result<()> {
+        info!("Resuming mapping {}", id);
+        self.bulk_paused.write().await.remove(id);
+
+        // A quarantined mapping only resumes once it has been fixed
+        if let Some(current) = self.get_mapping(id).await {
+            if current.status == MappingStatus::Quarantined {
+                self.check_mapping(&current)
+                    .await
+                    .context("Quarantined mapping is still invalid")?;
+            }
+        }
+
+        let (before, mapping) = {
+            let mut mappings = self.mappings.write().await;
+            if let Some(mapping) = mappings.get_mut(id) {
+                let before = mapping.status;
+                mapping.set_status(MappingStatus::Active);
+                mapping.updated_at = Utc::now();
+                (before, mapping.clone())
+            } else {
+                anyhow::bail!("Mapping not found");
+            }
+        };
+        self.record_audit(
+            AuditAction::Resume,
+            *id,
+            Some(before),
+            Some(MappingStatus::Active),
+        )
+        .await;
+
+        self.start_refresh_task(mapping).await?;
+
+        Ok(())
+    }
+
+    /// Pause every mapping that is refreshing
+    ///
+    /// Quarantined mappings are left alone so they can't be resumed without
+    /// being checked.
+    #[instrument(skip(self))]
+    pub async fn pause_all(&self) -> BulkStatusReport {
+        let ids = self.ids_where(|status| status.is_scheduled()).await;
+        let mut report = BulkStatusReport::default();
+        for id in ids {
+            match self.pause_mapping(&id).await {
+                Ok(()) => {
+                    self.bulk_paused.write().await.insert(id);
+                    report.changed_ids.push(id);
+                }
+                Err(e) => warn!("Failed to pause mapping {}: {:#}", id, e),
+            }
+        }
+        report.changed = report.changed_ids.len();
+        report
+    }
+
+    /// Resume the mappings `pause_all` paused, leaving ones paused on their
+    /// own paused
+    ///
+    /// Which mappings were paused in bulk isn't stored, so after a restart
+    /// they have to be resumed one by one.
+    #[instrument(skip(self))]
+    pub async fn resume_all(&self) -> BulkStatusReport {
+        let bulk_paused = std::mem::take(&mut *self.bulk_paused.write().await);
+        let ids = self
+            .ids_where(|status| status == MappingStatus::Paused)
+            .await
+            .into_iter()
+            .filter(|id| bulk_paused.contains(id));
+        let mut report = BulkStatusReport::default();
+        for id in ids {
+            match self.resume_mapping(&id).await {
+                Ok(()) => report.changed_ids.push(id),
+                Err(e) => warn!("Failed to resume mapping {}: {:#}", id, e),
+            }
+        }
+        report.changed = report.changed_ids.len();
+        report
+    }
+
+    /// IDs of the mappings whose status matches, ordered by short URL
+    async fn ids_where(&self, matches: impl Fn(MappingStatus) -> bool) -> Vec<Uuid> {
+        let mappings = self.mappings.read().await;
+        let mut matching: Vec<&Mapping> = mappings.values().filter(|m| matches(m.status)).collect();
+        matching.sort_by(|a, b| a.short_url.cmp(&b.short_url));
+        matching.iter().map(|m| m.id).collect()
+    }
+
+    /// Compare every active mapping's DNS record with its expected target and fix drift
+    #[instrument(skip(self))]
+    pub async fn reconcile(&self) -> ReconcileReport {
+        info!("Reconciling Route53 records");
+
+        let mut report = ReconcileReport::default();
+        let active: Vec<Mapping> = self
+            .list_mappings()
+            .await
+            .into_iter()
+            .filter(|m| m.status == MappingStatus::Active && m.manage_dns)
+            .collect();
+
+        for mapping in active {
+            report.checked += 1;
+
+            let result = async {
+                let clients = self
+                    .resolve_clients(mapping.credentials_profile.as_deref())
+                    .await?;
+                // Kept on the replica a failover switched to, not reverted
+                // to an unreadable primary
+                let target = mapping.serving_target();
+                let (bucket, key) = target.presign_target()?;
+
+                let presigned_url = clients
+                    .s3
+                    .generate_presigned_url(
+                        &bucket,
+                        &key,
+                        mapping.effective_presign_duration(),
+                        &target.presign_options(),
+                    )
+                    .await?;
+
+                reconcile_record(
+                    clients.route53.as_ref(),
+                    &mapping.hosted_zone_id,
+                    &mapping.short_url,
+                    &presigned_url,
+                    mapping.dns_record(),
+                    Some(mapping.id),
+                )
+                .await
+            }
+            .await;
+
+            match result {
+                Ok(true) => report.fixed += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    error!("Failed to reconcile mapping {}: {}", mapping.id, e);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        info!(
+            "Reconciliation complete: {} checked, {} fixed, {} failed",
+            report.checked, report.fixed, report.failed
+        );
+
+        report
+    }
+
+    /// Bring the refresh tasks back in line with the mappings: abort tasks of
+    /// deleted or paused mappings and start tasks for unpaused mappings whose
+    /// task is missing or has exited
+    #[instrument(skip(self))]
+    pub async fn gc(&self) -> GcReport {
+        let mut report = GcReport::default();
+        let mappings = self.list_mappings().await;
+
+        {
+            let mut tasks = self.tasks.write().await;
+            let orphaned: Vec<Uuid> = tasks
+                .keys()
+                .filter(|id| {
+                    !mappings
+                        .iter()
+                        .any(|m| m.id == **id && m.status.is_scheduled())
+                })
+                .copied()
+                .collect();
+            for id in orphaned {
+                if let Some(handle) = tasks.remove(&id) {
+                    warn!("Aborting orphaned refresh task for {}", id);
+                    handle.abort();
+                    report.aborted += 1;
+                }
+            }
+        }
+
+        for mapping in mappings {
+            if !mapping.status.is_scheduled() {
+                continue;
+            }
+            let running = self
+                .tasks
+                .read()
+                .await
+                .get(&mapping.id)
+                .is_some_and(|handle| !handle.is_finished());
+            if running {
+                continue;
+            }
+
+            warn!("Starting missing refresh task for {}", mapping.id);
+            let id = mapping.id;
+            match self.start_refresh_task(mapping).await {
+                Ok(()) => report.started += 1,
+                Err(e) => error!("Failed to start refresh task for {}: {}", id, e),
+            }
+        }
+
+        info!(
+            "Task GC complete: {} aborted, {} started",
+            report.aborted, report.started
+        );
+
+        report
+    }
+
+    /// Current client registry
+    pub async fn client_registry(&self) -> Arc<ClientRegistry> {
+        Arc::clone(&*self.clients.read().await)
+    }
+
+    /// Swap in freshly built clients; running refresh tasks pick them up on their next refresh
+    pub async fn replace_clients(&self, clients: ClientRegistry) {
+        info!("Replacing AWS clients");
+        *self.clients.write().await = Arc::new(clients);
+    }
+
+    /// Final state of every mapping, sorted by short URL
+    pub async fn summary(&self) -> Vec<MappingSummary> {
+        let mut summary: Vec<MappingSummary> = self
+            .mappings
+            .read()
+            .await
+            .values()
+            .map(|m| MappingSummary {
+                id: m.id,
+                short_url: m.short_url.clone(),
+                status: m.status,
+                last_error: m.last_error.clone(),
+            })
+            .collect();
+        summary.sort_by(|a, b| a.short_url.cmp(&b.short_url));
+        summary
+    }
+
+    /// Stop all refresh tasks and log the final state of each mapping
+    pub async fn shutdown(&self) {
+        info!("Shutting down mapping manager");
+
+        let mut stopped = 0;
+        for (_, handle) in self.tasks.write().await.drain() {
+            handle.abort();
+            stopped += 1;
+        }
+        info!("Stopped {} refresh task(s)", stopped);
+
+        for entry in self.summary().await {
+            info!(
+                mapping_id = %entry.id,
+                short_url = %entry.short_url,
+                status = %entry.status,
+                last_error = entry.last_error.as_deref().unwrap_or(""),
+                "Final mapping state"
+            );
+        }
+    }
+
+    /// Resolve the clients for a credentials profile from the current registry
+    async fn resolve_clients(&self, profile: Option<&str>) -> Result<ClientSet> {
+        self.clients.read().await.resolve(profile)
+    }
+
+    /// Start a refresh task for a mapping, refreshing immediately
+    async fn start_refresh_task(&self, mapping: Mapping) -> Result<()> {
+        self.start_refresh_task_after(mapping, Duration::ZERO).await
+    }
+
+    /// Start a refresh task for a mapping whose first refresh runs after `delay`
+    async fn start_refresh_task_after(&self, mapping: Mapping, delay: Duration) -> Result<()> {
+        let id = mapping.id;
+        let mappings = Arc::clone(&self.mappings);
+        // Fail fast if the mapping's credentials profile is unknown
+        self.resolve_clients(mapping.credentials_profile.as_deref())
+            .await?;
+        let clients = Arc::clone(&self.clients);
+        let logs = self.logs.clone();
+        let grace_period = self.grace_period;
+        let gate = refresh_gate(&self.refresh_gates, id).await;
+        let mut fast_retries = self.initial_retry_backoff.clone().into_iter();
+        let failure_backoff = self.failure_retry_backoff.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut mapping = mapping;
+            let presign_duration = mapping.effective_presign_duration();
+            let period = mapping.refresh_interval().max(MIN_REFRESH_INTERVAL);
+            let mut interval = interval_at(Instant::now() + delay, period);
+            let mut succeeded = false;
+            // Failed refreshes since the last success
+            let mut failures = 0;
+
+            loop {
+                // The first tick completes after `delay`, performing the initial refresh
+                interval.tick().await;
+                let mut last_refresh = gate.lock().await;
+
+                // A task that outlives its mapping stops instead of refreshing
+                if !mappings.read().await.contains_key(&id) {
+                    info!("Mapping {} was removed; stopping its refresh task", id);
+                    break;
+                }
+
+                if mapping.override_expired(Utc::now()) {
+                    info!(
+                        "Override of {} expired, reverting to {}",
+                        id, mapping.s3_url
+                    );
+                    mapping.clear_override();
+                    if let Some(stored) = mappings.write().await.get_mut(&id) {
+                        stored.clear_override();
+                    }
+                }
+
+                // Resolve clients on every refresh so reloaded credentials take effect
+                let resolved = clients
+                    .read()
+                    .await
+                    .resolve(mapping.credentials_profile.as_deref());
+                let outcome = match resolved {
+                    Ok(set) => {
+                        refresh_url(
+                            &mapping,
+                            set.s3.as_ref(),
+                            set.route53.as_ref(),
+                            &mappings,
+                            presign_duration,
+                            grace_period,
+                            &logs,
+                        )
+                        .await
+                    }
+                    Err(e) => {
+                        error!("Cannot refresh mapping {}: {}", mapping.id, e);
+                        RefreshOutcome::Failed
+                    }
+                };
+                *last_refresh = Some(Instant::now());
+                drop(last_refresh);
+
+                // Retry sooner than a full interval so a transient failure
+                // doesn't leave the mapping down for hours: before the first
+                // success through the initial backoff, then with the capped
+                // failure backoff until a refresh succeeds again
+                let retry = if outcome == RefreshOutcome::Refreshed {
+                    succeeded = true;
+                    failures = 0;
+                    None
+                } else if let RefreshOutcome::Deferred { .. } = outcome {
+                    // Not a failure: nothing to retry until the window opens
+                    None
+                } else if !succeeded {
+                    fast_retries.next()
+                } else {
+                    failures += 1;
+                    failure_backoff
+                        .get(failures - 1)
+                        .or(failure_backoff.last())
+                        .copied()
+                };
+                if let Some(delay) = retry {
+                    if succeeded {
+                        info!("Retrying refresh of {} in {:?}", mapping.id, delay);
+                    } else {
+                        info!("Retrying first refresh of {} in {:?}", mapping.id, delay);
+                    }
+                }
+                // Revert an override as soon as it expires
+                let override_left = mapping
+                    .override_s3_url
+                    .as_ref()
+                    .and(mapping.override_expires_at)
+                    .map(|expires_at| (expires_at - Utc::now()).to_std().unwrap_or_default());
+                // Try again as soon as a deferring window opens
+                let window_opens = match outcome {
+                    RefreshOutcome::Deferred { opens } => {
+                        opens.map(|opens| (opens - Utc::now()).to_std().unwrap_or_default())
+                    }
+                    _ => None,
+                };
+                let next = [retry, override_left, window_opens]
+                    .into_iter()
+                    .flatten()
+                    .min();
+                if let Some(delay) = next.filter(|delay| *delay < mapping.refresh_interval()) {
+                    interval.reset_after(delay);
+                }
+            }
+        });
+
+        // Registered under the tasks lock so a delete racing this start
+        // either finds the task to abort or has already removed the mapping
+        let mut tasks = self.tasks.write().await;
+        if !self.mappings.read().await.contains_key(&id) {
+            info!(
+                "Mapping {} was removed while starting; not refreshing it",
+                id
+            );
+            handle.abort();
+            return Ok(());
+        }
+        tasks.insert(id, handle);
+
+        Ok(())
+    }
+
+    /// Publish `s3_url` for a mapping instead of its own object until `ttl`
+    /// passes, without changing its configuration
+    #[instrument(skip(self))]
+    pub async fn set_override(&self, id: &Uuid, s3_url: String, ttl: Duration) -> Result<Mapping> {
+        if ttl.is_zero() || ttl > MAX_OVERRIDE_DURATION {
+            anyhow::bail!(
+                "Override duration must be between 1 and {} seconds",
+                MAX_OVERRIDE_DURATION.as_secs()
+            );
+        }
+        let mut mapping = self.get_mapping(id).await.context("Mapping not found")?;
+        mapping.override_s3_url = Some(s3_url);
+        mapping.override_expires_at = Some(
+            chrono::Duration::from_std(ttl)
+                .ok()
+                .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+                .context("Override duration is too long")?,
+        );
+        mapping.presign_target().context("Invalid override")?;
+
+        // Restarting the refresh task publishes the override right away
+        self.update_mapping(id, mapping).await?;
+        self.get_mapping(id).await.context("Mapping not found")
+    }
+
+    /// Drop a mapping's override, publishing its own object again
+    #[instrument(skip(self))]
+    pub async fn clear_override(&self, id: &Uuid) -> Result<Mapping> {
+        let mut mapping = self.get_mapping(id).await.context("Mapping not found")?;
+        mapping.clear_override();
+        self.update_mapping(id, mapping).await?;
+        self.get_mapping(id).await.context("Mapping not found")
+    }
+
+    /// Stop a refresh task for a mapping
+    async fn stop_refresh_task(&self, id: &Uuid) {
+        let mut tasks = self.tasks.write().await;
+        if let Some(handle) = tasks.remove(id) {
+            handle.abort();
+        }
+    }
+}
+
+/// Check a mapping's object, or for a prefix some object under it, can be read
+async fn check_readable(mapping: &Mapping, s3_client: &dyn StorageBackend) -> Result<()> {
+    let (bucket, key) = mapping.presign_target()?;
+    let options = mapping.presign_options();
+
+    if key.ends_with('/') {
+        if !s3_client.prefix_exists(&bucket, &key, &options).await? {
+            return Err(ObjectNotFound {
+                s3_url: format!("s3://{}/{}", bucket, key),
+            }
+            .into());
+        }
+    } else {
+        s3_client.head_object(&bucket, &key, &options).await?;
+    }
+    Ok(())
+}
+
+/// The mapping to presign at refresh: the mapping itself unless it has
+/// fallbacks, otherwise the first of its targets whose object is readable
+async fn select_target(mapping: &Mapping, s3_client: &dyn StorageBackend) -> Result<Mapping> {
+    let mut targets = mapping.failover_targets();
+    if targets.len() == 1 {
+        return Ok(targets.remove(0));
+    }
+    let mut errors = Vec::new();
+    for target in targets {
+        match check_readable(&target, s3_client).await {
+            Ok(()) => return Ok(target),
+            Err(e) => {
+                warn!(mapping_id = %mapping.id, "S3 target {} of {} is unavailable: {:#}", target.s3_url, mapping.id, e);
+                errors.push(format!("{}: {:#}", target.s3_url, e));
+            }
+        }
+    }
+    anyhow::bail!("No S3 target is readable ({})", errors.join("; "))
+}
+
+/// Time an access probe, failing it after `ACCESS_CHECK_TIMEOUT`
+async fn access_check(probe: impl std::future::Future<Output = Result<()>>) -> AccessCheck {
+    let started = Instant::now();
+    let result = tokio::time::timeout(ACCESS_CHECK_TIMEOUT, probe).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(Ok(())) => AccessCheck {
+            ok: true,
+            error: None,
+            latency_ms,
+        },
+        Ok(Err(e)) => AccessCheck {
+            ok: false,
+            error: Some(format!("{:#}", e)),
+            latency_ms,
+        },
+        Err(_) => AccessCheck {
+            ok: false,
+            error: Some(format!("Timed out after {:?}", ACCESS_CHECK_TIMEOUT)),
+            latency_ms,
+        },
+    }
+}
+
+/// Wrap an error from an AWS check that couldn't be completed
+fn aws_check_failed(err: anyhow::Error) -> anyhow::Error {
+    let transient = err.is::<TransientError>();
+    err.context(AwsCheckFailed { transient })
+}
+
+/// Short URLs and slugs in use, with the mapping using each
+#[derive(Debug, Default)]
+struct Claims {
+    short_urls: HashMap<String, Uuid>,
+    slugs: HashMap<String, Uuid>,
+}
+
+impl Claims {
+    fn of<'a>(mappings: impl IntoIterator<Item = &'a Mapping>) -> Self {
+        let mut claims = Claims::default();
+        for mapping in mappings {
+            claims.claim(mapping);
+        }
+        claims
+    }
+
+    /// Record a mapping's short URL, and its slug unless still to be derived
+    fn claim(&mut self, mapping: &Mapping) {
+        self.short_urls
+            .insert(mapping.short_url.clone(), mapping.id);
+        if !mapping.slug.is_empty() {
+            self.slugs.insert(mapping.slug.clone(), mapping.id);
+        }
+    }
+}
+
+/// Fail with `ShortUrlTaken` if a mapping other than `id` uses `short_url`
+fn check_short_url_free(
+    mappings: &HashMap<Uuid, Mapping>,
+    short_url: &str,
+    id: Uuid,
+) -> Result<(), ShortUrlTaken> {
+    match mappings
+        .values()
+        .find(|m| m.id != id && m.short_url == short_url)
+    {
+        Some(existing) => Err(ShortUrlTaken {
+            short_url: short_url.to_string(),
+            id: existing.id,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Fail with `SlugTaken` if a mapping other than `id` uses `slug`
+fn check_slug_free(slugs: &HashMap<String, Uuid>, slug: &str, id: Uuid) -> Result<(), SlugTaken> {
+    match slugs.get(slug) {
+        Some(existing) if *existing != id => Err(SlugTaken {
+            slug: slug.to_string(),
+            id: *existing,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Slug derived from `short_url` that no other mapping uses, numbered from
+/// `-2` on if the plain one is taken; the base is shortened to leave room
+/// for the number
+fn unique_slug(slugs: &HashMap<String, Uuid>, short_url: &str, id: Uuid) -> String {
+    let base = Some(derive_slug(short_url))
+        .filter(|slug| validate_slug(slug).is_ok())
+        .unwrap_or_else(|| "mapping".to_string());
+    std::iter::once(base.clone())
+        .chain((2u64..).map(|n| {
+            let suffix = format!("-{}", n);
+            let room = MAX_SLUG_LENGTH.saturating_sub(suffix.len());
+            format!(
+                "{}{}",
+                base[..base.len().min(room)].trim_end_matches('-'),
+                suffix
+            )
+        }))
+        .find(|slug| check_slug_free(slugs, slug, id).is_ok())
+        .expect("slug candidates are endless")
+}
+
+/// Give a mapping without a slug, or one whose slug is taken, a fresh one,
+/// and record it
+fn claim_slug(slugs: &mut HashMap<String, Uuid>, mapping: &mut Mapping) {
+    if mapping.slug.is_empty() || check_slug_free(slugs, &mapping.slug, mapping.id).is_err() {
+        mapping.slug = unique_slug(slugs, &mapping.short_url, mapping.id);
+    }
+    slugs.insert(mapping.slug.clone(), mapping.id);
+}
+
+/// Refresh lock for a mapping, created on first use
+async fn refresh_gate(gates: &RwLock<HashMap<Uuid, RefreshGate>>, id: Uuid) -> RefreshGate {
+    if let Some(gate) = gates.read().await.get(&id) {
+        return Arc::clone(gate);
+    }
+    Arc::clone(gates.write().await.entry(id).or_default())
+}
+
+/// How a refresh ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RefreshOutcome {
+    /// A fresh URL was published
+    Refreshed,
+    /// Outside the allowed window; nothing was published, and the next
+    /// attempt should be when the window opens
+    Deferred {
+        opens: Option<DateTime<Utc>>,
+    },
+    Failed,
+}
+
+/// Refresh the presigned URL and update Route53
+#[instrument(skip_all, fields(mapping_id = %mapping.id, short_url = %mapping.short_url))]
+async fn refresh_url(
+    mapping: &Mapping,
+    s3_client: &dyn StorageBackend,
+    route53_client: &dyn DnsBackend,
+    mappings: &Arc<RwLock<HashMap<Uuid, Mapping>>>,
+    presign_duration: Duration,
+    grace_period: Duration,
+    logs: &LogStore,
+) -> RefreshOutcome {
+    info!(mapping_id = %mapping.id, "Refreshing presigned URL for {}", mapping.id);
+
+    // The current record stays published until the allowed window opens
+    if let Some(window) = mapping
+        .allowed_window
+        .as_ref()
+        .filter(|_| mapping.dns_update_deferred(Utc::now()))
+    {
+        info!(
+            mapping_id = %mapping.id,
+            "Deferring DNS update for {} until its allowed window ({})",
+            mapping.id, window
+        );
+        logs.record(RefreshLog {
+            mapping_id: mapping.id,
+            timestamp: Utc::now(),
+            success: true,
+            event: RefreshEvent::Deferred,
+            message: format!(
+                "Outside the allowed window ({}); DNS update deferred",
+                window
+            ),
+        })
+        .await;
+        return RefreshOutcome::Deferred {
+            opens: window.next_open(Utc::now()),
+        };
+    }
+
+    let started = Instant::now();
+
+    let result = async {
+        let target = select_target(mapping, s3_client)
+            .await
+            .map_err(|e| (RefreshEvent::PresignFailed, e))?;
+        // Parse S3 URL
+        let (bucket, key) = target
+            .presign_target()
+            .map_err(|e| (RefreshEvent::PresignFailed, e))?;
+
+        // Generate new presigned URL
+        let presigned_url = s3_client
+            .generate_presigned_url(&bucket, &key, presign_duration, &target.presign_options())
+            .await
+            .map_err(|e| (RefreshEvent::PresignFailed, e))?;
+        // Temporary credentials cut the URL's real lifetime short
+        let warning = credentials_warning(presign_duration, s3_client.credentials_lifetime().await);
+
+        // Update Route53 DNS record, unless it is managed elsewhere
+        if mapping.manage_dns {
+            route53_client
+                .update_dns_record(
+                    &mapping.hosted_zone_id,
+                    &mapping.short_url,
+                    &presigned_url,
+                    mapping.dns_record(),
+                    Some(mapping.id),
+                )
+                .await
+                .map_err(|e| (RefreshEvent::DnsFailed, e))?;
+        }
+
+        Ok::<_, (RefreshEvent, anyhow::Error)>((warning, target.s3_url))
+    }
+    .await;
+
+    // Update mapping status, unless it was deleted mid-refresh
+    let succeeded = result.is_ok();
+    let mut mappings = mappings.write().await;
+    if let Some(stored_mapping) = mappings.get_mut(&mapping.id) {
+        // Presign and Route53 time, for latency percentiles
+        logs.latencies().record(mapping.id, started.elapsed());
+
+        let previous_status = stored_mapping.status;
+        let log = match result {
+            Ok((warning, served)) => {
+                let failed_over = mapping.fallback_s3_urls.contains(&served);
+                stored_mapping.serving_s3_url = Some(served.clone());
+                stored_mapping.last_refresh = Some(Utc::now());
+                stored_mapping.next_refresh = Some(
+                    Utc::now() + chrono::Duration::from_std(mapping.refresh_interval()).unwrap(),
+                );
+                stored_mapping.set_status(MappingStatus::Active);
+                stored_mapping.last_error = None;
+                stored_mapping.consecutive_failures = 0;
+
+                let log = RefreshLog {
+                    mapping_id: mapping.id,
+                    timestamp: Utc::now(),
+                    success: true,
+                    event: RefreshEvent::Success,
+                    message: match (&warning, failed_over) {
+                        (Some(warning), _) => {
+                            format!("Successfully refreshed presigned URL, but {}", warning)
+                        }
+                        (None, true) => format!(
+                            "Successfully refreshed presigned URL from fallback {}",
+                            served
+                        ),
+                        (None, false) => "Successfully refreshed presigned URL".to_string(),
+                    },
+                };
+                logs.record(log.clone()).await;
+
+                info!(mapping_id = %mapping.id, "Successfully refreshed presigned URL for {}", mapping.id);
+                if let Some(warning) = warning {
+                    warn!(mapping_id = %mapping.id, "Presigned URL for {}: {}", mapping.id, warning);
+                }
+                log
+            }
+            Err((event, e)) => {
+                let error_msg = format!("Failed to refresh URL: {}", e);
+                // Permissions on a new bucket may still be propagating
+                let in_grace = stored_mapping.last_refresh.is_none()
+                    && chrono::Duration::from_std(grace_period)
+                        .is_ok_and(|grace| Utc::now() - stored_mapping.created_at < grace);
+                stored_mapping.set_status(if in_grace {
+                    MappingStatus::Pending
+                } else {
+                    MappingStatus::Error
+                });
+                stored_mapping.last_error = Some(error_msg.clone());
+                stored_mapping.consecutive_failures =
+                    stored_mapping.consecutive_failures.saturating_add(1);
+
+                let log = RefreshLog {
+                    mapping_id: mapping.id,
+                    timestamp: Utc::now(),
+                    success: false,
+                    event,
+                    message: error_msg.clone(),
+                };
+                logs.record(log.clone()).await;
+
+                error!(mapping_id = %mapping.id, "Failed to refresh presigned URL for {}: {}", mapping.id, e);
+                log
+            }
+        };
+
+        if let Some(transition) = Transition::between(previous_status, stored_mapping.status) {
+            logs.notifiers().dispatch(Notification {
+                mapping_id: mapping.id,
+                short_url: mapping.short_url.clone(),
+                transition,
+                event: log.event,
+                message: log.message,
+                timestamp: log.timestamp,
+            });
+        }
+    }
+    if succeeded {
+        RefreshOutcome::Refreshed
+    } else {
+        RefreshOutcome::Failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route53::{MockDnsBackend, DEFAULT_TTL};
+    use crate::s3::{HeadInfo, PresignOptions, MAX_PRESIGN_DURATION, MIN_PRESIGN_DURATION};
+    use async_trait::async_trait;
+
+    /// Skips the S3 and Route53 checks, which these tests aren't about
+    const UNVERIFIED: AddOptions = AddOptions {
+        initial_delay: None,
+        skip_validation: true,
+        paused: false,
+    };
+
+    /// Storage double presigning predictable URLs, or failing every presign
+    /// with `presign_error`
+    #[derive(Default)]
+    struct TestStorage {
+        presign_error: Option<&'static str>,
+    }
+
+    #[async_trait]
+    impl StorageBackend for TestStorage {
+        async fn generate_presigned_url(
+            &self,
+            bucket: &str,
+            key: &str,
+            _duration: Duration,
+            _options: &PresignOptions,
+        ) -> Result<String> {
+            if let Some(error) = self.presign_error {
+                anyhow::bail!("{}", error);
+            }
+            Ok(format!(
+                "https://{}.s3.amazonaws.com/{}?X-Amz-Signature=test",
+                bucket, key
+            ))
+        }
+
+        async fn head_object(
+            &self,
+            _bucket: &str,
+            _key: &str,
+            _options: &PresignOptions,
+        ) -> Result<HeadInfo> {
+            Ok(HeadInfo {
+                content_length: Some(1024),
+                last_modified: None,
+            })
+        }
+
+        async fn prefix_exists(
+            &self,
+            _bucket: &str,
+            _prefix: &str,
+            _options: &PresignOptions,
+        ) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    /// DNS double accepting every update
+    fn accepting_dns() -> MockDnsBackend {
+        let mut dns = MockDnsBackend::new();
+        dns.expect_get_record().returning(|_, _| Ok(None));
+        dns.expect_get_zone_name()
+            .returning(|_| Ok(Some("example.com.".to_string())));
+        dns.expect_validate_zone().returning(|_, _| Ok(()));
+        dns.expect_update_dns_record()
+            .returning(|_, _, _, _, _| Ok(()));
+        dns.expect_check_access().returning(|| Ok(()));
+        dns
+    }
+
+    /// A manager whose refresh tasks run against test doubles, not AWS
+    async fn test_manager() -> MappingManager {
+        let (manager, _log_rx) = MappingManager::new(TestStorage::default(), accepting_dns());
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_effective_config_reports_clamped_presign_duration() {
+        let manager = test_manager().await;
+
+        let mut mapping = Mapping::new(
+            "s3://test-bucket/test-key".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        mapping.presign_duration_secs = 30 * 24 * 60 * 60; // 30 days
+        let id = manager.add_mapping_with(mapping, UNVERIFIED).await.unwrap();
+
+        let effective = manager.effective_config(&id).await.unwrap();
+        assert_eq!(
+            effective.presign_duration_secs,
+            MAX_PRESIGN_DURATION.as_secs()
+        );
+        assert_eq!(effective.dns_ttl_secs, DEFAULT_TTL);
+
+        // Stored capped, as are updates
+        let mut stored = manager.get_mapping(&id).await.unwrap();
+        assert_eq!(stored.presign_duration_secs, MAX_PRESIGN_DURATION.as_secs());
+        stored.presign_duration_secs = 10 * 24 * 60 * 60;
+        manager.update_mapping(&id, stored).await.unwrap();
+        assert_eq!(
+            manager
+                .get_mapping(&id)
+                .await
+                .unwrap()
+                .presign_duration_secs,
+            MAX_PRESIGN_DURATION.as_secs()
+        );
+
+        manager.delete_mapping(&id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_short_url_must_be_unique() {
+        let manager = test_manager().await;
+        let mapping = |short_url: &str| {
+            Mapping::new(
+                "s3://test-bucket/test-key".to_string(),
+                short_url.to_string(),
+                "Z1234567890ABC".to_string(),
+            )
+        };
+        let first = manager
+            .add_mapping_with(mapping("files.example.com"), UNVERIFIED)
+            .await
+            .unwrap();
+        let second = manager
+            .add_mapping_with(mapping("other.example.com"), UNVERIFIED)
+            .await
+            .unwrap();
+
+        // Compared after normalization
+        let err = manager
+            .add_mapping_with(mapping("Files.Example.com."), UNVERIFIED)
+            .await
+            .unwrap_err();
+        let taken = err.downcast_ref::<ShortUrlTaken>().unwrap();
+        assert_eq!(taken.short_url, "files.example.com");
+        assert_eq!(taken.id, first);
+        assert_eq!(manager.list_mappings().await.len(), 2);
+
+        // A mapping keeps its own host, but can't take another's
+        let mut stored = manager.get_mapping(&first).await.unwrap();
+        stored.notes = Some("Quarterly report".to_string());
+        manager.update_mapping(&first, stored).await.unwrap();
+
+        let mut stored = manager.get_mapping(&second).await.unwrap();
+        stored.short_url = "files.example.com".to_string();
+        let err = manager.update_mapping(&second, stored).await.unwrap_err();
+        assert!(err.downcast_ref::<ShortUrlTaken>().is_some());
+        assert_eq!(
+            manager.get_mapping(&second).await.unwrap().short_url,
+            "other.example.com"
+        );
+
+        manager.delete_mapping(&first).await.unwrap();
+        manager.delete_mapping(&second).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_presign_failure_logs_presign_failed_event() {
+        let s3_client = TestStorage {
+            presign_error: Some("AccessDenied"),
+        };
+        // A failed presign leaves the record alone
+        let mut route53_client = MockDnsBackend::new();
+        route53_client.expect_update_dns_record().times(0);
+        let (log_tx, mut log_rx) = mpsc::unbounded_channel();
+        let logs = LogStore::new(log_tx);
+
+        let mapping = Mapping::new(
+            "s3://test-bucket/test-key".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        let mappings = Arc::new(RwLock::new(HashMap::from([(mapping.id, mapping.clone())])));
+
+        refresh_url(
+            &mapping,
+            &s3_client,
+            &route53_client,
+            &mappings,
+            Duration::from_secs(3600),
+            Duration::ZERO,
+            &logs,
+        )
+        .await;
+
+        let log = log_rx.recv().await.unwrap();
+        assert!(!log.success);
+        assert_eq!(log.event, RefreshEvent::PresignFailed);
+        assert!(log.message.contains("AccessDenied"));
+    }
+
+    #[tokio::test]
+    async fn test_replace_clients_swaps_client_instances() {
+        let manager = test_manager().await;
+        let before = manager.client_registry().await.resolve(None).unwrap();
+
+        manager
+            .replace_clients(ClientRegistry::new(TestStorage::default(), accepting_dns()))
+            .await;
+
+        let after = manager.client_registry().await.resolve(None).unwrap();
+        assert!(!Arc::ptr_eq(&before.s3, &after.s3));
+        assert!(!Arc::ptr_eq(&before.route53, &after.route53));
+    }
+
+    #[tokio::test]
+    async fn test_presign_duration_minimum_enforced() {
+        let manager = test_manager().await;
+
+        let mut mapping = Mapping::new(
+            "s3://test-bucket/test-key".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        mapping.presign_duration_secs = 0;
+        let err = manager
+            .add_mapping_with(mapping.clone(), UNVERIFIED)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("at least"));
+
+        // The shortest usable duration, leaving room for a 1s refresh interval
+        mapping.presign_duration_secs = MIN_PRESIGN_DURATION.as_secs() + 1;
+        mapping.refresh_interval_secs = 1;
+        let id = manager
+            .add_mapping_with(mapping.clone(), UNVERIFIED)
+            .await
+            .unwrap();
+
+        mapping.presign_duration_secs = 0;
+        assert!(manager.update_mapping(&id, mapping).await.is_err());
+
+        manager.delete_mapping(&id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_summary_has_one_entry_per_mapping() {
+        let manager = test_manager().await;
+
+        let mut ids = Vec::new();
+        for short_url in ["b.example.com", "a.example.com"] {
+            let mapping = Mapping::new(
+                "s3://test-bucket/test-key".to_string(),
+                short_url.to_string(),
+                "Z1234567890ABC".to_string(),
+            );
+            ids.push(manager.add_mapping_with(mapping, UNVERIFIED).await.unwrap());
+        }
+        manager.pause_mapping(&ids[0]).await.unwrap();
+
+        let summary = manager.summary().await;
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].short_url, "a.example.com");
+        assert_eq!(summary[0].id, ids[1]);
+        assert_eq!(summary[1].short_url, "b.example.com");
+        assert_eq!(summary[1].id, ids[0]);
+        assert_eq!(summary[1].status, MappingStatus::Paused);
+
+        manager.shutdown().await;
+        assert!(manager.tasks.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume_are_audited_in_order() {
+        let manager = test_manager().await;
+
+        let mapping = Mapping::new(
+            "s3://test-bucket/test-key".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        let id = manager.add_mapping_with(mapping, UNVERIFIED).await.unwrap();
+        manager.pause_mapping(&id).await.unwrap();
+        manager.resume_mapping(&id).await.unwrap();
+
+        let entries = manager
+            .audit(&AuditQuery {
+                mapping_id: Some(id),
+                limit: Some(2),
+            })
+            .await;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, AuditAction::Pause);
+        assert_eq!(entries[0].before, Some(MappingStatus::Active));
+        assert_eq!(entries[0].after, Some(MappingStatus::Paused));
+        assert_eq!(entries[1].action, AuditAction::Resume);
+        assert_eq!(entries[1].before, Some(MappingStatus::Paused));
+        assert_eq!(entries[1].after, Some(MappingStatus::Active));
+        assert!(entries[0].timestamp <= entries[1].timestamp);
+
+        manager.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_invalid_mappings_are_quarantined_on_load() {
+        let manager = test_manager().await;
+
+        let new_mapping = |s3_url: &str, short_url: &str| {
+            Mapping::new(
+                s3_url.to_string(),
+                short_url.to_string(),
+                "Z1234567890ABC".to_string(),
+            )
+        };
+        let good = new_mapping("s3://test-bucket/test-key", "good.example.com");
+        let bad_url = new_mapping("https://test-bucket/test-key", "bad-url.example.com");
+        let mut bad_duration = new_mapping("s3://test-bucket/test-key", "bad-duration.example.com");
+        bad_duration.refresh_interval_secs = 0;
+        let (good_id, bad_url_id, bad_duration_id) = (good.id, bad_url.id, bad_duration.id);
+
+        let report = manager
+            .load_mappings(vec![good, bad_url, bad_duration])
+            .await;
+        assert_eq!(report.loaded, 1);
+        assert_eq!(report.quarantined, 2);
+
+        for id in [bad_url_id, bad_duration_id] {
+            let mapping = manager.get_mapping(&id).await.unwrap();
+            assert_eq!(mapping.status, MappingStatus::Quarantined);
+            assert!(mapping.last_error.is_some());
+        }
+        assert_eq!(
+            manager.get_mapping(&good_id).await.unwrap().status,
+            MappingStatus::Active
+        );
+
+        let tasks = manager.tasks.read().await;
+        assert_eq!(tasks.len(), 1);
+        assert!(tasks.contains_key(&good_id));
+        drop(tasks);
+
+        // Quarantined mappings are only listed when asked for
+        let listed = manager
+            .list_mappings_page(ListFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(listed.mappings.len(), 1);
+        let quarantined = manager
+            .list_mappings_page(ListFilter {
+                status: Some(MappingStatus::Quarantined),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(quarantined.mappings.len(), 2);
+
+        // And can't be resumed until fixed
+        assert!(manager.resume_mapping(&bad_url_id).await.is_err());
+
+        manager.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_list_mappings_by_status_and_offset() {
+        let manager = test_manager().await;
+        let created = Utc::now() - chrono::Duration::hours(1);
+        let mut error_ids = Vec::new();
+        for i in 0..5 {
+            let mut mapping = Mapping::new(
+                "s3://test-bucket/test-key".to_string(),
+                format!("{}.example.com", i),
+                "Z1234567890ABC".to_string(),
+            );
+            mapping.created_at = created + chrono::Duration::minutes(i);
+            if i % 2 == 0 {
+                mapping.status = MappingStatus::Error;
+                error_ids.push(mapping.id);
+            }
+            manager.mappings.write().await.insert(mapping.id, mapping);
+        }
+
+        let errors = |offset, limit| ListFilter {
+            status: Some(MappingStatus::Error),
+            limit,
+            offset: Some(offset),
+            cursor: None,
+        };
+
+        // Only errored mappings, oldest first
+        let page = manager.list_mappings_page(errors(0, None)).await.unwrap();
+        let ids: Vec<Uuid> = page.mappings.iter().map(|m| m.id).collect();
+        assert_eq!(ids, error_ids);
+        assert_eq!(page.total, 3);
+        assert_eq!(page.next_offset, None);
+
+        let page = manager
+            .list_mappings_page(errors(0, Some(2)))
+            .await
+            .unwrap();
+        assert_eq!(page.mappings.len(), 2);
+        assert_eq!(page.next_offset, Some(2));
+
+        // The last page is short and has no successor
+        let page = manager
+            .list_mappings_page(errors(2, Some(2)))
+            .await
+            .unwrap();
+        assert_eq!(page.mappings.len(), 1);
+        assert_eq!(page.mappings[0].id, error_ids[2]);
+        assert_eq!(page.next_offset, None);
+
+        // A page that ends exactly at the last mapping has no successor either
+        let page = manager
+            .list_mappings_page(errors(1, Some(2)))
+            .await
+            .unwrap();
+        assert_eq!(page.mappings.len(), 2);
+        assert_eq!(page.next_offset, None);
+
+        // Past the end
+        let page = manager
+            .list_mappings_page(errors(3, Some(2)))
+            .await
+            .unwrap();
+        assert!(page.mappings.is_empty());
+        assert_eq!(page.total, 3);
+
+        let both = ListFilter {
+            cursor: Some(String::new()),
+            ..errors(0, None)
+        };
+        assert!(manager.list_mappings_page(both).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_task_for_removed_mapping_is_not_registered() {
+        let manager = test_manager().await;
+
+        // As when a delete lands between storing a mapping and starting its task
+        let mapping = Mapping::new(
+            "s3://test-bucket/test-key".to_string(),
+            "gone.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        manager.start_refresh_task(mapping).await.unwrap();
+        assert!(manager.tasks.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_gc_repairs_tasks() {
+        let manager = test_manager().await;
+
+        let mut ids = Vec::new();
+        for short_url in ["a.example.com", "b.example.com"] {
+            let mapping = Mapping::new(
+                "s3://test-bucket/test-key".to_string(),
+                short_url.to_string(),
+                "Z1234567890ABC".to_string(),
+            );
+            ids.push(manager.add_mapping_with(mapping, UNVERIFIED).await.unwrap());
+        }
+
+        // A task for a mapping that doesn't exist, and an active mapping without one
+        let orphan = Uuid::new_v4();
+        manager
+            .tasks
+            .write()
+            .await
+            .insert(orphan, tokio::spawn(std::future::pending()));
+        manager.tasks.write().await.remove(&ids[0]).unwrap().abort();
+
+        let report = manager.gc().await;
+        assert_eq!(report.aborted, 1);
+        assert_eq!(report.started, 1);
+
+        let tasks = manager.tasks.read().await;
+        assert!(!tasks.contains_key(&orphan));
+        assert!(tasks.contains_key(&ids[0]));
+        assert!(tasks.contains_key(&ids[1]));
+        drop(tasks);
+
+        // Nothing left to repair
+        let report = manager.gc().await;
+        assert_eq!((report.aborted, report.started), (0, 0));
+
+        manager.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_first_refresh_failure_within_grace_stays_pending() {
+        let s3_client = TestStorage {
+            presign_error: Some("AccessDenied"),
+        };
+        let mut route53_client = MockDnsBackend::new();
+        route53_client.expect_update_dns_record().times(0);
+        let (log_tx, _log_rx) = mpsc::unbounded_channel();
+        let logs = LogStore::new(log_tx);
+        let grace = Duration::from_secs(60 * 60);
+
+        let mut mapping = Mapping::new(
+            "s3://test-bucket/test-key".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        let mappings = Arc::new(RwLock::new(HashMap::from([(mapping.id, mapping.clone())])));
+
+        let duration = Duration::from_secs(3600);
+        refresh_url(
+            &mapping,
+            &s3_client,
+            &route53_client,
+            &mappings,
+            duration,
+            grace,
+            &logs,
+        )
+        .await;
+        let stored = mappings.read().await[&mapping.id].clone();
+        assert_eq!(stored.status, MappingStatus::Pending);
+        assert!(stored.last_error.is_some());
+
+        // Once the grace window has passed, failures mark the mapping as errored
+        mapping.created_at = Utc::now() - chrono::Duration::hours(2);
+        mappings.write().await.insert(mapping.id, mapping.clone());
+        refresh_url(
+            &mapping,
+            &s3_client,
+            &route53_client,
+            &mappings,
+            duration,
+            grace,
+            &logs,
+        )
+        .await;
+        assert_eq!(
+            mappings.read().await[&mapping.id].status,
+            MappingStatus::Error
+        );
+    }
+
+    #[test]
+    fn test_numbered_slugs_fit_the_length_limit() {
+        let short_url = format!("{}.example.com", "a".repeat(70));
+        let base = derive_slug(&short_url);
+        assert_eq!(base.len(), MAX_SLUG_LENGTH);
+
+        let mut slugs = HashMap::from([(base.clone(), Uuid::new_v4())]);
+        let second = unique_slug(&slugs, &short_url, Uuid::new_v4());
+        assert_eq!(second, format!("{}-2", &base[..MAX_SLUG_LENGTH - 2]));
+        assert!(validate_slug(&second).is_ok());
+
+        slugs.insert(second, Uuid::new_v4());
+        let third = unique_slug(&slugs, &short_url, Uuid::new_v4());
+        assert!(third.ends_with("-3"));
+        assert!(validate_slug(&third).is_ok());
     }
 }
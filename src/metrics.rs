@@ -0,0 +1,401 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use aws_sdk_cloudwatch::types::{Dimension, MetricDatum, StandardUnit};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use crate::types::{LatencyPercentiles, LatencyStats, MappingUptime};
+
+/// How often counters are pushed to CloudWatch
+pub const DEFAULT_PUBLISH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Running totals of refresh outcomes, shared between the log store and the
+/// publisher
+#[derive(Debug, Clone, Default)]
+pub struct RefreshCounters {
+    succeeded: Arc<AtomicU64>,
+    failed: Arc<AtomicU64>,
+}
+
+/// Refresh outcomes counted since the previous snapshot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RefreshCounts {
+    pub succeeded: u64,
+    pub failed: u64,
+}
+
+impl RefreshCounters {
+    pub fn record(&self, success: bool) {
+        let counter = if success {
+            &self.succeeded
+        } else {
+            &self.failed
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Read and reset the counters
+    pub fn take(&self) -> RefreshCounts {
+        RefreshCounts {
+            succeeded: self.succeeded.swap(0, Ordering::Relaxed),
+            failed: self.failed.swap(0, Ordering::Relaxed),
+        }
+    }
+
+    /// Add taken counts back, e.g. when publishing them failed
+    pub fn restore(&self, counts: RefreshCounts) {
+        self.succeeded
+            .fetch_add(counts.succeeded, Ordering::Relaxed);
+        self.failed.fetch_add(counts.failed, Ordering::Relaxed);
+    }
+}
+
+/// Growth factor between latency bucket bounds; percentiles are accurate to
+/// within this factor
+const LATENCY_BUCKET_GROWTH: f64 = 1.05;
+
+/// Latency buckets: 1ms up to about an hour, anything longer lands in the last
+const LATENCY_BUCKETS: usize = 310;
+
+/// Streaming latency histogram with logarithmic buckets, so percentiles are
+/// estimated in constant memory however many samples are recorded
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    buckets: Vec<u64>,
+    count: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; LATENCY_BUCKETS],
+            count: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        let ms = latency.as_secs_f64() * 1000.0;
+        let bucket = if ms <= 1.0 {
+            0
+        } else {
+            (ms.ln() / LATENCY_BUCKET_GROWTH.ln()).ceil() as usize
+        };
+        self.buckets[bucket.min(LATENCY_BUCKETS - 1)] += 1;
+        self.count += 1;
+    }
+
+    /// Estimated latency below which a fraction `q` of samples fall: the
+    /// upper bound of the bucket holding that rank
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((q * self.count as f64).ceil() as u64).clamp(1, self.count);
+        let mut seen = 0;
+        let bucket = self.buckets.iter().position(|&n| {
+            seen += n;
+            seen >= rank
+        })?;
+        let ms = LATENCY_BUCKET_GROWTH.powi(bucket as i32);
+        Some(Duration::from_secs_f64(ms / 1000.0))
+    }
+
+    pub fn percentiles(&self) -> LatencyPercentiles {
+        let ms = |q| self.quantile(q).map(|d| d.as_secs_f64() * 1000.0);
+        LatencyPercentiles {
+            count: self.count,
+            p50_ms: ms(0.50),
+            p95_ms: ms(0.95),
+            p99_ms: ms(0.99),
+        }
+    }
+}
+
+/// Refresh durations across all mappings and per mapping
+#[derive(Debug, Clone, Default)]
+pub struct RefreshLatencies {
+    inner: Arc<Mutex<LatencyHistograms>>,
+}
+
+#[derive(Debug, Default)]
+struct LatencyHistograms {
+    global: LatencyHistogram,
+    mappings: HashMap<Uuid, LatencyHistogram>,
+}
+
+impl RefreshLatencies {
+    /// Record how long one refresh of a mapping took
+    pub fn record(&self, mapping_id: Uuid, latency: Duration) {
+        let mut histograms = self.inner.lock().unwrap();
+        histograms.global.record(latency);
+        histograms
+            .mappings
+            .entry(mapping_id)
+            .or_default()
+            .record(latency);
+    }
+
+    /// Drop a mapping's histogram; its samples stay in the global one
+    pub fn remove(&self, mapping_id: &Uuid) {
+        self.inner.lock().unwrap().mappings.remove(mapping_id);
+    }
+
+    pub fn stats(&self) -> LatencyStats {
+        let histograms = self.inner.lock().unwrap();
+        LatencyStats {
+            global: histograms.global.percentiles(),
+            mappings: histograms
+                .mappings
+                .iter()
+                .map(|(id, histogram)| (*id, histogram.percentiles()))
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+}
+
+/// Escape a Prometheus label value
+fn label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render per-mapping health and status-duration gauges in the Prometheus
+/// text exposition format
+pub fn prometheus_text(uptimes: &[MappingUptime]) -> String {
+    let labels = |uptime: &MappingUptime| {
+        format!(
+            "mapping_id=\"{}\",short_url=\"{}\",status=\"{}\"",
+            uptime.mapping_id,
+            label_value(&uptime.short_url),
+            uptime.status.to_string().to_lowercase()
+        )
+    };
+
+    let mut text = String::new();
+    text.push_str("# HELP s3_buddy_mapping_healthy Whether the mapping is active (1) or not (0)\n");
+    text.push_str("# TYPE s3_buddy_mapping_healthy gauge\n");
+    for uptime in uptimes {
+        text.push_str(&format!(
+            "s3_buddy_mapping_healthy{{{}}} {}\n",
+            labels(uptime),
+            u8::from(uptime.healthy)
+        ));
+    }
+    text.push_str(
+        "# HELP s3_buddy_mapping_status_seconds Seconds the mapping has been in its current status\n",
+    );
+    text.push_str("# TYPE s3_buddy_mapping_status_seconds gauge\n");
+    for uptime in uptimes {
+        text.push_str(&format!(
+            "s3_buddy_mapping_status_seconds{{{}}} {}\n",
+            labels(uptime),
+            uptime.status_secs
+        ));
+    }
+    text
+}
+
+/// Build the CloudWatch data points for a counts snapshot
+pub fn metric_data(counts: &RefreshCounts) -> Vec<MetricDatum> {
+    let service = Dimension::builder()
+        .name("Service")
+        .value("s3-buddy")
+        .build();
+
+    [
+        ("RefreshSuccess", counts.succeeded),
+        ("RefreshFailure", counts.failed),
+    ]
+    .into_iter()
+    .map(|(name, value)| {
+        MetricDatum::builder()
+            .metric_name(name)
+            .dimensions(service.clone())
+            .unit(StandardUnit::Count)
+            .value(value as f64)
+            .build()
+    })
+    .collect()
+}
+
+/// Destination for metric data, abstracted so publishing can run without AWS
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait MetricsPublisher: Send + Sync {
+    async fn put_metric_data(&self, namespace: &str, data: Vec<MetricDatum>) -> Result<()>;
+}
+
+/// CloudWatch client wrapper for publishing metrics
+pub struct CloudWatchClient {
+    client: aws_sdk_cloudwatch::Client,
+}
+
+impl CloudWatchClient {
+    pub fn new(client: aws_sdk_cloudwatch::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl MetricsPublisher for CloudWatchClient {
+    async fn put_metric_data(&self, namespace: &str, data: Vec<MetricDatum>) -> Result<()> {
+        self.client
+            .put_metric_data()
+            .namespace(namespace)
+            .set_metric_data(Some(data))
+            .send()
+            .await
+            .context("Failed to put CloudWatch metric data")?;
+        Ok(())
+    }
+}
+
+/// Push the counts accumulated since the last successful call; if the push
+/// fails, they are kept for the next one
+#[instrument(skip(publisher, counters))]
+pub async fn publish_once(
+    publisher: &dyn MetricsPublisher,
+    namespace: &str,
+    counters: &RefreshCounters,
+) -> Result<()> {
+    let counts = counters.take();
+    let result = publisher
+        .put_metric_data(namespace, metric_data(&counts))
+        .await;
+    if result.is_err() {
+        counters.restore(counts);
+    }
+    result
+}
+
+/// Publish refresh counters to CloudWatch every `interval`
+pub fn spawn_publisher(
+    publisher: Arc<dyn MetricsPublisher>,
+    namespace: String,
+    counters: RefreshCounters,
+    interval: Duration,
+) -> JoinHandle<()> {
+    info!("Publishing metrics to CloudWatch namespace {}", namespace);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        // The first tick completes immediately; skip it so each push covers a full interval
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = publish_once(publisher.as_ref(), &namespace, &counters).await {
+                error!("Failed to publish metrics: {:#}", e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles_are_approximate() {
+        let latencies = RefreshLatencies::default();
+        let id = Uuid::new_v4();
+        for ms in 1..=1000 {
+            latencies.record(id, Duration::from_millis(ms));
+        }
+
+        let stats = latencies.stats();
+        assert_eq!(stats.global.count, 1000);
+        let close = |actual: Option<f64>, expected: f64| {
+            let actual = actual.unwrap();
+            assert!(
+                actual >= expected && actual <= expected * LATENCY_BUCKET_GROWTH,
+                "{} not within a bucket of {}",
+                actual,
+                expected
+            );
+        };
+        close(stats.global.p50_ms, 500.0);
+        close(stats.global.p95_ms, 950.0);
+        close(stats.global.p99_ms, 990.0);
+        assert_eq!(stats.mappings[&id].p99_ms, stats.global.p99_ms);
+
+        // Outliers beyond the last bucket don't overflow it
+        let mut histogram = LatencyHistogram::default();
+        histogram.record(Duration::from_secs(86400));
+        assert!(histogram.quantile(0.5).unwrap() > Duration::from_secs(3000));
+        assert_eq!(LatencyHistogram::default().quantile(0.5), None);
+    }
+
+    #[tokio::test]
+    async fn test_publish_builds_count_datums() {
+        let counters = RefreshCounters::default();
+        for success in [true, true, true, false] {
+            counters.record(success);
+        }
+
+        let mut publisher = MockMetricsPublisher::new();
+        publisher
+            .expect_put_metric_data()
+            .withf(|namespace, data| {
+                let value = |name: &str| {
+                    data.iter()
+                        .find(|d| d.metric_name() == Some(name))
+                        .and_then(|d| d.value())
+                };
+                namespace == "S3Buddy"
+                    && data.len() == 2
+                    && value("RefreshSuccess") == Some(3.0)
+                    && value("RefreshFailure") == Some(1.0)
+                    && data.iter().all(|d| {
+                        d.unit() == Some(&StandardUnit::Count)
+                            && d.dimensions().len() == 1
+                            && d.dimensions()[0].name() == Some("Service")
+                            && d.dimensions()[0].value() == Some("s3-buddy")
+                    })
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        publish_once(&publisher, "S3Buddy", &counters)
+            .await
+            .unwrap();
+
+        // Counters are reset once published
+        assert_eq!(counters.take(), RefreshCounts::default());
+    }
+
+    #[tokio::test]
+    async fn test_failed_publish_keeps_counts() {
+        let counters = RefreshCounters::default();
+        counters.record(true);
+        counters.record(false);
+
+        let mut publisher = MockMetricsPublisher::new();
+        publisher
+            .expect_put_metric_data()
+            .times(1)
+            .returning(|_, _| Err(anyhow::anyhow!("Throttling")));
+        assert!(publish_once(&publisher, "S3Buddy", &counters)
+            .await
+            .is_err());
+
+        // Counted with whatever comes in before the next push
+        counters.record(true);
+        assert_eq!(
+            counters.take(),
+            RefreshCounts {
+                succeeded: 2,
+                failed: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use aws_sdk_sesv2::types::{Body, Content, Destination, EmailContent, Message};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, instrument};
+use uuid::Uuid;
+
+use crate::types::{MappingStatus, RefreshEvent};
+
+/// Status change worth alerting on
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transition {
+    /// A refresh failure moved the mapping into `Error`
+    Failed,
+    /// A refresh succeeded after the mapping was in `Error`
+    Recovered,
+}
+
+impl Transition {
+    /// The alert, if any, for a mapping moving from `from` to `to`
+    pub fn between(from: MappingStatus, to: MappingStatus) -> Option<Self> {
+        match (from, to) {
+            (from, MappingStatus::Error) if from != to => Some(Transition::Failed),
+            (MappingStatus::Error, MappingStatus::Active) => Some(Transition::Recovered),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Transition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Transition::Failed => write!(f, "failed"),
+            Transition::Recovered => write!(f, "recovered"),
+        }
+    }
+}
+
+/// A mapping's status transition, as sent to notifiers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub mapping_id: Uuid,
+    pub short_url: String,
+    pub transition: Transition,
+    /// Outcome of the refresh that caused the transition
+    pub event: RefreshEvent,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Notification {
+    /// One-line summary, e.g. `files.example.com failed: Failed to refresh URL: ...`
+    pub fn summary(&self) -> String {
+        format!("{} {}: {}", self.short_url, self.transition, self.message)
+    }
+}
+
+/// Destination for status transition alerts
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, event: &Notification) -> Result<()>;
+}
+
+/// HTTP transport for webhook notifiers, abstracted so payloads can be
+/// checked without a server
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    async fn post_json(&self, url: &str, body: &serde_json::Value) -> Result<()>;
+}
+
+/// Posts JSON with reqwest, treating non-2xx responses as failures
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn post_json(&self, url: &str, body: &serde_json::Value) -> Result<()> {
+        self.client
+            .post(url)
+            .json(body)
+            .send()
+            .await
+            .context("Failed to send webhook")?
+            .error_for_status()
+            .context("Webhook rejected notification")?;
+        Ok(())
+    }
+}
+
+/// Email transport for the email notifier
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait EmailTransport: Send + Sync {
+    async fn send_email(&self, from: &str, to: &[String], subject: &str, body: &str) -> Result<()>;
+}
+
+/// Amazon SES (v2) client wrapper for sending plain-text email
+pub struct SesTransport {
+    client: aws_sdk_sesv2::Client,
+}
+
+impl SesTransport {
+    pub fn new(client: aws_sdk_sesv2::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl EmailTransport for SesTransport {
+    async fn send_email(&self, from: &str, to: &[String], subject: &str, body: &str) -> Result<()> {
+        let message = Message::builder()
+            .subject(Content::builder().data(subject).build()?)
+            .body(
+                Body::builder()
+                    .text(Content::builder().data(body).build()?)
+                    .build(),
+            )
+            .build();
+        self.client
+            .send_email()
+            .from_email_address(from)
+            .destination(
+                Destination::builder()
+                    .set_to_addresses(Some(to.to_vec()))
+                    .build(),
+            )
+            .content(EmailContent::builder().simple(message).build())
+            .send()
+            .await
+            .context("Failed to send email through SES")?;
+        Ok(())
+    }
+}
+
+/// Posts a message to a Slack incoming webhook
+pub struct SlackNotifier {
+    url: String,
+    transport: Arc<dyn HttpTransport>,
+}
+
+impl SlackNotifier {
+    pub fn new(url: String, transport: Arc<dyn HttpTransport>) -> Self {
+        Self { url, transport }
+    }
+
+    /// Slack message body
+    pub fn payload(event: &Notification) -> serde_json::Value {
+        let icon = match event.transition {
+            Transition::Failed => ":red_circle:",
+            Transition::Recovered => ":large_green_circle:",
+        };
+        json!({ "text": format!("{} s3-buddy: {}", icon, event.summary()) })
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    async fn notify(&self, event: &Notification) -> Result<()> {
+        self.transport
+            .post_json(&self.url, &Self::payload(event))
+            .await
+    }
+}
+
+/// Posts the notification as JSON to an arbitrary URL
+pub struct WebhookNotifier {
+    url: String,
+    transport: Arc<dyn HttpTransport>,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String, transport: Arc<dyn HttpTransport>) -> Self {
+        Self { url, transport }
+    }
+
+    /// The notification's fields, plus the refresh `error` for failures
+    /// (`null` otherwise)
+    pub fn payload(event: &Notification) -> serde_json::Value {
+        let mut payload = serde_json::to_value(event).unwrap_or_default();
+        let error = (event.transition == Transition::Failed).then(|| event.message.clone());
+        payload["error"] = error.into();
+        payload
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, event: &Notification) -> Result<()> {
+        self.transport
+            .post_json(&self.url, &Self::payload(event))
+            .await
+    }
+}
+
+/// Emails the notification to a fixed list of recipients
+pub struct EmailNotifier {
+    from: String,
+    to: Vec<String>,
+    transport: Arc<dyn EmailTransport>,
+}
+
+impl EmailNotifier {
+    pub fn new(from: String, to: Vec<String>, transport: Arc<dyn EmailTransport>) -> Self {
+        Self {
+            from,
+            to,
+            transport,
+        }
+    }
+
+    /// Subject and plain-text body
+    pub fn message(event: &Notification) -> (String, String) {
+        let subject = format!("[s3-buddy] {} {}", event.short_url, event.transition);
+        let body = format!(
+            "Mapping: {}\nShort URL: {}\nEvent: {:?}\nTime: {}\n\n{}\n",
+            event.mapping_id,
+            event.short_url,
+            event.event,
+            event.timestamp.to_rfc3339(),
+            event.message
+        );
+        (subject, body)
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, event: &Notification) -> Result<()> {
+        let (subject, body) = Self::message(event);
+        self.transport
+            .send_email(&self.from, &self.to, &subject, &body)
+            .await
+    }
+}
+
+/// Every enabled notifier; each notification is sent to all of them
+#[derive(Clone, Default)]
+pub struct Notifiers {
+    notifiers: Arc<Vec<Arc<dyn Notifier>>>,
+}
+
+impl Notifiers {
+    pub fn new(notifiers: Vec<Arc<dyn Notifier>>) -> Self {
+        Self {
+            notifiers: Arc::new(notifiers),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notifiers.is_empty()
+    }
+
+    /// Send to every notifier; one failing doesn't stop the others
+    #[instrument(skip(self), fields(mapping_id = %event.mapping_id))]
+    pub async fn notify_all(&self, event: &Notification) {
+        for notifier in self.notifiers.iter() {
+            if let Err(e) = notifier.notify(event).await {
+                error!("Failed to send notification: {:#}", e);
+            }
+        }
+    }
+
+    /// Notify in the background so a slow endpoint never delays a refresh
+    pub fn dispatch(&self, event: Notification) {
+        if self.is_empty() {
+            return;
+        }
+        let notifiers = self.clone();
+        tokio::spawn(async move { notifiers.notify_all(&event).await });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(transition: Transition) -> Notification {
+        Notification {
+            mapping_id: Uuid::nil(),
+            short_url: "files.example.com".to_string(),
+            transition,
+            event: RefreshEvent::PresignFailed,
+            message: "Failed to refresh URL: AccessDenied".to_string(),
+            timestamp: "2024-05-01T12:00:00Z".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_transitions_worth_alerting_on() {
+        use MappingStatus::*;
+        let between = Transition::between;
+        assert_eq!(between(Active, Error), Some(Transition::Failed));
+        assert_eq!(between(Pending, Error), Some(Transition::Failed));
+        assert_eq!(between(Error, Error), None);
+        assert_eq!(between(Error, Active), Some(Transition::Recovered));
+        assert_eq!(between(Pending, Active), None);
+        assert_eq!(between(Active, Paused), None);
+    }
+
+    #[tokio::test]
+    async fn test_slack_posts_text_message() {
+        let mut transport = MockHttpTransport::new();
+        transport
+            .expect_post_json()
+            .withf(|url, body| {
+                url == "https://hooks.slack.com/services/T0/B0/x"
+                    && body
+                        == &json!({
+                            "text": ":red_circle: s3-buddy: files.example.com failed: \
+                                     Failed to refresh URL: AccessDenied"
+                        })
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let notifier = SlackNotifier::new(
+            "https://hooks.slack.com/services/T0/B0/x".to_string(),
+            Arc::new(transport),
+        );
+        notifier
+            .notify(&notification(Transition::Failed))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_webhook_posts_notification_json() {
+        let mut transport = MockHttpTransport::new();
+        transport
+            .expect_post_json()
+            .withf(|url, body| {
+                url == "https://alerts.example.com/hook"
+                    && body["mapping_id"] == Uuid::nil().to_string()
+                    && body["short_url"] == "files.example.com"
+                    && body["transition"] == "recovered"
+                    && body["event"] == "PresignFailed"
+                    && body["timestamp"] == "2024-05-01T12:00:00Z"
+                    && body["error"].is_null()
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let notifier = WebhookNotifier::new(
+            "https://alerts.example.com/hook".to_string(),
+            Arc::new(transport),
+        );
+        notifier
+            .notify(&notification(Transition::Recovered))
+            .await
+            .unwrap();
+
+        let failed = WebhookNotifier::payload(&notification(Transition::Failed));
+        assert_eq!(failed["error"], "Failed to refresh URL: AccessDenied");
+    }
+
+    #[tokio::test]
+    async fn test_email_has_subject_and_details() {
+        let mut transport = MockEmailTransport::new();
+        transport
+            .expect_send_email()
+            .withf(|from, to, subject, body| {
+                from == "s3-buddy@example.com"
+                    && to == ["ops@example.com".to_string()]
+                    && subject == "[s3-buddy] files.example.com recovered"
+                    && body.contains("Short URL: files.example.com\n")
+                    && body.contains("Time: 2024-05-01T12:00:00+00:00\n")
+                    && body.ends_with("Failed to refresh URL: AccessDenied\n")
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        let notifier = EmailNotifier::new(
+            "s3-buddy@example.com".to_string(),
+            vec!["ops@example.com".to_string()],
+            Arc::new(transport),
+        );
+        notifier
+            .notify(&notification(Transition::Recovered))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failing_notifier_does_not_stop_others() {
+        let mut failing = MockNotifier::new();
+        failing
+            .expect_notify()
+            .times(1)
+            .returning(|_| Err(anyhow::anyhow!("connection refused")));
+        let mut working = MockNotifier::new();
+        working.expect_notify().times(1).returning(|_| Ok(()));
+
+        let notifiers = Notifiers::new(vec![Arc::new(failing), Arc::new(working)]);
+        notifiers
+            .notify_all(&notification(Transition::Failed))
+            .await;
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Position in a listing ordered by timestamp, then ID
+///
+/// Encoded as an opaque hex string so clients treat it as a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cursor {
+    pub timestamp: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn new(timestamp: DateTime<Utc>, id: Uuid) -> Self {
+        Self { timestamp, id }
+    }
+
+    pub fn encode(&self) -> String {
+        let nanos = self.timestamp.timestamp_nanos_opt().unwrap_or(i64::MAX);
+        format!("{:016x}{}", nanos, self.id.simple())
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self> {
+        let invalid = || anyhow::anyhow!("Invalid cursor");
+        if cursor.len() != 48 || !cursor.is_ascii() {
+            return Err(invalid());
+        }
+
+        let (nanos, id) = cursor.split_at(16);
+        let nanos = u64::from_str_radix(nanos, 16).map_err(|_| invalid())? as i64;
+        let id = Uuid::try_parse(id).map_err(|_| invalid())?;
+
+        Ok(Self {
+            timestamp: DateTime::from_timestamp_nanos(nanos),
+            id,
+        })
+    }
+}
+
+/// Decode an optional cursor query parameter
+pub fn parse_cursor(cursor: Option<&str>) -> Result<Option<Cursor>> {
+    cursor
+        .filter(|c| !c.is_empty())
+        .map(Cursor::decode)
+        .transpose()
+        .context("Failed to parse cursor")
+}
+
+/// Up to `limit` items after `after` in ascending key order, plus the cursor
+/// for the following page if more remain
+pub fn page_after<T>(
+    mut items: Vec<T>,
+    key: impl Fn(&T) -> Cursor,
+    after: Option<Cursor>,
+    limit: usize,
+) -> (Vec<T>, Option<String>) {
+    items.sort_by_key(|item| key(item));
+    if let Some(after) = after {
+        items.retain(|item| key(item) > after);
+    }
+
+    let next_cursor = (items.len() > limit).then(|| key(&items[limit - 1]).encode());
+    items.truncate(limit);
+    (items, next_cursor)
+}
+
+/// The `limit` most recent items before `before`, oldest first, plus the
+/// cursor for the preceding (older) page if more remain
+pub fn page_before<T>(
+    mut items: Vec<T>,
+    key: impl Fn(&T) -> Cursor,
+    before: Option<Cursor>,
+    limit: usize,
+) -> (Vec<T>, Option<String>) {
+    items.sort_by_key(|item| key(item));
+    if let Some(before) = before {
+        items.retain(|item| key(item) < before);
+    }
+
+    let skip = items.len().saturating_sub(limit);
+    let next_cursor = (skip > 0).then(|| key(&items[skip]).encode());
+    (items.split_off(skip), next_cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor::new(Utc::now(), Uuid::new_v4());
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(Cursor::decode("not-a-cursor").is_err());
+    }
+
+    #[test]
+    fn test_page_before_walks_backwards() {
+        let base = Utc::now();
+        let items: Vec<Cursor> = (0..5)
+            .map(|i| Cursor::new(base + chrono::Duration::seconds(i), Uuid::new_v4()))
+            .collect();
+
+        let (page, next) = page_before(items.clone(), |c| *c, None, 2);
+        assert_eq!(page, items[3..].to_vec());
+
+        let before = parse_cursor(next.as_deref()).unwrap();
+        let (page, next) = page_before(items.clone(), |c| *c, before, 2);
+        assert_eq!(page, items[1..3].to_vec());
+
+        let before = parse_cursor(next.as_deref()).unwrap();
+        let (page, next) = page_before(items.clone(), |c| *c, before, 2);
+        assert_eq!(page, items[..1].to_vec());
+        assert!(next.is_none());
+    }
+}
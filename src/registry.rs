@@ -0,0 +1,296 @@
+use anyhow::Result;
+use aws_config::timeout::TimeoutConfig;
+use aws_config::{BehaviorVersion, Region, SdkConfig};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::retry::RetryConfig;
+use crate::route53::{DnsBackend, Route53Client, DEFAULT_CHANGE_RATE};
+use crate::s3::{S3Client, S3Endpoint, StorageBackend, DEFAULT_MAX_URL_LENGTH};
+
+/// Storage and DNS backends sharing the same credentials
+#[derive(Clone)]
+pub struct ClientSet {
+    pub s3: Arc<dyn StorageBackend>,
+    pub route53: Arc<dyn DnsBackend>,
+}
+
+/// Registry of AWS clients keyed by credentials profile name
+///
+/// Mappings without a profile use the default clients.
+#[derive(Clone)]
+pub struct ClientRegistry {
+    default: ClientSet,
+    profiles: HashMap<String, ClientSet>,
+}
+
+impl ClientRegistry {
+    pub fn new(
+        s3_client: impl StorageBackend + 'static,
+        route53_client: impl DnsBackend + 'static,
+    ) -> Self {
+        Self {
+            default: ClientSet {
+                s3: Arc::new(s3_client),
+                route53: Arc::new(route53_client),
+            },
+            profiles: HashMap::new(),
+        }
+    }
+
+    /// Build a registry from the default AWS config plus any named profiles
+    /// listed (comma-separated) in `S3_BUDDY_PROFILES`
+    ///
+    /// The SDK's default credentials chain refreshes expiring credentials on
+    /// its own; rebuilding the registry forces a fresh credentials lookup.
+    pub async fn from_env() -> Self {
+        Self::from_config(&load_aws_config(None).await).await
+    }
+
+    /// Like `from_env`, with an already loaded default AWS config
+    pub async fn from_config(aws_config: &SdkConfig) -> Self {
+        let max_url_length = std::env::var("MAX_PRESIGNED_URL_LENGTH")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_URL_LENGTH);
+        let endpoint = S3Endpoint {
+            url: std::env::var("S3_ENDPOINT_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            force_path_style: std::env::var("S3_FORCE_PATH_STYLE")
+                .is_ok_and(|v| v == "true" || v == "1"),
+        };
+        // S3-compatible services generally don't report bucket regions
+        let discover_regions = std::env::var("S3_DISCOVER_BUCKET_REGION")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(endpoint.url.is_none());
+        let change_rate = std::env::var("ROUTE53_CHANGES_PER_SECOND")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CHANGE_RATE);
+        // Log Route53 changes instead of making them
+        let dry_run = std::env::var("DRY_RUN").is_ok_and(|v| v == "true" || v == "1");
+        if dry_run {
+            warn!("DRY_RUN is set: Route53 changes are logged, not applied");
+        }
+        let mut retry = RetryConfig::default();
+        if let Some(max_retries) = std::env::var("RETRY_MAX_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            retry.max_retries = max_retries;
+        }
+        if let Some(millis) = std::env::var("RETRY_BASE_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            retry.base_delay = Duration::from_millis(millis);
+        }
+        // The SDK retries transient failures of other calls; Route53 record
+        // updates are retried by the client, logging each retry
+        let with_retry = |config: &aws_config::SdkConfig| {
+            config.to_builder().retry_config(retry.sdk_config()).build()
+        };
+        let route53_client = |config: &aws_config::SdkConfig| {
+            Route53Client::new(aws_sdk_route53::Client::new(&with_retry(config)))
+                .with_change_rate(change_rate)
+                .with_retry(retry)
+                .with_dry_run(dry_run)
+        };
+        let clock_skew = std::env::var("PRESIGN_CLOCK_SKEW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or_default();
+        let s3_client = |config: &aws_config::SdkConfig| {
+            let client = S3Client::new(endpoint.client((&with_retry(config)).into()))
+                .with_max_url_length(max_url_length)
+                .with_clock_skew(clock_skew)
+                .with_region_discovery(discover_regions);
+            match config.credentials_provider() {
+                Some(provider) => client.with_credentials_provider(provider),
+                None => client,
+            }
+        };
+
+        let mut registry = Self::new(s3_client(aws_config), route53_client(aws_config));
+
+        if let Ok(profiles) = std::env::var("S3_BUDDY_PROFILES") {
+            for name in profiles.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                let profile_config = load_aws_config(Some(name)).await;
+                registry.add_profile(
+                    name,
+                    s3_client(&profile_config),
+                    route53_client(&profile_config),
+                );
+            }
+        }
+
+        registry
+    }
+
+    /// Register the clients to use for a named credentials profile
+    pub fn add_profile(
+        &mut self,
+        name: impl Into<String>,
+        s3_client: impl StorageBackend + 'static,
+        route53_client: impl DnsBackend + 'static,
+    ) {
+        self.profiles.insert(
+            name.into(),
+            ClientSet {
+                s3: Arc::new(s3_client),
+                route53: Arc::new(route53_client),
+            },
+        );
+    }
+
+    /// Names of all registered profiles
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.profiles.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Resolve the clients for a profile, falling back to the default clients
+    pub fn resolve(&self, profile: Option<&str>) -> Result<ClientSet> {
+        match profile {
+            None => Ok(self.default.clone()),
+            Some(name) => self
+                .profiles
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Unknown credentials profile: {}", name)),
+        }
+    }
+}
+
+/// Load the AWS config for `profile`, or for `AWS_PROFILE` if none is given,
+/// in the region from `AWS_REGION`
+///
+/// Whatever isn't set is resolved by the SDK's default chains, as on an EC2
+/// instance or ECS task with an IAM role. Logs the resolved region, so
+/// credentials issues can be told apart from a wrong region.
+pub async fn load_aws_config(profile: Option<&str>) -> SdkConfig {
+    let env_profile = std::env::var("AWS_PROFILE").ok();
+    let profile = profile.or(env_profile.as_deref()).filter(|p| !p.is_empty());
+    let region = std::env::var("AWS_REGION")
+        .ok()
+        .filter(|r| !r.is_empty())
+        .map(Region::new);
+
+    let mut loader = aws_config::defaults(BehaviorVersion::latest());
+    if let Some(profile) = profile {
+        loader = loader.profile_name(profile);
+    }
+    if let Some(region) = region {
+        loader = loader.region(region);
+    }
+    if let Some(timeouts) = timeout_config_from_lookup(|name| std::env::var(name).ok()) {
+        loader = loader.timeout_config(timeouts);
+    }
+    let config = loader.load().await;
+
+    info!(
+        "AWS config loaded: region {}, {}",
+        config
+            .region()
+            .map_or_else(|| "unset".to_string(), |r| r.to_string()),
+        match profile {
+            Some(profile) => format!("profile {}", profile),
+            None => "default credentials chain".to_string(),
+        }
+    );
+    config
+}
+
+/// SDK timeouts from `AWS_OP_TIMEOUT_MS` (a whole call, retries included)
+/// and `AWS_CONNECT_TIMEOUT_MS`, read through `lookup`; `None` when neither
+/// is set, keeping the SDK's defaults
+pub fn timeout_config_from_lookup(
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Option<TimeoutConfig> {
+    let millis = |name| {
+        lookup(name)
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_millis)
+    };
+    let operation = millis("AWS_OP_TIMEOUT_MS");
+    let connect = millis("AWS_CONNECT_TIMEOUT_MS");
+    if operation.is_none() && connect.is_none() {
+        return None;
+    }
+    let mut timeouts = TimeoutConfig::builder();
+    timeouts
+        .set_operation_timeout(operation)
+        .set_connect_timeout(connect);
+    Some(timeouts.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn clients() -> (S3Client, Route53Client) {
+        let config = aws_config::from_env().load().await;
+        (
+            S3Client::new(aws_sdk_s3::Client::new(&config)),
+            Route53Client::new(aws_sdk_route53::Client::new(&config)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_profiles_resolve_to_different_clients() {
+        let (s3, route53) = clients().await;
+        let mut registry = ClientRegistry::new(s3, route53);
+
+        let (s3, route53) = clients().await;
+        registry.add_profile("prod", s3, route53);
+        let (s3, route53) = clients().await;
+        registry.add_profile("staging", s3, route53);
+
+        let prod = registry.resolve(Some("prod")).unwrap();
+        let staging = registry.resolve(Some("staging")).unwrap();
+        let default = registry.resolve(None).unwrap();
+
+        assert!(!Arc::ptr_eq(&prod.s3, &staging.s3));
+        assert!(!Arc::ptr_eq(&prod.route53, &staging.route53));
+        assert!(!Arc::ptr_eq(&prod.s3, &default.s3));
+
+        // Resolving the same profile twice yields the same clients
+        let prod_again = registry.resolve(Some("prod")).unwrap();
+        assert!(Arc::ptr_eq(&prod.s3, &prod_again.s3));
+    }
+
+    #[test]
+    fn test_timeout_config_from_env() {
+        assert!(timeout_config_from_lookup(|_| None).is_none());
+        assert!(timeout_config_from_lookup(|_| Some("soon".to_string())).is_none());
+
+        let config = timeout_config_from_lookup(|name| match name {
+            "AWS_OP_TIMEOUT_MS" => Some("5000".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(config.operation_timeout(), Some(Duration::from_secs(5)));
+        assert_eq!(config.connect_timeout(), None);
+
+        let config = timeout_config_from_lookup(|name| match name {
+            "AWS_OP_TIMEOUT_MS" => Some("5000".to_string()),
+            "AWS_CONNECT_TIMEOUT_MS" => Some("250".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(config.connect_timeout(), Some(Duration::from_millis(250)));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_profile_is_rejected() {
+        let (s3, route53) = clients().await;
+        let registry = ClientRegistry::new(s3, route53);
+
+        assert!(registry.resolve(Some("missing")).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use std::future::Future;
+use std::time::Duration;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+/// Retries after the first attempt unless configured otherwise
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Backoff before the first retry; doubles with every further attempt
+pub const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// Longest backoff between two attempts
+pub const MAX_DELAY: Duration = Duration::from_secs(20);
+
+/// AWS error codes for throttling and transient server-side failures
+const TRANSIENT_CODES: &[&str] = &[
+    "Throttling",
+    "ThrottlingException",
+    "ThrottledException",
+    "RequestLimitExceeded",
+    "TooManyRequestsException",
+    "PriorRequestNotComplete",
+    "SlowDown",
+    "RequestTimeout",
+    "RequestTimeoutException",
+    "InternalError",
+    "InternalFailure",
+    "ServiceUnavailable",
+];
+
+/// How AWS calls are retried when they fail transiently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// Retries after the first attempt; zero disables retrying
+    pub max_retries: u32,
+    /// Backoff before the first retry
+    pub base_delay: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: DEFAULT_BASE_DELAY,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Exponential backoff before retry number `retry` (from 0): half of
+    /// `base_delay * 2^retry`, capped at `MAX_DELAY`, plus up to as much again
+    /// at random so clients throttled together don't retry together
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry).unwrap_or(u32::MAX);
+        let half = self.base_delay.saturating_mul(factor).min(MAX_DELAY) / 2;
+        half + jittered(half)
+    }
+
+    /// Run `attempt` until it succeeds, fails with an error `is_transient`
+    /// rejects, or the retries run out, warning on each retry
+    pub async fn run<T, E, F, Fut>(
+        &self,
+        operation: &str,
+        is_transient: impl Fn(&E) -> bool,
+        mut attempt: F,
+    ) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: std::fmt::Display,
+    {
+        let mut retry = 0;
+        loop {
+            match attempt().await {
+                Ok(value) => return Ok(value),
+                Err(e) if !is_transient(&e) => return Err(e),
+                Err(e) if retry >= self.max_retries => {
+                    if self.max_retries > 0 {
+                        error!("{} failed after {} retries: {:#}", operation, retry, e);
+                    }
+                    return Err(e);
+                }
+                Err(e) => {
+                    let delay = self.backoff(retry);
+                    retry += 1;
+                    warn!(
+                        "{} failed, retry {}/{} in {:?}: {:#}",
+                        operation, retry, self.max_retries, delay, e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// The SDK retry config doing this: standard retries with exponential
+    /// backoff and jitter, capped at `MAX_DELAY`
+    pub fn sdk_config(&self) -> aws_config::retry::RetryConfig {
+        aws_config::retry::RetryConfig::standard()
+            .with_max_attempts(self.max_retries.saturating_add(1))
+            .with_initial_backoff(self.base_delay)
+            .with_max_backoff(MAX_DELAY)
+    }
+}
+
+/// Context marking an AWS failure that may clear up if tried again later,
+/// such as throttling or a timeout
+#[derive(Debug)]
+pub struct TransientError;
+
+impl std::fmt::Display for TransientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AWS is temporarily unavailable")
+    }
+}
+
+impl std::error::Error for TransientError {}
+
+/// Add `TransientError` to a failed AWS call's error if `transient`
+pub fn mark_transient(err: anyhow::Error, transient: bool) -> anyhow::Error {
+    if transient {
+        err.context(TransientError)
+    } else {
+        err
+    }
+}
+
+/// Whether an AWS error code or HTTP status means the call may succeed if
+/// retried: throttling and 5xx responses, but not validation errors such as
+/// a missing hosted zone
+pub fn is_transient(code: Option<&str>, status: Option<u16>) -> bool {
+    code.is_some_and(|code| TRANSIENT_CODES.contains(&code))
+        || status.is_some_and(|status| status == 429 || status >= 500)
+}
+
+/// Random duration of up to `max`
+pub(crate) fn jittered(max: Duration) -> Duration {
+    let max_millis = max.as_millis() as u64;
+    if max_millis == 0 {
+        return Duration::ZERO;
+    }
+    // v4 UUIDs are random, which saves a dependency on `rand`
+    let random = Uuid::new_v4().as_u64_pair().0;
+    Duration::from_millis(1 + random % max_millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn config(max_retries: u32) -> RetryConfig {
+        RetryConfig {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        assert!(is_transient(Some("Throttling"), Some(400)));
+        assert!(is_transient(Some("PriorRequestNotComplete"), Some(400)));
+        assert!(is_transient(None, Some(503)));
+        assert!(is_transient(None, Some(429)));
+        assert!(!is_transient(Some("NoSuchHostedZone"), Some(404)));
+        assert!(!is_transient(Some("InvalidChangeBatch"), Some(400)));
+        assert!(!is_transient(None, None));
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let config = RetryConfig {
+            max_retries: 10,
+            base_delay: Duration::from_millis(100),
+        };
+        for retry in 0..3 {
+            let full = Duration::from_millis(100 << retry);
+            let delay = config.backoff(retry);
+            assert!(delay >= full / 2 && delay <= full, "{:?}", delay);
+        }
+        assert!(config.backoff(30) <= MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_retries_only_what_the_classifier_allows() {
+        // Fails with "throttled" twice, then succeeds
+        let calls = AtomicU32::new(0);
+        let result: Result<u32, String> = config(3)
+            .run(
+                "test",
+                |e: &String| e == "throttled",
+                || async {
+                    let n = calls.fetch_add(1, Ordering::SeqCst);
+                    if n < 2 {
+                        Err("throttled".to_string())
+                    } else {
+                        Ok(n)
+                    }
+                },
+            )
+            .await;
+        assert_eq!(result, Ok(2));
+
+        // A permanent error isn't retried
+        let calls = AtomicU32::new(0);
+        let result: Result<(), String> = config(3)
+            .run(
+                "test",
+                |e: &String| e == "throttled",
+                || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err("no such zone".to_string())
+                },
+            )
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Retries run out
+        let calls = AtomicU32::new(0);
+        let result: Result<(), String> = config(2)
+            .run(
+                "test",
+                |_: &String| true,
+                || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err("throttled".to_string())
+                },
+            )
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_sdk_config() {
+        let sdk = RetryConfig {
+            max_retries: 2,
+            base_delay: Duration::from_millis(100),
+        }
+        .sdk_config();
+        assert_eq!(sdk.max_attempts(), 3);
+        assert_eq!(sdk.initial_backoff(), Duration::from_millis(100));
+        assert_eq!(sdk.max_backoff(), MAX_DELAY);
+
+        // No retries is a single attempt
+        let sdk = RetryConfig {
+            max_retries: 0,
+            ..Default::default()
+        }
+        .sdk_config();
+        assert_eq!(sdk.max_attempts(), 1);
+    }
+}
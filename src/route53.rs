@@ -1,73 +1,553 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use aws_sdk_route53::error::{ProvideErrorMetadata, SdkError};
 use aws_sdk_route53::types::{
-    Change, ChangeAction, ChangeBatch, ResourceRecord, ResourceRecordSet, RrType,
+    AliasTarget, Change, ChangeAction, ChangeBatch, ResourceRecord, ResourceRecordSet, RrType,
 };
-use tracing::{info, instrument};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::{info, instrument, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::retry::{self, RetryConfig};
+
+/// TTL applied to the records this client writes (5 minutes)
+pub const DEFAULT_TTL: i64 = 300;
+
+/// Largest TTL Route53 accepts
+pub const MAX_TTL: i64 = i32::MAX as i64;
+
+/// Route53 hosted zone ID and endpoint of the S3 website endpoint in each
+/// region, the targets of alias records
+const S3_WEBSITE_ENDPOINTS: &[(&str, &str, &str)] = &[
+    (
+        "us-east-1",
+        "Z3AQBSTGFYJSTF",
+        "s3-website-us-east-1.amazonaws.com",
+    ),
+    (
+        "us-east-2",
+        "Z2O1EMRO9K5GLX",
+        "s3-website.us-east-2.amazonaws.com",
+    ),
+    (
+        "us-west-1",
+        "Z2F56UZL2M1ACD",
+        "s3-website-us-west-1.amazonaws.com",
+    ),
+    (
+        "us-west-2",
+        "Z3BJ6K6RIION7M",
+        "s3-website-us-west-2.amazonaws.com",
+    ),
+    (
+        "ca-central-1",
+        "Z1QDHH18159H29",
+        "s3-website.ca-central-1.amazonaws.com",
+    ),
+    (
+        "eu-west-1",
+        "Z1BKCTXD74EZPE",
+        "s3-website-eu-west-1.amazonaws.com",
+    ),
+    (
+        "eu-west-2",
+        "Z3GKZC51ZF0DB4",
+        "s3-website.eu-west-2.amazonaws.com",
+    ),
+    (
+        "eu-central-1",
+        "Z21DNDUVLTQW6Q",
+        "s3-website.eu-central-1.amazonaws.com",
+    ),
+    (
+        "ap-northeast-1",
+        "Z2M4EHUR26P7ZW",
+        "s3-website-ap-northeast-1.amazonaws.com",
+    ),
+    (
+        "ap-southeast-1",
+        "Z3O0J2DXBE1FTB",
+        "s3-website-ap-southeast-1.amazonaws.com",
+    ),
+    (
+        "ap-southeast-2",
+        "Z1WCIGYICN2BYD",
+        "s3-website-ap-southeast-2.amazonaws.com",
+    ),
+    (
+        "sa-east-1",
+        "Z7KQH4QJS55SO",
+        "s3-website-sa-east-1.amazonaws.com",
+    ),
+];
+
+/// Kind of record written for a mapping
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsRecordType {
+    /// CNAME to the presigned URL's host
+    #[default]
+    Cname,
+    /// A record aliased to the bucket region's S3 website endpoint; unlike a
+    /// CNAME it can sit at a zone apex
+    Alias,
+}
+
+impl DnsRecordType {
+    /// Route53 record type written for this kind
+    pub fn rr_type(self) -> RrType {
+        match self {
+            DnsRecordType::Cname => RrType::Cname,
+            DnsRecordType::Alias => RrType::A,
+        }
+    }
+}
+
+/// How a mapping's record is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnsRecordConfig {
+    pub record_type: DnsRecordType,
+    /// TTL in seconds; alias records take the target's TTL instead
+    pub ttl: i64,
+}
+
+impl Default for DnsRecordConfig {
+    fn default() -> Self {
+        Self {
+            record_type: DnsRecordType::Cname,
+            ttl: DEFAULT_TTL,
+        }
+    }
+}
+
+/// Current value of a DNS record as stored in Route53
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordValue {
+    pub record_type: String,
+    pub values: Vec<String>,
+    pub ttl: Option<i64>,
+}
+
+/// DNS operations, abstracted so refresh and reconciliation can run without AWS
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait DnsBackend: Send + Sync {
+    /// Look up the current record for a name
+    async fn get_record(&self, hosted_zone_id: &str, name: &str) -> Result<Option<RecordValue>>;
+
+    /// Look up a hosted zone's domain name
+    async fn get_zone_name(&self, hosted_zone_id: &str) -> Result<Option<String>>;
+
+    /// Check a hosted zone exists and `short_url` is its apex or a name
+    /// under it; failures to look the zone up, such as access denied, are
+    /// reported as they are rather than as a missing zone
+    async fn validate_zone(&self, hosted_zone_id: &str, short_url: &str) -> Result<()> {
+        let zone_name = self
+            .get_zone_name(hosted_zone_id)
+            .await
+            .context(ZoneLookupFailed {
+                hosted_zone_id: hosted_zone_id.to_string(),
+            })?
+            .with_context(|| format!("Hosted zone {} not found", hosted_zone_id))?;
+        if !zone_contains(&zone_name, short_url) {
+            anyhow::bail!(
+                "{} is not in hosted zone {} ({})",
+                short_url,
+                hosted_zone_id,
+                zone_name.trim_end_matches('.')
+            );
+        }
+        Ok(())
+    }
+
+    /// Point a name at the host of a presigned URL, tagging the change with
+    /// the owning mapping
+    async fn update_dns_record(
+        &self,
+        hosted_zone_id: &str,
+        short_url: &str,
+        presigned_url: &str,
+        record: DnsRecordConfig,
+        mapping_id: Option<Uuid>,
+    ) -> Result<()>;
+
+    /// Cheap call proving the service is reachable with these credentials
+    async fn check_access(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A hosted zone that couldn't be looked up, e.g. for lack of access, as
+/// opposed to one that doesn't exist
+#[derive(Debug)]
+pub struct ZoneLookupFailed {
+    pub hosted_zone_id: String,
+}
+
+impl std::fmt::Display for ZoneLookupFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to look up hosted zone {}", self.hosted_zone_id)
+    }
+}
+
+impl std::error::Error for ZoneLookupFailed {}
+
+/// Maximum length Route53 accepts for a change batch comment
+pub const MAX_COMMENT_LENGTH: usize = 256;
+
+/// Maximum number of changes Route53 accepts in a single batch
+pub const MAX_BATCH_CHANGES: usize = 1000;
+
+/// Outcome of applying a set of changes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplyReport {
+    pub applied: usize,
+    pub rejected: usize,
+}
+
+/// Why a change batch was not applied
+#[derive(Debug)]
+enum BatchError {
+    /// Route53 rejected the batch as a whole (`InvalidChangeBatch`)
+    InvalidBatch(anyhow::Error),
+    /// Any other failure; retrying individual changes won't help
+    Other(anyhow::Error),
+}
+
+/// Whether a Route53 call failed in a way worth retrying: timeouts, dropped
+/// connections, throttling and server errors
+fn is_transient_error<E: ProvideErrorMetadata>(err: &SdkError<E>) -> bool {
+    match err {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => {
+            true
+        }
+        _ => retry::is_transient(err.code(), err.raw_response().map(|r| r.status().as_u16())),
+    }
+}
+
+/// Route53's per-account limit on `ChangeResourceRecordSets` calls
+pub const DEFAULT_CHANGE_RATE: u32 = 5;
+
+/// Spaces out calls so they stay under a per-second rate
+///
+/// Callers queue on the mutex in arrival order, so bursts from many refresh
+/// tasks are released one slot at a time.
+#[derive(Debug)]
+struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / per_second.max(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait for the next free slot
+    async fn acquire(&self) {
+        let mut next_slot = self.next_slot.lock().await;
+        let now = Instant::now();
+        if *next_slot > now {
+            tokio::time::sleep_until(*next_slot).await;
+        }
+        *next_slot = (*next_slot).max(now) + self.interval;
+    }
+}
 
 /// Route53 client wrapper for DNS operations
 pub struct Route53Client {
     client: aws_sdk_route53::Client,
+    /// Shared by every change submission made through this client
+    limiter: RateLimiter,
+    /// Retries of record updates, made here rather than by the SDK so each
+    /// one is logged
+    retry: RetryConfig,
+    /// Log change batches instead of submitting them
+    dry_run: bool,
 }
 
 impl Route53Client {
     pub fn new(client: aws_sdk_route53::Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            limiter: RateLimiter::new(DEFAULT_CHANGE_RATE),
+            retry: RetryConfig::default(),
+            dry_run: false,
+        }
     }
 
-    /// Update or create a CNAME record pointing to the presigned URL
+    /// Build and log every change batch without submitting it, leaving
+    /// Route53 untouched
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Retry record updates that fail with throttling or server errors
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Limit change submissions to `per_second` calls per second
+    pub fn with_change_rate(mut self, per_second: u32) -> Self {
+        self.limiter = RateLimiter::new(per_second);
+        self
+    }
+
+    /// Update or create the record pointing to the presigned URL's host
     #[instrument(skip(self, presigned_url))]
     pub async fn update_dns_record(
         &self,
         hosted_zone_id: &str,
         short_url: &str,
         presigned_url: &str,
+        record: DnsRecordConfig,
+        mapping_id: Option<Uuid>,
     ) -> Result<()> {
         info!(
             "Updating DNS record {} to point to presigned URL",
             short_url
         );
 
-        // Extract the hostname from the presigned URL
-        let target_url = Self::extract_hostname(presigned_url)?;
+        let change_batch = Self::upsert_batch(short_url, presigned_url, record, mapping_id)?;
 
-        let resource_record = ResourceRecord::builder()
-            .value(target_url)
-            .build()
-            .context("Failed to build resource record")?;
+        let sent = send_or_log(self.dry_run, hosted_zone_id, change_batch, |change_batch| {
+            let request = self
+                .client
+                .change_resource_record_sets()
+                .hosted_zone_id(hosted_zone_id)
+                .change_batch(change_batch);
+            let limiter = &self.limiter;
+            async move {
+                self.retry
+                    .run("Route53 record update", is_transient_error, || {
+                        // Retried only here, not again inside the SDK
+                        let request = request.clone().customize().config_override(
+                            aws_sdk_route53::config::Builder::default()
+                                .retry_config(aws_config::retry::RetryConfig::disabled()),
+                        );
+                        async move {
+                            // Every attempt counts against the rate limit
+                            limiter.acquire().await;
+                            request.send().await
+                        }
+                    })
+                    .await
+                    .context("Failed to update Route53 record")?;
+                Ok(())
+            }
+        })
+        .await?;
 
-        let record_set = ResourceRecordSet::builder()
-            .name(short_url)
-            .r#type(RrType::Cname)
-            .ttl(300) // 5 minutes TTL
-            .resource_records(resource_record)
+        if sent {
+            info!("Successfully updated DNS record {}", short_url);
+        }
+
+        Ok(())
+    }
+
+    /// Build the single-change batch `update_dns_record` submits
+    pub fn upsert_batch(
+        short_url: &str,
+        presigned_url: &str,
+        record: DnsRecordConfig,
+        mapping_id: Option<Uuid>,
+    ) -> Result<ChangeBatch> {
+        ChangeBatch::builder()
+            .changes(Self::upsert_change(short_url, presigned_url, record)?)
+            .comment(change_comment(mapping_id, short_url))
             .build()
-            .context("Failed to build record set")?;
+            .context("Failed to build change batch")
+    }
 
-        let change = Change::builder()
+    /// Build an UPSERT change pointing a CNAME at the presigned URL's host,
+    /// or an alias at its region's S3 website endpoint
+    pub fn upsert_change(
+        short_url: &str,
+        presigned_url: &str,
+        record: DnsRecordConfig,
+    ) -> Result<Change> {
+        let record_set = match record.record_type {
+            DnsRecordType::Cname => {
+                // Extract the hostname from the presigned URL
+                let target_url = Self::extract_hostname(presigned_url)?;
+
+                let resource_record = ResourceRecord::builder()
+                    .value(target_url)
+                    .build()
+                    .context("Failed to build resource record")?;
+
+                ResourceRecordSet::builder()
+                    .name(short_url)
+                    .r#type(RrType::Cname)
+                    .ttl(record.ttl)
+                    .resource_records(resource_record)
+                    .build()
+                    .context("Failed to build record set")?
+            }
+            DnsRecordType::Alias => {
+                let (zone_id, endpoint) = s3_website_endpoint(presigned_url)?;
+                let alias_target = AliasTarget::builder()
+                    .hosted_zone_id(zone_id)
+                    .dns_name(endpoint)
+                    .evaluate_target_health(false)
+                    .build()
+                    .context("Failed to build alias target")?;
+
+                ResourceRecordSet::builder()
+                    .name(short_url)
+                    .r#type(RrType::A)
+                    .alias_target(alias_target)
+                    .build()
+                    .context("Failed to build record set")?
+            }
+        };
+
+        Change::builder()
             .action(ChangeAction::Upsert)
             .resource_record_set(record_set)
             .build()
-            .context("Failed to build change")?;
+            .context("Failed to build change")
+    }
 
+    /// Apply many changes to a zone in batches of up to `MAX_BATCH_CHANGES`
+    ///
+    /// If Route53 rejects a batch as invalid, its changes are retried one at a
+    /// time so the valid ones still land.
+    #[instrument(skip(self, changes), fields(count = changes.len()))]
+    pub async fn apply_changes(
+        &self,
+        hosted_zone_id: &str,
+        changes: Vec<Change>,
+    ) -> Result<ApplyReport> {
+        apply_in_batches(changes, |batch| self.submit_batch(hosted_zone_id, batch)).await
+    }
+
+    /// Submit a single change batch, classifying invalid-batch rejections
+    async fn submit_batch(
+        &self,
+        hosted_zone_id: &str,
+        changes: Vec<Change>,
+    ) -> std::result::Result<(), BatchError> {
         let change_batch = ChangeBatch::builder()
-            .changes(change)
+            .set_changes(Some(changes))
             .comment("Updated by s3-buddy")
             .build()
-            .context("Failed to build change batch")?;
+            .context("Failed to build change batch")
+            .map_err(BatchError::Other)?;
 
+        if self.dry_run {
+            log_dry_run(hosted_zone_id, &change_batch);
+            return Ok(());
+        }
+
+        self.limiter.acquire().await;
         self.client
             .change_resource_record_sets()
             .hosted_zone_id(hosted_zone_id)
             .change_batch(change_batch)
             .send()
             .await
-            .context("Failed to update Route53 record")?;
+            .map_err(|e| {
+                let invalid = e
+                    .as_service_error()
+                    .is_some_and(|se| se.is_invalid_change_batch());
+                let err = anyhow::Error::new(e).context("Failed to apply Route53 changes");
+                if invalid {
+                    BatchError::InvalidBatch(err)
+                } else {
+                    BatchError::Other(err)
+                }
+            })?;
 
-        info!("Successfully updated DNS record {}", short_url);
+        Ok(())
+    }
 
+    /// Fetch the current record for a name, if one exists
+    #[instrument(skip(self))]
+    pub async fn get_record(
+        &self,
+        hosted_zone_id: &str,
+        name: &str,
+    ) -> Result<Option<RecordValue>> {
+        let output = self
+            .client
+            .list_resource_record_sets()
+            .hosted_zone_id(hosted_zone_id)
+            .start_record_name(name)
+            .max_items(1)
+            .send()
+            .await
+            .context("Failed to list Route53 records")?;
+
+        let wanted = normalize_name(name);
+        let record = output
+            .resource_record_sets()
+            .iter()
+            .find(|r| normalize_name(r.name()) == wanted)
+            .map(|r| RecordValue {
+                record_type: r.r#type().as_str().to_string(),
+                // An alias has no records of its own, only its target
+                values: match r.alias_target() {
+                    Some(alias) => vec![alias.dns_name().to_string()],
+                    None => r
+                        .resource_records()
+                        .iter()
+                        .map(|rr| rr.value().to_string())
+                        .collect(),
+                },
+                ttl: r.ttl(),
+            });
+
+        Ok(record)
+    }
+
+    /// Check Route53 answers with these credentials by listing one hosted zone
+    pub async fn check_access(&self) -> Result<()> {
+        self.client
+            .list_hosted_zones()
+            .max_items(1)
+            .send()
+            .await
+            .context("Failed to list Route53 hosted zones")?;
         Ok(())
     }
 
+    /// Fetch the domain name of a hosted zone; `None` if there is no such zone
+    #[instrument(skip(self))]
+    pub async fn get_zone_name(&self, hosted_zone_id: &str) -> Result<Option<String>> {
+        let output = match self
+            .client
+            .get_hosted_zone()
+            .id(hosted_zone_id)
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_no_such_hosted_zone()) =>
+            {
+                return Ok(None)
+            }
+            Err(e) => {
+                let transient = is_transient_error(&e);
+                let err = anyhow::Error::new(e).context("Failed to get Route53 hosted zone");
+                return Err(retry::mark_transient(err, transient));
+            }
+        };
+
+        Ok(output.hosted_zone().map(|zone| zone.name().to_string()))
+    }
+
     /// Extract hostname from presigned URL for CNAME target
     fn extract_hostname(url: &str) -> Result<String> {
         let parsed = url::Url::parse(url).context("Failed to parse presigned URL")?;
@@ -79,6 +559,237 @@ impl Route53Client {
     }
 }
 
+#[async_trait]
+impl DnsBackend for Route53Client {
+    async fn get_record(&self, hosted_zone_id: &str, name: &str) -> Result<Option<RecordValue>> {
+        Route53Client::get_record(self, hosted_zone_id, name).await
+    }
+
+    async fn get_zone_name(&self, hosted_zone_id: &str) -> Result<Option<String>> {
+        Route53Client::get_zone_name(self, hosted_zone_id).await
+    }
+
+    async fn update_dns_record(
+        &self,
+        hosted_zone_id: &str,
+        short_url: &str,
+        presigned_url: &str,
+        record: DnsRecordConfig,
+        mapping_id: Option<Uuid>,
+    ) -> Result<()> {
+        Route53Client::update_dns_record(
+            self,
+            hosted_zone_id,
+            short_url,
+            presigned_url,
+            record,
+            mapping_id,
+        )
+        .await
+    }
+
+    async fn check_access(&self) -> Result<()> {
+        Route53Client::check_access(self).await
+    }
+}
+
+/// Hand a change batch to `send`, or in a dry run only log it
+///
+/// Returns whether the batch was sent.
+async fn send_or_log<F, Fut>(
+    dry_run: bool,
+    hosted_zone_id: &str,
+    change_batch: ChangeBatch,
+    send: F,
+) -> Result<bool>
+where
+    F: FnOnce(ChangeBatch) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    if dry_run {
+        log_dry_run(hosted_zone_id, &change_batch);
+        return Ok(false);
+    }
+    send(change_batch).await?;
+    Ok(true)
+}
+
+fn log_dry_run(hosted_zone_id: &str, change_batch: &ChangeBatch) {
+    info!(
+        "Dry run: not submitting change batch to hosted zone {}: {:?}",
+        hosted_zone_id, change_batch
+    );
+}
+
+/// Submit changes in batches, falling back to one-by-one on invalid batches
+async fn apply_in_batches<F, Fut>(changes: Vec<Change>, mut submit: F) -> Result<ApplyReport>
+where
+    F: FnMut(Vec<Change>) -> Fut,
+    Fut: std::future::Future<Output = std::result::Result<(), BatchError>>,
+{
+    let mut report = ApplyReport::default();
+
+    for chunk in changes.chunks(MAX_BATCH_CHANGES) {
+        match submit(chunk.to_vec()).await {
+            Ok(()) => report.applied += chunk.len(),
+            Err(BatchError::InvalidBatch(e)) if chunk.len() == 1 => {
+                warn!("Route53 rejected change: {:#}", e);
+                report.rejected += 1;
+            }
+            Err(BatchError::InvalidBatch(e)) => {
+                warn!(
+                    "Route53 rejected batch of {} changes, retrying individually: {:#}",
+                    chunk.len(),
+                    e
+                );
+                for change in chunk {
+                    match submit(vec![change.clone()]).await {
+                        Ok(()) => report.applied += 1,
+                        Err(BatchError::InvalidBatch(e)) => {
+                            warn!("Route53 rejected change: {:#}", e);
+                            report.rejected += 1;
+                        }
+                        Err(BatchError::Other(e)) => return Err(e),
+                    }
+                }
+            }
+            Err(BatchError::Other(e)) => return Err(e),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Change batch comment identifying the mapping behind a change, so it can
+/// be traced from the Route53 console
+pub fn change_comment(mapping_id: Option<Uuid>, short_url: &str) -> String {
+    let comment = match mapping_id {
+        Some(id) => format!("s3-buddy: {} {}", id, short_url),
+        None => format!("s3-buddy: {}", short_url),
+    };
+    comment.chars().take(MAX_COMMENT_LENGTH).collect()
+}
+
+/// Hosted zone ID without the `/hostedzone/` prefix Route53 returns it with,
+/// checked to look like `Z` followed by letters and digits
+pub fn normalize_hosted_zone_id(hosted_zone_id: &str) -> Result<String> {
+    let trimmed = hosted_zone_id.trim();
+    let id = trimmed
+        .strip_prefix("/hostedzone/")
+        .or_else(|| trimmed.strip_prefix("hostedzone/"))
+        .unwrap_or(trimmed);
+
+    let valid = id.len() > 1
+        && id.starts_with('Z')
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit());
+    if !valid {
+        anyhow::bail!(
+            "Invalid hosted zone ID {:?}: expected an ID like Z1234567890ABC, not a zone name",
+            hosted_zone_id
+        );
+    }
+    Ok(id.to_string())
+}
+
+/// Lowercase a DNS name and strip its trailing dot for comparison
+fn normalize_name(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Whether `name` is a zone's apex or a name under it
+pub fn zone_contains(zone_name: &str, name: &str) -> bool {
+    let zone = normalize_name(zone_name);
+    let name = normalize_name(name);
+    name == zone
+        || name
+            .strip_suffix(&zone)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Region of an S3 endpoint hostname such as `bucket.s3.us-west-2.amazonaws.com`
+/// (`us-east-1` for the global `bucket.s3.amazonaws.com`)
+fn s3_region(host: &str) -> Option<&str> {
+    let mut labels = host.strip_suffix(".amazonaws.com")?.rsplit('.');
+    match labels.next()? {
+        "s3" => Some("us-east-1"),
+        label => match label.strip_prefix("s3-") {
+            // Legacy dash-style regional endpoint
+            Some(region) => Some(region),
+            None => (labels.next()? == "s3").then_some(label),
+        },
+    }
+}
+
+/// Route53 hosted zone ID and hostname of the S3 website endpoint in the
+/// presigned URL's region
+pub fn s3_website_endpoint(presigned_url: &str) -> Result<(&'static str, &'static str)> {
+    let parsed = url::Url::parse(presigned_url).context("Failed to parse presigned URL")?;
+    let host = parsed.host_str().context("No hostname in presigned URL")?;
+    s3_region(host)
+        .and_then(|region| {
+            S3_WEBSITE_ENDPOINTS
+                .iter()
+                .find(|(r, _, _)| *r == region)
+                .map(|(_, zone_id, endpoint)| (*zone_id, *endpoint))
+        })
+        .with_context(|| {
+            format!(
+                "No S3 website endpoint known for {}; use a CNAME record instead",
+                host
+            )
+        })
+}
+
+/// Name a mapping's record should point at for a presigned URL
+pub fn expected_target(presigned_url: &str, record_type: DnsRecordType) -> Result<String> {
+    match record_type {
+        DnsRecordType::Cname => Ok(normalize_name(&Route53Client::extract_hostname(
+            presigned_url,
+        )?)),
+        DnsRecordType::Alias => Ok(normalize_name(s3_website_endpoint(presigned_url)?.1)),
+    }
+}
+
+/// Whether a record is a single record of the given type pointing at `expected`
+pub fn points_to(record: Option<&RecordValue>, expected: &str, record_type: DnsRecordType) -> bool {
+    record.is_some_and(|record| {
+        record.record_type == record_type.rr_type().as_str()
+            && record.values.len() == 1
+            && normalize_name(&record.values[0]) == expected
+    })
+}
+
+/// Compare the live record against the presigned URL's host and upsert on drift
+///
+/// Returns `true` if a corrective upsert was made.
+#[instrument(skip(dns, presigned_url))]
+pub async fn reconcile_record(
+    dns: &dyn DnsBackend,
+    hosted_zone_id: &str,
+    short_url: &str,
+    presigned_url: &str,
+    record: DnsRecordConfig,
+    mapping_id: Option<Uuid>,
+) -> Result<bool> {
+    let expected = expected_target(presigned_url, record.record_type)?;
+    let current = dns.get_record(hosted_zone_id, short_url).await?;
+
+    if points_to(current.as_ref(), &expected, record.record_type) {
+        return Ok(false);
+    }
+
+    warn!(
+        "DNS record {} drifted (found {:?}, expected {}), re-upserting",
+        short_url, current, expected
+    );
+    dns.update_dns_record(hosted_zone_id, short_url, presigned_url, record, mapping_id)
+        .await?;
+
+    Ok(true)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,6 +799,11 @@ mod tests {
         let url = "https://my-bucket.s3.amazonaws.com/path/to/file?X-Amz-Signature=abc";
         let hostname = Route53Client::extract_hostname(url).unwrap();
         assert_eq!(hostname, "my-bucket.s3.amazonaws.com.");
+
+        // Custom endpoints may use another port; a CNAME can't carry it
+        let url = "http://minio.example.com:9000/my-bucket/file?X-Amz-Signature=abc";
+        let hostname = Route53Client::extract_hostname(url).unwrap();
+        assert_eq!(hostname, "minio.example.com.");
     }
 
     #[tokio::test]
@@ -99,4 +815,274 @@ mod tests {
         // Just verify we can create the client
         assert!(std::mem::size_of_val(&route53_client) > 0);
     }
+
+    #[test]
+    fn test_normalize_hosted_zone_id() {
+        assert_eq!(
+            normalize_hosted_zone_id("/hostedzone/Z1234567890ABC").unwrap(),
+            "Z1234567890ABC"
+        );
+        assert_eq!(
+            normalize_hosted_zone_id(" Z1234567890ABC ").unwrap(),
+            "Z1234567890ABC"
+        );
+
+        for invalid in [
+            "example.com",
+            "Z",
+            "",
+            "/hostedzone/",
+            "z123abc",
+            "Z123-456",
+        ] {
+            assert!(normalize_hosted_zone_id(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    const PRESIGNED: &str = "https://my-bucket.s3.amazonaws.com/file?X-Amz-Signature=abc";
+
+    fn cname(value: &str) -> RecordValue {
+        RecordValue {
+            record_type: "CNAME".to_string(),
+            values: vec![value.to_string()],
+            ttl: Some(DEFAULT_TTL),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_fixes_drifted_record() {
+        let mut dns = MockDnsBackend::new();
+        dns.expect_get_record()
+            .returning(|_, _| Ok(Some(cname("somewhere-else.example.com."))));
+        dns.expect_update_dns_record()
+            .withf(|zone, name, url, record, _| {
+                zone == "Z1234567890ABC"
+                    && name == "short.example.com"
+                    && url == PRESIGNED
+                    && *record == DnsRecordConfig::default()
+            })
+            .times(1)
+            .returning(|_, _, _, _, _| Ok(()));
+
+        let fixed = reconcile_record(
+            &dns,
+            "Z1234567890ABC",
+            "short.example.com",
+            PRESIGNED,
+            DnsRecordConfig::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(fixed);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_leaves_matching_record() {
+        let mut dns = MockDnsBackend::new();
+        dns.expect_get_record()
+            .returning(|_, _| Ok(Some(cname("My-Bucket.s3.amazonaws.com."))));
+        dns.expect_update_dns_record().times(0);
+
+        let fixed = reconcile_record(
+            &dns,
+            "Z1234567890ABC",
+            "short.example.com",
+            PRESIGNED,
+            DnsRecordConfig::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(!fixed);
+    }
+
+    #[test]
+    fn test_upsert_change_with_custom_ttl() {
+        let record = DnsRecordConfig {
+            record_type: DnsRecordType::Cname,
+            ttl: 60,
+        };
+        let change = Route53Client::upsert_change("short.example.com", PRESIGNED, record).unwrap();
+        let record_set = change.resource_record_set().unwrap();
+        assert_eq!(record_set.r#type(), &RrType::Cname);
+        assert_eq!(record_set.ttl(), Some(60));
+        assert_eq!(
+            record_set.resource_records()[0].value(),
+            "my-bucket.s3.amazonaws.com."
+        );
+    }
+
+    #[test]
+    fn test_upsert_change_with_alias() {
+        let record = DnsRecordConfig {
+            record_type: DnsRecordType::Alias,
+            ttl: DEFAULT_TTL,
+        };
+        let presigned = "https://my-bucket.s3.us-west-2.amazonaws.com/file?X-Amz-Signature=abc";
+        let change = Route53Client::upsert_change("example.com", presigned, record).unwrap();
+        let record_set = change.resource_record_set().unwrap();
+        assert_eq!(record_set.r#type(), &RrType::A);
+        assert_eq!(record_set.ttl(), None);
+        let alias = record_set.alias_target().unwrap();
+        assert_eq!(alias.hosted_zone_id(), "Z3BJ6K6RIION7M");
+        assert_eq!(alias.dns_name(), "s3-website-us-west-2.amazonaws.com");
+
+        // Custom endpoints have no website endpoint to alias
+        let minio = "http://minio.example.com:9000/my-bucket/file?X-Amz-Signature=abc";
+        assert!(Route53Client::upsert_change("example.com", minio, record).is_err());
+    }
+
+    #[test]
+    fn test_zone_contains() {
+        assert!(zone_contains("example.com.", "short.example.com"));
+        assert!(zone_contains("example.com.", "a.b.example.com."));
+        assert!(zone_contains("Example.COM.", "short.example.com"));
+        assert!(zone_contains("example.com.", "example.com"));
+        assert!(!zone_contains("example.com.", "short.example.org"));
+        assert!(!zone_contains("example.com.", "notexample.com"));
+        assert!(!zone_contains("sub.example.com.", "example.com"));
+    }
+
+    #[test]
+    fn test_s3_region_from_host() {
+        assert_eq!(s3_region("my-bucket.s3.amazonaws.com"), Some("us-east-1"));
+        assert_eq!(
+            s3_region("my-bucket.s3.eu-west-1.amazonaws.com"),
+            Some("eu-west-1")
+        );
+        assert_eq!(s3_region("s3.eu-west-1.amazonaws.com"), Some("eu-west-1"));
+        assert_eq!(
+            s3_region("my-bucket.s3-ap-southeast-2.amazonaws.com"),
+            Some("ap-southeast-2")
+        );
+        assert_eq!(s3_region("minio.example.com"), None);
+        assert_eq!(s3_region("my-bucket.example.amazonaws.com"), None);
+    }
+
+    #[tokio::test]
+    async fn test_apply_changes_falls_back_to_individual_on_invalid_batch() {
+        let changes: Vec<Change> = ["a.example.com", "bad.example.com", "c.example.com"]
+            .iter()
+            .map(|name| {
+                Route53Client::upsert_change(name, PRESIGNED, DnsRecordConfig::default()).unwrap()
+            })
+            .collect();
+
+        let applied = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let report = apply_in_batches(changes, |batch| {
+            let applied = applied.clone();
+            async move {
+                let names: Vec<String> = batch
+                    .iter()
+                    .map(|c| c.resource_record_set().unwrap().name().to_string())
+                    .collect();
+                if names.iter().any(|n| n == "bad.example.com") {
+                    return Err(BatchError::InvalidBatch(anyhow::anyhow!("bad record")));
+                }
+                applied.lock().unwrap().extend(names);
+                Ok(())
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            report,
+            ApplyReport {
+                applied: 2,
+                rejected: 1
+            }
+        );
+        assert_eq!(
+            *applied.lock().unwrap(),
+            vec!["a.example.com".to_string(), "c.example.com".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_concurrent_submissions() {
+        let limiter = std::sync::Arc::new(RateLimiter::new(20));
+        let start = Instant::now();
+
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move {
+                    limiter.acquire().await;
+                    Instant::now()
+                })
+            })
+            .collect();
+
+        let mut times = Vec::new();
+        for handle in handles {
+            times.push(handle.await.unwrap());
+        }
+        times.sort();
+
+        // 20/s means one call every 50ms; allow a little scheduler slack
+        for pair in times.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(45));
+        }
+        assert!(times[4] - start >= Duration::from_millis(190));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_logs_batch_without_sending() {
+        let id = Uuid::new_v4();
+        let batch = Route53Client::upsert_batch(
+            "short.example.com",
+            PRESIGNED,
+            DnsRecordConfig::default(),
+            Some(id),
+        )
+        .unwrap();
+        assert_eq!(
+            batch.comment(),
+            Some(format!("s3-buddy: {} short.example.com", id).as_str())
+        );
+        let [change] = batch.changes() else {
+            panic!("expected one change: {:?}", batch.changes());
+        };
+        assert_eq!(change.action(), &ChangeAction::Upsert);
+        let record_set = change.resource_record_set().unwrap();
+        assert_eq!(record_set.name(), "short.example.com");
+        assert_eq!(record_set.r#type(), &RrType::Cname);
+        assert_eq!(record_set.ttl(), Some(DEFAULT_TTL));
+        assert_eq!(
+            record_set.resource_records()[0].value(),
+            "my-bucket.s3.amazonaws.com."
+        );
+
+        let sent = std::sync::Mutex::new(Vec::new());
+        let send = |batch: ChangeBatch| {
+            sent.lock().unwrap().push(batch);
+            async { Ok(()) }
+        };
+
+        let was_sent = send_or_log(true, "Z1234567890ABC", batch.clone(), send)
+            .await
+            .unwrap();
+        assert!(!was_sent);
+        assert!(sent.lock().unwrap().is_empty());
+
+        let was_sent = send_or_log(false, "Z1234567890ABC", batch.clone(), send)
+            .await
+            .unwrap();
+        assert!(was_sent);
+        assert_eq!(*sent.lock().unwrap(), vec![batch]);
+    }
+
+    #[test]
+    fn test_change_comment_identifies_mapping() {
+        let id = Uuid::new_v4();
+        let comment = change_comment(Some(id), "short.example.com");
+        assert_eq!(comment, format!("s3-buddy: {} short.example.com", id));
+
+        let long_name = format!("{}.example.com", "a".repeat(300));
+        let comment = change_comment(Some(id), &long_name);
+        assert!(comment.contains(&id.to_string()));
+        assert_eq!(comment.chars().count(), MAX_COMMENT_LENGTH);
+    }
 }
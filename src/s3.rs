@@ -1,16 +1,525 @@
 use anyhow::{Context, Result};
-use aws_sdk_s3::presigning::PresigningConfig;
-use std::time::Duration;
-use tracing::{info, instrument};
+use async_trait::async_trait;
+use aws_sdk_s3::config::{ProvideCredentials, SharedCredentialsProvider};
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::presigning::{PresignedRequest, PresigningConfig};
+use aws_sdk_s3::types::RequestPayer;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use tracing::{info, instrument, warn};
+use utoipa::ToSchema;
+
+use crate::retry;
+
+/// An object, or objects under a prefix, that doesn't exist, as opposed to
+/// one that couldn't be checked
+#[derive(Debug)]
+pub struct ObjectNotFound {
+    pub s3_url: String,
+}
+
+impl std::fmt::Display for ObjectNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.s3_url.ends_with('/') {
+            write!(f, "No objects under {}", self.s3_url)
+        } else {
+            write!(f, "No object at {}", self.s3_url)
+        }
+    }
+}
+
+impl std::error::Error for ObjectNotFound {}
+
+/// Maximum lifetime AWS SigV4 allows for a presigned URL (7 days)
+pub const MAX_PRESIGN_DURATION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Shortest presign duration accepted; anything shorter is expired on arrival
+pub const MIN_PRESIGN_DURATION: Duration = Duration::from_secs(1);
+
+/// Default cap on presigned URL length, safe for common clients and CDNs
+pub const DEFAULT_MAX_URL_LENGTH: usize = 8192;
+
+/// How long a failed bucket region lookup is remembered before trying again
+const REGION_RETRY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// How long the signing credentials' expiry is remembered before asking the
+/// provider again
+const CREDENTIALS_RECHECK_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// HTTP method a presigned URL is signed for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresignMethod {
+    /// Download the object
+    #[default]
+    Get,
+    /// Check the object's existence, size and metadata without downloading it
+    Head,
+    /// Upload the object, replacing any existing one
+    Put,
+}
+
+/// How the bucket is addressed in presigned URLs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressingStyle {
+    /// Let the SDK choose, based on the bucket name and endpoint
+    #[default]
+    Auto,
+    /// `https://s3.<region>.amazonaws.com/<bucket>/<key>`, for bucket names
+    /// with dots that break TLS validation as a hostname
+    Path,
+    /// `https://<bucket>.s3.<region>.amazonaws.com/<key>`
+    VirtualHosted,
+}
+
+/// Where S3 requests are sent, for S3-compatible services such as MinIO,
+/// Cloudflare R2 or LocalStack
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct S3Endpoint {
+    /// Endpoint used instead of AWS, e.g. `http://localhost:9000`; presigned
+    /// URLs point at its host
+    pub url: Option<String>,
+    /// Put the bucket in the path rather than the hostname, as most
+    /// self-hosted services require
+    pub force_path_style: bool,
+}
+
+impl S3Endpoint {
+    /// Client for this endpoint, with everything else from `config`
+    pub fn client(&self, mut config: aws_sdk_s3::config::Builder) -> aws_sdk_s3::Client {
+        if let Some(url) = &self.url {
+            config = config.endpoint_url(url);
+        }
+        if self.force_path_style {
+            config = config.force_path_style(true);
+        }
+        aws_sdk_s3::Client::from_conf(config.build())
+    }
+}
+
+/// Size and modification time of an existing object
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeadInfo {
+    pub content_length: Option<i64>,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Per-request options applied when presigning
+#[derive(Debug, Clone, Default)]
+pub struct PresignOptions {
+    /// Method the URL is signed for
+    pub method: PresignMethod,
+    /// Sign the request for a requester-pays bucket
+    pub requester_pays: bool,
+    /// Use the S3 Transfer Acceleration endpoint
+    pub accelerate: bool,
+    /// Extra query parameters added to the request before it is signed
+    pub extra_query_params: BTreeMap<String, String>,
+    /// Path-style or virtual-hosted-style bucket addressing
+    pub addressing_style: AddressingStyle,
+    /// `Content-Type` S3 responds with instead of the object's own (GET only)
+    pub response_content_type: Option<String>,
+    /// `Content-Disposition` S3 responds with, e.g. from
+    /// [`attachment_disposition`] (GET only)
+    pub response_content_disposition: Option<String>,
+    /// `Content-Type` the upload must be sent with (PUT only)
+    pub content_type: Option<String>,
+    /// Object version to read instead of the latest (GET and HEAD only)
+    pub version_id: Option<String>,
+}
+
+/// `attachment; filename="..."` making browsers download under `filename`
+///
+/// Control characters (so no CR/LF header injection) are dropped and quotes,
+/// backslashes and path separators replaced, so the value can't escape its
+/// quoted string. `None` if nothing usable is left.
+pub fn attachment_disposition(filename: &str) -> Option<String> {
+    let sanitized: String = filename
+        .chars()
+        .filter(|c| !c.is_control())
+        .map(|c| match c {
+            '"' | '\\' | '/' => '_',
+            c => c,
+        })
+        .collect();
+    let sanitized = sanitized.trim();
+    if sanitized.is_empty() {
+        return None;
+    }
+    Some(format!("attachment; filename=\"{}\"", sanitized))
+}
+
+/// Object storage operations, abstracted so refreshes can run without AWS
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Generate a presigned GET URL for an object
+    async fn generate_presigned_url(
+        &self,
+        bucket: &str,
+        key: &str,
+        duration: Duration,
+        options: &PresignOptions,
+    ) -> Result<String>;
+
+    /// Check an object exists and is readable with these credentials,
+    /// failing with `ObjectNotFound` if it doesn't exist
+    async fn head_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        options: &PresignOptions,
+    ) -> Result<HeadInfo>;
+
+    /// Whether any object exists under `prefix`
+    async fn prefix_exists(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        options: &PresignOptions,
+    ) -> Result<bool>;
+
+    /// Region requests are signed for, if known
+    fn region(&self) -> Option<String> {
+        None
+    }
+
+    /// Time left before temporary credentials expire, if they do
+    async fn credentials_lifetime(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Cheap call proving the service is reachable with these credentials:
+    /// a look at `probe_bucket` if given, or listing buckets
+    async fn check_access(&self, _probe_bucket: Option<&str>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Warning for a presigned URL that stops working before `requested`
+/// because the credentials signing it expire sooner
+pub fn credentials_warning(requested: Duration, lifetime: Option<Duration>) -> Option<String> {
+    let lifetime = lifetime.filter(|lifetime| *lifetime < requested)?;
+    Some(format!(
+        "credentials expire in {}s, so the URL is only valid for that long instead of {}s",
+        lifetime.as_secs(),
+        requested.as_secs()
+    ))
+}
+
+/// What a client is configured for beyond the default one: region,
+/// acceleration and addressing style
+type ClientKey = (Option<String>, bool, AddressingStyle);
 
 /// S3 client wrapper for presigned URL operations
 pub struct S3Client {
     client: aws_sdk_s3::Client,
+    max_url_length: usize,
+    /// How far before now URLs are signed, to tolerate client clock drift
+    clock_skew: Duration,
+    discover_regions: bool,
+    /// Discovered bucket regions, keyed by bucket name
+    regions: RwLock<HashMap<String, String>>,
+    /// When each bucket's region lookup last failed, so it isn't retried on
+    /// every presign
+    region_failures: RwLock<HashMap<String, Instant>>,
+    /// Clients for other regions and endpoints, built on first use
+    clients: RwLock<HashMap<ClientKey, aws_sdk_s3::Client>>,
+    /// Credentials the client signs with, checked for expiry
+    credentials: Option<SharedCredentialsProvider>,
+    /// Their expiry (`None` if they don't expire) and when it was looked up,
+    /// so presigning doesn't ask the provider every time
+    credentials_expiry: RwLock<Option<(Instant, Option<SystemTime>)>>,
 }
 
 impl S3Client {
     pub fn new(client: aws_sdk_s3::Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            max_url_length: DEFAULT_MAX_URL_LENGTH,
+            clock_skew: Duration::ZERO,
+            discover_regions: false,
+            regions: RwLock::new(HashMap::new()),
+            region_failures: RwLock::new(HashMap::new()),
+            clients: RwLock::new(HashMap::new()),
+            credentials: None,
+            credentials_expiry: RwLock::new(None),
+        }
+    }
+
+    /// Check the expiry of the credentials the client signs with, to warn
+    /// when URLs would outlive them
+    pub fn with_credentials_provider(mut self, provider: SharedCredentialsProvider) -> Self {
+        self.credentials = Some(provider);
+        self
+    }
+
+    /// Time left before the signing credentials expire, for temporary credentials
+    ///
+    /// The expiry is looked up again after `CREDENTIALS_RECHECK_AFTER`, or
+    /// once it passes, when the provider will have rotated them.
+    pub async fn credentials_lifetime(&self) -> Option<Duration> {
+        let provider = self.credentials.as_ref()?;
+        let cached = *self.credentials_expiry.read().await;
+        let expiry = match cached {
+            Some((checked_at, expiry))
+                if checked_at.elapsed() < CREDENTIALS_RECHECK_AFTER
+                    && expiry.is_none_or(|expiry| expiry > SystemTime::now()) =>
+            {
+                expiry
+            }
+            _ => {
+                let expiry = provider.provide_credentials().await.ok()?.expiry();
+                *self.credentials_expiry.write().await = Some((Instant::now(), expiry));
+                expiry
+            }
+        };
+        Some(
+            expiry?
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Presign against each bucket's own region, discovered on first use
+    pub fn with_region_discovery(mut self, enabled: bool) -> Self {
+        self.discover_regions = enabled;
+        self
+    }
+
+    /// Sign URLs as of `skew` ago, extending their expiry by the same amount
+    /// so clients whose clocks run behind or ahead still accept them
+    pub fn with_clock_skew(mut self, skew: Duration) -> Self {
+        self.clock_skew = skew;
+        self
+    }
+
+    /// Reject generated URLs longer than `max` characters
+    pub fn with_max_url_length(mut self, max: usize) -> Self {
+        self.max_url_length = max;
+        self
+    }
+
+    /// Region the underlying client signs for, if configured
+    pub fn region(&self) -> Option<String> {
+        self.client.config().region().map(|r| r.to_string())
+    }
+
+    /// Discover a bucket's region, caching the result; a failed lookup is
+    /// remembered and not retried for a few minutes
+    #[instrument(skip(self))]
+    pub async fn discover_region(&self, bucket: &str) -> Result<String> {
+        // Boxed so callers' futures don't inline the SDK's deeply nested one
+        Box::pin(self.discover_region_with(bucket, || self.lookup_bucket_region(bucket))).await
+    }
+
+    async fn discover_region_with<F, Fut>(&self, bucket: &str, lookup: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<String>>,
+    {
+        if let Some(region) = self.regions.read().await.get(bucket) {
+            return Ok(region.clone());
+        }
+        if let Some(failed_at) = self.region_failures.read().await.get(bucket) {
+            if failed_at.elapsed() < REGION_RETRY_AFTER {
+                anyhow::bail!("Region lookup for bucket {} failed recently", bucket);
+            }
+        }
+
+        let region = match lookup().await {
+            Ok(region) => region,
+            Err(e) => {
+                warn!(
+                    "Could not discover region for bucket {}, using default: {:#}",
+                    bucket, e
+                );
+                self.region_failures
+                    .write()
+                    .await
+                    .insert(bucket.to_string(), Instant::now());
+                return Err(e);
+            }
+        };
+        self.region_failures.write().await.remove(bucket);
+        info!("Discovered region {} for bucket {}", region, bucket);
+        self.regions
+            .write()
+            .await
+            .insert(bucket.to_string(), region.clone());
+
+        Ok(region)
+    }
+
+    async fn lookup_bucket_region(&self, bucket: &str) -> Result<String> {
+        let output = self
+            .client
+            .get_bucket_location()
+            .bucket(bucket)
+            .send()
+            .await
+            .context("Failed to get bucket location")?;
+
+        // Buckets in us-east-1 report an empty location constraint, and "EU"
+        // is the legacy name for eu-west-1
+        let region = match output.location_constraint().map(|c| c.as_str()) {
+            None | Some("") => "us-east-1",
+            Some("EU") => "eu-west-1",
+            Some(other) => other,
+        };
+
+        Ok(region.to_string())
+    }
+
+    /// Client to use for a bucket, switching region if discovery is enabled and
+    /// endpoint if acceleration is requested; clients other than the default
+    /// are built once and reused
+    async fn client_for_bucket(
+        &self,
+        bucket: &str,
+        options: &PresignOptions,
+    ) -> aws_sdk_s3::Client {
+        // Access point ARNs carry their own region
+        let region = if self.discover_regions && !bucket.starts_with("arn:") {
+            // Failures were logged when the lookup failed
+            self.discover_region(bucket)
+                .await
+                .ok()
+                .filter(|region| Some(region.as_str()) != self.region().as_deref())
+        } else {
+            None
+        };
+
+        if region.is_none()
+            && !options.accelerate
+            && options.addressing_style == AddressingStyle::Auto
+        {
+            return self.client.clone();
+        }
+
+        let key = (region, options.accelerate, options.addressing_style);
+        if let Some(client) = self.clients.read().await.get(&key) {
+            return client.clone();
+        }
+        let mut config = self.client.config().to_builder();
+        if let Some(region) = &key.0 {
+            config = config.region(aws_sdk_s3::config::Region::new(region.clone()));
+        }
+        if options.accelerate {
+            config = config.accelerate(true);
+        }
+        match options.addressing_style {
+            AddressingStyle::Auto => {}
+            AddressingStyle::Path => config = config.force_path_style(true),
+            AddressingStyle::VirtualHosted => config = config.force_path_style(false),
+        }
+        let client = aws_sdk_s3::Client::from_conf(config.build());
+        self.clients.write().await.insert(key, client.clone());
+        client
+    }
+
+    /// Check an object exists and is readable
+    #[instrument(skip(self))]
+    pub async fn head_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        options: &PresignOptions,
+    ) -> Result<HeadInfo> {
+        let client = self.client_for_bucket(bucket, options).await;
+        let mut request = client
+            .head_object()
+            .bucket(bucket)
+            .key(key)
+            .set_version_id(options.version_id.clone());
+        if options.requester_pays {
+            request = request.request_payer(RequestPayer::Requester);
+        }
+
+        let output = match request.send().await {
+            Ok(output) => output,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => {
+                return Err(ObjectNotFound {
+                    s3_url: format!("s3://{}/{}", bucket, key),
+                }
+                .into())
+            }
+            Err(e) => {
+                let transient = sdk_error_is_transient(&e);
+                let err = anyhow::Error::new(e)
+                    .context(format!("Failed to read s3://{}/{}", bucket, key));
+                return Err(retry::mark_transient(err, transient));
+            }
+        };
+
+        Ok(HeadInfo {
+            content_length: output.content_length(),
+            last_modified: output
+                .last_modified()
+                .and_then(|t| DateTime::from_timestamp(t.secs(), t.subsec_nanos())),
+        })
+    }
+
+    /// Whether any object exists under `prefix`, listing at most one key
+    #[instrument(skip(self))]
+    pub async fn prefix_exists(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        options: &PresignOptions,
+    ) -> Result<bool> {
+        let client = self.client_for_bucket(bucket, options).await;
+        let mut request = client
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .max_keys(1);
+        if options.requester_pays {
+            request = request.request_payer(RequestPayer::Requester);
+        }
+
+        let output = match request.send().await {
+            Ok(output) => output,
+            Err(e) => {
+                let transient = sdk_error_is_transient(&e);
+                let err = anyhow::Error::new(e)
+                    .context(format!("Failed to list s3://{}/{}", bucket, prefix));
+                return Err(retry::mark_transient(err, transient));
+            }
+        };
+
+        Ok(!output.contents().is_empty())
+    }
+
+    /// Check S3 answers with these credentials, via `HeadBucket` on
+    /// `probe_bucket` or, without one, `ListBuckets`
+    pub async fn check_access(&self, probe_bucket: Option<&str>) -> Result<()> {
+        match probe_bucket {
+            Some(bucket) => {
+                let client = self
+                    .client_for_bucket(bucket, &PresignOptions::default())
+                    .await;
+                client
+                    .head_bucket()
+                    .bucket(bucket)
+                    .send()
+                    .await
+                    .with_context(|| format!("Failed to head bucket {}", bucket))?;
+            }
+            None => {
+                self.client
+                    .list_buckets()
+                    .max_buckets(1)
+                    .send()
+                    .await
+                    .context("Failed to list buckets")?;
+            }
+        }
+        Ok(())
     }
 
     /// Generate a presigned URL for an S3 object
@@ -20,29 +529,263 @@ impl S3Client {
         bucket: &str,
         key: &str,
         duration: Duration,
+        options: &PresignOptions,
+    ) -> Result<String> {
+        // Boxed so callers' futures don't inline the SDK's deeply nested ones,
+        // which can push them past rustc's layout query depth limit
+        Box::pin(self.presign_checked(bucket, key, duration, options)).await
+    }
+
+    /// Presign after checking the duration, then check the URL's length
+    async fn presign_checked(
+        &self,
+        bucket: &str,
+        key: &str,
+        duration: Duration,
+        options: &PresignOptions,
     ) -> Result<String> {
         info!(
             "Generating presigned URL for s3://{}/{} with duration {:?}",
             bucket, key, duration
         );
 
-        let presigning_config =
-            PresigningConfig::expires_in(duration).context("Failed to create presigning config")?;
+        if duration < MIN_PRESIGN_DURATION {
+            anyhow::bail!(
+                "Presign duration {:?} is below the minimum of {:?}",
+                duration,
+                MIN_PRESIGN_DURATION
+            );
+        }
+        // The skew extends the expiry, so it counts against the maximum too
+        if duration + self.clock_skew > MAX_PRESIGN_DURATION {
+            anyhow::bail!(
+                "Presign duration {:?} plus clock skew {:?} exceeds the AWS maximum of {:?}",
+                duration,
+                self.clock_skew,
+                MAX_PRESIGN_DURATION
+            );
+        }
 
-        let presigned_request = self
-            .client
-            .get_object()
-            .bucket(bucket)
-            .key(key)
-            .presigned(presigning_config)
-            .await
-            .context("Failed to generate presigned URL")?;
+        let presigned_request = self.presign_request(bucket, key, duration, options).await?;
 
         let url = presigned_request.uri().to_string();
+        if url.len() > self.max_url_length {
+            warn!(
+                "Presigned URL for s3://{}/{} is {} characters, over the {} limit",
+                bucket,
+                key,
+                url.len(),
+                self.max_url_length
+            );
+            anyhow::bail!(
+                "Presigned URL length {} exceeds maximum of {}",
+                url.len(),
+                self.max_url_length
+            );
+        }
         info!("Generated presigned URL: {}", url);
 
         Ok(url)
     }
+
+    /// Generate a presigned HEAD URL for an S3 object
+    pub async fn generate_presigned_head_url(
+        &self,
+        bucket: &str,
+        key: &str,
+        duration: Duration,
+    ) -> Result<String> {
+        let options = PresignOptions {
+            method: PresignMethod::Head,
+            ..Default::default()
+        };
+        self.generate_presigned_url(bucket, key, duration, &options)
+            .await
+    }
+
+    /// Generate a presigned PUT URL for uploading an S3 object
+    ///
+    /// With a `content_type`, uploads must send that `Content-Type` header.
+    pub async fn generate_presigned_put_url(
+        &self,
+        bucket: &str,
+        key: &str,
+        duration: Duration,
+        content_type: Option<&str>,
+    ) -> Result<String> {
+        let options = PresignOptions {
+            method: PresignMethod::Put,
+            content_type: content_type.map(str::to_string),
+            ..Default::default()
+        };
+        self.generate_presigned_url(bucket, key, duration, &options)
+            .await
+    }
+
+    /// Sign a request for the object with the method in `options`
+    async fn presign_request(
+        &self,
+        bucket: &str,
+        key: &str,
+        duration: Duration,
+        options: &PresignOptions,
+    ) -> Result<PresignedRequest> {
+        let presigning_config = PresigningConfig::builder()
+            .start_time(SystemTime::now() - self.clock_skew)
+            .expires_in(duration + self.clock_skew)
+            .build()
+            .context("Failed to create presigning config")?;
+
+        let client = self.client_for_bucket(bucket, options).await;
+        let request_payer = options.requester_pays.then_some(RequestPayer::Requester);
+
+        // Query parameters are added before signing so S3 accepts them as part
+        // of the signature
+        let query = (!options.extra_query_params.is_empty())
+            .then(|| encode_query(&options.extra_query_params));
+        // The SDK falls back to path style for bucket names that aren't valid
+        // hostnames, so virtual-hosted style is forced by rewriting the URI
+        let virtual_host_bucket = (options.addressing_style == AddressingStyle::VirtualHosted)
+            .then(|| bucket.to_string());
+        let add_query = move |req: &mut aws_sdk_s3::config::http::HttpRequest| {
+            if let Some(bucket) = &virtual_host_bucket {
+                if let Some(uri) = virtual_hosted_uri(req.uri(), bucket) {
+                    if let Err(e) = req.set_uri(uri) {
+                        warn!("Could not use virtual-hosted style: {}", e);
+                    }
+                }
+            }
+            if let Some(query) = &query {
+                let separator = if req.uri().contains('?') { '&' } else { '?' };
+                let uri = format!("{}{}{}", req.uri(), separator, query);
+                if let Err(e) = req.set_uri(uri) {
+                    warn!("Could not add query parameters: {}", e);
+                }
+            }
+        };
+
+        match options.method {
+            PresignMethod::Get => client
+                .get_object()
+                .bucket(bucket)
+                .key(key)
+                .set_request_payer(request_payer)
+                .set_version_id(options.version_id.clone())
+                .set_response_content_type(options.response_content_type.clone())
+                .set_response_content_disposition(options.response_content_disposition.clone())
+                .customize()
+                .mutate_request(add_query)
+                .presigned(presigning_config)
+                .await
+                .context("Failed to generate presigned URL"),
+            PresignMethod::Head => client
+                .head_object()
+                .bucket(bucket)
+                .key(key)
+                .set_request_payer(request_payer)
+                .set_version_id(options.version_id.clone())
+                .customize()
+                .mutate_request(add_query)
+                .presigned(presigning_config)
+                .await
+                .context("Failed to generate presigned HEAD URL"),
+            PresignMethod::Put => client
+                .put_object()
+                .bucket(bucket)
+                .key(key)
+                .set_request_payer(request_payer)
+                .set_content_type(options.content_type.clone())
+                .customize()
+                .mutate_request(add_query)
+                .presigned(presigning_config)
+                .await
+                .context("Failed to generate presigned PUT URL"),
+        }
+    }
+}
+
+/// Whether an S3 call failed in a way that may clear up: timeouts, dropped
+/// connections, throttling and server errors
+fn sdk_error_is_transient<E: ProvideErrorMetadata>(err: &SdkError<E>) -> bool {
+    match err {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => {
+            true
+        }
+        _ => retry::is_transient(err.code(), err.raw_response().map(|r| r.status().as_u16())),
+    }
+}
+
+/// Move the bucket of a path-style URI into the hostname, leaving other URIs alone
+fn virtual_hosted_uri(uri: &str, bucket: &str) -> Option<String> {
+    let (scheme, rest) = uri.split_once("://")?;
+    let (host, path) = rest.split_once('/')?;
+    let path = path
+        .strip_prefix(bucket)
+        .filter(|path| path.is_empty() || path.starts_with(['/', '?']))?;
+    Some(format!("{}://{}.{}{}", scheme, bucket, host, path))
+}
+
+/// Percent-encode query parameters the way SigV4 canonicalizes them
+fn encode_query(params: &BTreeMap<String, String>) -> String {
+    fn encode(s: &str) -> String {
+        s.bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                    (b as char).to_string()
+                }
+                _ => format!("%{:02X}", b),
+            })
+            .collect()
+    }
+
+    params
+        .iter()
+        .map(|(k, v)| format!("{}={}", encode(k), encode(v)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[async_trait]
+impl StorageBackend for S3Client {
+    async fn generate_presigned_url(
+        &self,
+        bucket: &str,
+        key: &str,
+        duration: Duration,
+        options: &PresignOptions,
+    ) -> Result<String> {
+        S3Client::generate_presigned_url(self, bucket, key, duration, options).await
+    }
+
+    async fn head_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        options: &PresignOptions,
+    ) -> Result<HeadInfo> {
+        S3Client::head_object(self, bucket, key, options).await
+    }
+
+    async fn prefix_exists(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        options: &PresignOptions,
+    ) -> Result<bool> {
+        S3Client::prefix_exists(self, bucket, prefix, options).await
+    }
+
+    fn region(&self) -> Option<String> {
+        S3Client::region(self)
+    }
+
+    async fn credentials_lifetime(&self) -> Option<Duration> {
+        S3Client::credentials_lifetime(self).await
+    }
+
+    async fn check_access(&self, probe_bucket: Option<&str>) -> Result<()> {
+        S3Client::check_access(self, probe_bucket).await
+    }
 }
 
 #[cfg(test)]
@@ -58,4 +801,503 @@ mod tests {
         // Just verify we can create the client
         assert!(std::mem::size_of_val(&s3_client) > 0);
     }
+
+    fn static_config() -> aws_sdk_s3::config::Builder {
+        aws_sdk_s3::Config::builder()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new("us-east-1"))
+            .credentials_provider(aws_sdk_s3::config::Credentials::new(
+                "AKIDEXAMPLE",
+                "secret",
+                None,
+                None,
+                "test",
+            ))
+    }
+
+    #[tokio::test]
+    async fn test_custom_endpoint_presigns_against_its_host() {
+        let endpoint = S3Endpoint {
+            url: Some("http://localhost:9000".to_string()),
+            force_path_style: true,
+        };
+        let client = S3Client::new(endpoint.client(static_config()));
+
+        let url = client
+            .generate_presigned_url(
+                "my-bucket",
+                "file.pdf",
+                Duration::from_secs(3600),
+                &PresignOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert!(url.starts_with("http://localhost:9000/my-bucket/file.pdf?"));
+        assert!(url.contains("X-Amz-Signature="));
+
+        // Without path style the bucket moves into the endpoint's hostname
+        let endpoint = S3Endpoint {
+            url: Some("https://minio.example.com".to_string()),
+            force_path_style: false,
+        };
+        let client = S3Client::new(endpoint.client(static_config()));
+        let url = client
+            .generate_presigned_url(
+                "my-bucket",
+                "file.pdf",
+                Duration::from_secs(3600),
+                &PresignOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert!(url.starts_with("https://my-bucket.minio.example.com/file.pdf?"));
+    }
+
+    fn static_client() -> S3Client {
+        S3Client::new(aws_sdk_s3::Client::from_conf(static_config().build()))
+    }
+
+    #[tokio::test]
+    async fn test_requester_pays_is_signed() {
+        let client = static_client();
+        let duration = Duration::from_secs(3600);
+
+        let options = PresignOptions {
+            requester_pays: true,
+            ..Default::default()
+        };
+        let url = client
+            .generate_presigned_url("bucket", "key", duration, &options)
+            .await
+            .unwrap();
+        assert!(url.contains("x-amz-request-payer"));
+
+        let url = client
+            .generate_presigned_url("bucket", "key", duration, &PresignOptions::default())
+            .await
+            .unwrap();
+        assert!(!url.contains("x-amz-request-payer"));
+    }
+
+    #[tokio::test]
+    async fn test_over_length_url_is_rejected() {
+        let client = static_client().with_max_url_length(64);
+        let result = client
+            .generate_presigned_url(
+                "bucket",
+                "key",
+                Duration::from_secs(3600),
+                &PresignOptions::default(),
+            )
+            .await;
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("exceeds maximum of 64"));
+    }
+
+    #[tokio::test]
+    async fn test_zero_duration_is_rejected() {
+        let client = static_client();
+        let result = client
+            .generate_presigned_url("bucket", "key", Duration::ZERO, &PresignOptions::default())
+            .await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("below the minimum"));
+
+        let result = client
+            .generate_presigned_url(
+                "bucket",
+                "key",
+                MIN_PRESIGN_DURATION,
+                &PresignOptions::default(),
+            )
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_discover_region_is_cached() {
+        let client = static_client();
+        let lookups = std::sync::atomic::AtomicUsize::new(0);
+        let lookup = || async {
+            lookups.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok("eu-west-2".to_string())
+        };
+
+        let region = client.discover_region_with("bucket", lookup).await.unwrap();
+        assert_eq!(region, "eu-west-2");
+        let region = client.discover_region_with("bucket", lookup).await.unwrap();
+        assert_eq!(region, "eu-west-2");
+        assert_eq!(lookups.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_region_lookup_is_not_retried_at_once() {
+        let client = static_client();
+        let lookups = std::sync::atomic::AtomicUsize::new(0);
+        let lookup = || async {
+            lookups.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            anyhow::bail!("AccessDenied")
+        };
+
+        assert!(client.discover_region_with("bucket", lookup).await.is_err());
+        assert!(client.discover_region_with("bucket", lookup).await.is_err());
+        assert_eq!(lookups.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_presign_uses_discovered_region() {
+        let client = static_client().with_region_discovery(true);
+        client
+            .discover_region_with("bucket", || async { Ok("eu-west-2".to_string()) })
+            .await
+            .unwrap();
+
+        for _ in 0..2 {
+            let url = client
+                .generate_presigned_url(
+                    "bucket",
+                    "key",
+                    Duration::from_secs(3600),
+                    &PresignOptions::default(),
+                )
+                .await
+                .unwrap();
+            assert!(url.contains("eu-west-2"));
+        }
+        // The regional client is built once
+        assert_eq!(client.clients.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_addressing_style_for_dotted_bucket() {
+        let client = static_client();
+        let duration = Duration::from_secs(3600);
+        let presign = |addressing_style| {
+            let options = PresignOptions {
+                addressing_style,
+                ..Default::default()
+            };
+            let client = &client;
+            async move {
+                client
+                    .generate_presigned_url("my.bucket.com", "file.pdf", duration, &options)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let url = presign(AddressingStyle::Path).await;
+        assert!(url.starts_with("https://s3.us-east-1.amazonaws.com/my.bucket.com/file.pdf?"));
+
+        let url = presign(AddressingStyle::VirtualHosted).await;
+        assert!(url.starts_with("https://my.bucket.com.s3.us-east-1.amazonaws.com/file.pdf?"));
+        assert!(url.contains("X-Amz-Signature="));
+
+        // Plain bucket names are virtual-hosted unless path style is forced
+        let options = PresignOptions {
+            addressing_style: AddressingStyle::Path,
+            ..Default::default()
+        };
+        let url = client
+            .generate_presigned_url("bucket", "file.pdf", duration, &options)
+            .await
+            .unwrap();
+        assert!(url.starts_with("https://s3.us-east-1.amazonaws.com/bucket/file.pdf?"));
+        let url = client
+            .generate_presigned_url("bucket", "file.pdf", duration, &PresignOptions::default())
+            .await
+            .unwrap();
+        assert!(url.starts_with("https://bucket.s3.us-east-1.amazonaws.com/file.pdf?"));
+    }
+
+    #[tokio::test]
+    async fn test_accelerate_uses_accelerate_host() {
+        let client = static_client();
+        let duration = Duration::from_secs(3600);
+
+        let options = PresignOptions {
+            accelerate: true,
+            ..Default::default()
+        };
+        let url = client
+            .generate_presigned_url("bucket", "key", duration, &options)
+            .await
+            .unwrap();
+        assert!(url.starts_with("https://bucket.s3-accelerate.amazonaws.com/"));
+
+        let url = client
+            .generate_presigned_url("bucket", "key", duration, &PresignOptions::default())
+            .await
+            .unwrap();
+        assert!(!url.contains("s3-accelerate"));
+        assert!(url.starts_with("https://bucket.s3."));
+    }
+
+    #[tokio::test]
+    async fn test_extra_query_params_are_signed_into_url() {
+        let client = static_client();
+        let options = PresignOptions {
+            extra_query_params: BTreeMap::from([
+                ("utm_source".to_string(), "newsletter".to_string()),
+                ("tag".to_string(), "a b/c".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        let url = client
+            .generate_presigned_url("bucket", "key", Duration::from_secs(3600), &options)
+            .await
+            .unwrap();
+        let parsed = url::Url::parse(&url).unwrap();
+        let query: HashMap<String, String> = parsed.query_pairs().into_owned().collect();
+        assert_eq!(query["utm_source"], "newsletter");
+        assert_eq!(query["tag"], "a b/c");
+        assert!(query.contains_key("X-Amz-Signature"));
+        assert!(url.contains("tag=a%20b%2Fc"));
+    }
+
+    #[tokio::test]
+    async fn test_response_content_type_override_is_signed_into_url() {
+        let client = static_client();
+        let options = PresignOptions {
+            response_content_type: Some("application/pdf".to_string()),
+            ..Default::default()
+        };
+
+        let url = client
+            .generate_presigned_url("bucket", "key", Duration::from_secs(3600), &options)
+            .await
+            .unwrap();
+        let parsed = url::Url::parse(&url).unwrap();
+        let query: HashMap<String, String> = parsed.query_pairs().into_owned().collect();
+        assert_eq!(query["response-content-type"], "application/pdf");
+        assert!(query.contains_key("X-Amz-Signature"));
+    }
+
+    #[tokio::test]
+    async fn test_response_content_disposition_is_signed_into_url() {
+        let client = static_client();
+        let options = PresignOptions {
+            response_content_disposition: attachment_disposition("Q3 report.pdf"),
+            ..Default::default()
+        };
+
+        let url = client
+            .generate_presigned_url("bucket", "key", Duration::from_secs(3600), &options)
+            .await
+            .unwrap();
+        let parsed = url::Url::parse(&url).unwrap();
+        let query: HashMap<String, String> = parsed.query_pairs().into_owned().collect();
+        assert_eq!(
+            query["response-content-disposition"],
+            "attachment; filename=\"Q3 report.pdf\""
+        );
+    }
+
+    #[test]
+    fn test_attachment_disposition_sanitizes_filename() {
+        assert_eq!(
+            attachment_disposition("a\"b.pdf\r\nSet-Cookie: x=1").unwrap(),
+            "attachment; filename=\"a_b.pdfSet-Cookie: x=1\""
+        );
+        assert_eq!(
+            attachment_disposition("../etc\\passwd").unwrap(),
+            "attachment; filename=\".._etc_passwd\""
+        );
+        assert_eq!(attachment_disposition(" \n\t "), None);
+    }
+
+    #[tokio::test]
+    async fn test_access_point_arn_uses_access_point_host() {
+        let client = static_client();
+        let duration = Duration::from_secs(3600);
+        let options = PresignOptions::default();
+
+        let url = client
+            .generate_presigned_url(
+                "arn:aws:s3:us-east-1:123456789012:accesspoint/my-ap",
+                "key",
+                duration,
+                &options,
+            )
+            .await
+            .unwrap();
+        assert!(url
+            .starts_with("https://my-ap-123456789012.s3-accesspoint.us-east-1.amazonaws.com/key?"));
+
+        let url = client
+            .generate_presigned_url("bucket", "key", duration, &options)
+            .await
+            .unwrap();
+        assert!(url.starts_with("https://bucket.s3."));
+    }
+
+    #[tokio::test]
+    async fn test_head_presign_uses_head_method() {
+        let client = static_client();
+        let duration = Duration::from_secs(3600);
+        let head = PresignOptions {
+            method: PresignMethod::Head,
+            ..Default::default()
+        };
+
+        let request = client
+            .presign_request("bucket", "key", duration, &head)
+            .await
+            .unwrap();
+        assert_eq!(request.method(), "HEAD");
+
+        let request = client
+            .presign_request("bucket", "key", duration, &PresignOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(request.method(), "GET");
+
+        let url = client
+            .generate_presigned_head_url("bucket", "key", duration)
+            .await
+            .unwrap();
+        assert!(url.contains("X-Amz-Signature="));
+    }
+
+    #[tokio::test]
+    async fn test_presign_pins_object_version() {
+        let client = static_client();
+        let duration = Duration::from_secs(3600);
+        let pinned = PresignOptions {
+            version_id: Some("3HL4kqtJlcpXroDTDmJ".to_string()),
+            ..Default::default()
+        };
+
+        for method in [PresignMethod::Get, PresignMethod::Head] {
+            let options = PresignOptions {
+                method,
+                ..pinned.clone()
+            };
+            let url = client
+                .generate_presigned_url("bucket", "key", duration, &options)
+                .await
+                .unwrap();
+            assert!(url.contains("versionId=3HL4kqtJlcpXroDTDmJ"), "{}", url);
+        }
+
+        let url = client
+            .generate_presigned_url("bucket", "key", duration, &PresignOptions::default())
+            .await
+            .unwrap();
+        assert!(!url.contains("versionId"));
+    }
+
+    #[tokio::test]
+    async fn test_put_presign_signs_the_content_type() {
+        let client = static_client();
+        let duration = Duration::from_secs(3600);
+        let put = PresignOptions {
+            method: PresignMethod::Put,
+            content_type: Some("application/pdf".to_string()),
+            ..Default::default()
+        };
+
+        let request = client
+            .presign_request("bucket", "upload.pdf", duration, &put)
+            .await
+            .unwrap();
+        assert_eq!(request.method(), "PUT");
+        assert!(request
+            .headers()
+            .any(|(name, value)| name == "content-type" && value == "application/pdf"));
+        assert!(request
+            .uri()
+            .contains("X-Amz-SignedHeaders=content-type%3Bhost"));
+
+        let url = client
+            .generate_presigned_put_url("bucket", "upload.pdf", duration, None)
+            .await
+            .unwrap();
+        assert!(url.contains("X-Amz-Signature="));
+        assert!(url.contains("X-Amz-SignedHeaders=host"));
+    }
+
+    #[tokio::test]
+    async fn test_short_lived_credentials_are_detected() {
+        let hour = Duration::from_secs(3600);
+        assert!(static_client().credentials_lifetime().await.is_none());
+
+        let expiring = aws_sdk_s3::config::Credentials::new(
+            "AKIDEXAMPLE",
+            "secret",
+            Some("token".to_string()),
+            Some(SystemTime::now() + Duration::from_secs(600)),
+            "test",
+        );
+        let client =
+            static_client().with_credentials_provider(SharedCredentialsProvider::new(expiring));
+        let lifetime = client.credentials_lifetime().await.unwrap();
+        assert!(lifetime <= Duration::from_secs(600));
+        assert!(lifetime > Duration::from_secs(500));
+        // Remembered, not asked for on every presign
+        let (checked_at, _) = client.credentials_expiry.read().await.unwrap();
+        assert!(client.credentials_lifetime().await.unwrap() <= lifetime);
+        assert_eq!(
+            client.credentials_expiry.read().await.unwrap().0,
+            checked_at
+        );
+
+        let warning = credentials_warning(hour, Some(lifetime)).unwrap();
+        assert!(warning.contains("instead of 3600s"));
+        assert!(credentials_warning(Duration::from_secs(300), Some(lifetime)).is_none());
+        assert!(credentials_warning(hour, None).is_none());
+
+        // Signing still succeeds; the URL just expires with the credentials
+        client
+            .generate_presigned_url("bucket", "key", hour, &PresignOptions::default())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_clock_skew_backdates_signature() {
+        let skew = Duration::from_secs(300);
+        let client = static_client().with_clock_skew(skew);
+        let before = chrono::Utc::now();
+
+        let url = client
+            .generate_presigned_url(
+                "bucket",
+                "key",
+                Duration::from_secs(3600),
+                &PresignOptions::default(),
+            )
+            .await
+            .unwrap();
+        let url = url::Url::parse(&url).unwrap();
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.into_owned())
+                .unwrap()
+        };
+
+        let signed_at =
+            chrono::NaiveDateTime::parse_from_str(&param("X-Amz-Date"), "%Y%m%dT%H%M%SZ")
+                .unwrap()
+                .and_utc();
+        assert!(signed_at <= before - chrono::Duration::seconds(299));
+        assert_eq!(param("X-Amz-Expires"), "3900");
+
+        // A maximum-length URL has no room left for the skew
+        let err = client
+            .generate_presigned_url(
+                "bucket",
+                "key",
+                MAX_PRESIGN_DURATION,
+                &PresignOptions::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("plus clock skew"));
+    }
 }
@@ -4,8 +4,8 @@ use tokio::time::interval;
 use tracing::{error, info, instrument};
 
 use crate::config::Config;
-use crate::route53::Route53Client;
-use crate::s3::S3Client;
+use crate::route53::{DnsRecordConfig, Route53Client};
+use crate::s3::{PresignOptions, S3Client};
 
 /// URL refresh scheduler
 pub struct Scheduler {
@@ -57,7 +57,15 @@ impl Scheduler {
         // Generate new presigned URL
         let presigned_url = self
             .s3_client
-            .generate_presigned_url(&bucket, &key, self.config.presign_duration)
+            .generate_presigned_url(
+                &bucket,
+                &key,
+                self.config.presign_duration,
+                &PresignOptions {
+                    version_id: self.config.version_id(),
+                    ..Default::default()
+                },
+            )
             .await?;
 
         // Update Route53 DNS record
@@ -66,6 +74,8 @@ impl Scheduler {
                 &self.config.hosted_zone_id,
                 &self.config.short_url,
                 &presigned_url,
+                DnsRecordConfig::default(),
+                None,
             )
             .await?;
 
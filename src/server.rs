@@ -1,17 +1,69 @@
 #[allow(unused_imports)]
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Body,
+    extract::{FromRequestParts, Path, Query, Request, State},
+    http::{header, request::Parts, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{delete, get, post, put},
     Json, Router,
 };
 use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
-use crate::manager::MappingManager;
-use crate::types::{CreateMappingRequest, ListMappingsResponse, Mapping, UpdateMappingRequest};
+use crate::manager::{AwsCheckFailed, MappingManager, PresignBusy, ShortUrlTaken, SlugTaken};
+use crate::registry::ClientRegistry;
+use crate::route53::DnsRecordType;
+use crate::s3::{attachment_disposition, AddressingStyle};
+use crate::types::{
+    AccessCheck, AuditEntry, AuditQuery, AwsReadiness, BuildInfo, BulkDurationsRequest,
+    BulkStatusReport, BulkUpdateQuery, BulkUpdateReport, CreateMappingRequest, DnsStatus,
+    EffectiveConfig, GcReport, GroupedMappings, ImportQuery, ImportReport, LatencyStats,
+    ListDeletedResponse, ListFilter, ListLogsResponse, ListMappingsQuery, ListMappingsResponse,
+    LogQuery, ManualRefresh, Mapping, MappingDiff, MappingExport, MappingHealth, MappingStatus,
+    Operation, PresignQuery, PresignedUrl, Readiness, ReconcileReport, RefreshWindow, ServerInfo,
+    SetOverrideRequest, Stats, UpdateMappingRequest, ValidationReport, ZoneUsage, ZonesQuery,
+};
+
+/// OpenAPI document for the management API, served at `/openapi.json`
+///
+/// Errors are plain-text messages with the status code telling them apart.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "S3 Buddy", description = "Manage S3 presigned URL mappings"),
+    paths(
+        health_check,
+        readiness,
+        aws_readiness,
+        list_mappings,
+        create_mapping,
+        get_mapping,
+        get_mapping_by_slug,
+        update_mapping,
+        delete_mapping,
+        pause_mapping,
+        resume_mapping,
+    ),
+    components(schemas(
+        Mapping,
+        MappingStatus,
+        CreateMappingRequest,
+        UpdateMappingRequest,
+        ListMappingsResponse,
+        Readiness,
+        AwsReadiness,
+        AccessCheck,
+        RefreshWindow,
+        Operation,
+        AddressingStyle,
+        DnsRecordType,
+    ))
+)]
+pub struct ApiDoc;
 
 /// Shared application state
 #[derive(Clone)]
@@ -19,40 +71,367 @@ pub struct AppState {
     pub manager: Arc<MappingManager>,
 }
 
+/// Mapping named in the `:id` path segment by its ID or its slug; a slug
+/// naming no mapping is rejected with 404
+pub struct MappingId(pub Uuid);
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for MappingId {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let not_found = || (StatusCode::NOT_FOUND, "Mapping not found".to_string());
+        let Path(id) = Path::<String>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| not_found())?;
+        state
+            .manager
+            .resolve_id(&id)
+            .await
+            .map(MappingId)
+            .ok_or_else(not_found)
+    }
+}
+
 /// Create the HTTP API router
 pub fn create_router(manager: Arc<MappingManager>) -> Router {
     let state = AppState { manager };
 
     Router::new()
         .route("/health", get(health_check))
-        .route("/mappings", get(list_mappings).post(create_mapping))
+        .route("/health/ready", get(readiness))
+        .route("/health/aws", get(aws_readiness))
+        .route("/info", get(server_info))
+        .route("/version", get(version))
+        .route("/openapi.json", get(openapi))
+        .route(
+            "/mappings",
+            get(list_mappings)
+                .post(create_mapping)
+                .patch(bulk_update_mappings),
+        )
+        .route("/mappings/validate", post(validate_mapping))
+        .route("/mappings/deleted", get(list_deleted))
+        .route("/mappings/health", get(list_health))
+        .route("/mappings/grouped", get(list_grouped))
+        .route("/mappings/pause-all", post(pause_all))
+        .route("/mappings/resume-all", post(resume_all))
+        .route("/mappings/by-slug/:slug", get(get_mapping_by_slug))
         .route(
             "/mappings/:id",
             get(get_mapping).put(update_mapping).delete(delete_mapping),
         )
+        .route("/mappings/:id/effective", get(get_effective_config))
+        .route("/mappings/:id/diff", post(diff_mapping))
+        .route("/mappings/:id/refresh", post(refresh_mapping))
+        .route("/mappings/:id/presign", get(presign_mapping))
+        .route("/mappings/:id/logs", get(list_mapping_logs))
+        .route("/mappings/:id/dns", get(get_dns_status))
+        .route(
+            "/mappings/:id/override",
+            post(set_override).delete(clear_override),
+        )
         .route("/mappings/:id/pause", post(pause_mapping))
         .route("/mappings/:id/resume", post(resume_mapping))
+        .route("/mappings/:id/restore", post(restore_mapping))
+        .route("/zones", get(list_zones))
+        .route("/export", get(export_mappings))
+        .route("/import", post(import_mappings))
+        .route("/audit", get(list_audit))
+        .route("/stats", get(stats))
+        .route("/stats/latency", get(latency_stats))
+        .route("/metrics", get(prometheus_metrics))
+        .route("/logs", get(list_logs))
+        .route("/admin/reconcile", post(reconcile))
+        .route("/admin/reload-credentials", post(reload_credentials))
+        .route("/admin/gc", post(gc))
+        .layer(middleware::from_fn(pretty_json))
         .layer(TraceLayer::new_for_http())
         .with_state(state)
 }
 
+/// CORS for browser dashboards on `origins`: comma-separated origins such as
+/// `https://dash.example.com`, or `*` for any
+///
+/// `None` when no origin is given, so responses carry no CORS headers.
+pub fn cors_layer(origins: &str) -> anyhow::Result<Option<CorsLayer>> {
+    let origins: Vec<&str> = origins
+        .split(',')
+        .map(str::trim)
+        .filter(|o| !o.is_empty())
+        .collect();
+    if origins.is_empty() {
+        return Ok(None);
+    }
+
+    let allow_origin = if origins.contains(&"*") {
+        AllowOrigin::any()
+    } else {
+        let origins = origins
+            .iter()
+            .map(|o| {
+                HeaderValue::from_str(o.trim_end_matches('/'))
+                    .map_err(|_| anyhow::anyhow!("Invalid CORS origin: {}", o))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ])
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE]),
+    ))
+}
+
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses((
+        status = 200,
+        description = "The server is up",
+        content_type = "application/json",
+        example = json!({ "status": "healthy" })
+    ))
+)]
 async fn health_check() -> impl IntoResponse {
     Json(serde_json::json!({
         "status": "healthy"
     }))
 }
 
-/// List all mappings
-async fn list_mappings(State(state): State<AppState>) -> impl IntoResponse {
-    let mappings = state.manager.list_mappings().await;
-    Json(ListMappingsResponse { mappings })
+/// The API's OpenAPI document
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Whether the query string asks for indented JSON with `pretty=true`
+fn wants_pretty(query: Option<&str>) -> bool {
+    query.is_some_and(|query| {
+        query
+            .split('&')
+            .any(|pair| matches!(pair, "pretty" | "pretty=true" | "pretty=1"))
+    })
+}
+
+/// Re-serialize JSON responses indented when the request asks for `?pretty=true`
+async fn pretty_json(request: Request, next: Next) -> Response {
+    let pretty = wants_pretty(request.uri().query());
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if !pretty || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let body = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .map_or_else(|_| Body::from(bytes), Body::from);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, body)
+}
+
+/// Task and mapping counts, with the health and time in the current status
+/// of every mapping
+async fn stats(State(state): State<AppState>) -> Json<Stats> {
+    Json(state.manager.stats().await)
+}
+
+/// Per-mapping gauges in the Prometheus text format
+async fn prometheus_metrics(State(state): State<AppState>) -> Response {
+    let text = crate::metrics::prometheus_text(&state.manager.uptimes().await);
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], text).into_response()
+}
+
+/// Refresh duration percentiles across all mappings and per mapping
+async fn latency_stats(State(state): State<AppState>) -> Json<LatencyStats> {
+    Json(state.manager.latency_stats())
+}
+
+/// 503 until every scheduled mapping has refreshed successfully once
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    responses(
+        (status = 200, description = "Every scheduled mapping has refreshed", body = Readiness),
+        (status = 503, description = "Some mappings are still waiting", body = Readiness),
+    )
+)]
+async fn readiness(State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let readiness = state.manager.readiness().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+/// 503 unless S3 and Route53 both answer a cheap probe
+#[utoipa::path(
+    get,
+    path = "/health/aws",
+    responses(
+        (status = 200, description = "S3 and Route53 are reachable", body = AwsReadiness),
+        (status = 503, description = "S3 or Route53 is unreachable", body = AwsReadiness),
+    )
+)]
+async fn aws_readiness(State(state): State<AppState>) -> (StatusCode, Json<AwsReadiness>) {
+    let readiness = state.manager.aws_readiness().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+/// Every mapping's configuration, to import into another server
+async fn export_mappings(State(state): State<AppState>) -> Json<MappingExport> {
+    Json(state.manager.export_all().await)
+}
+
+/// Create the mappings of an export; `?mode=replace` deletes existing ones
+/// first. 400, with nothing changed, if any entry is invalid
+async fn import_mappings(
+    State(state): State<AppState>,
+    Query(query): Query<ImportQuery>,
+    Json(export): Json<MappingExport>,
+) -> (StatusCode, Json<ImportReport>) {
+    let report = state.manager.import_all(export, query.mode).await;
+    let status = if report.applied {
+        StatusCode::OK
+    } else {
+        StatusCode::BAD_REQUEST
+    };
+    (status, Json(report))
+}
+
+/// Server details for clients, such as the message of the day
+async fn server_info(State(state): State<AppState>) -> Json<ServerInfo> {
+    Json(state.manager.info())
+}
+
+/// Which build of the server is running
+async fn version() -> Json<BuildInfo> {
+    Json(BuildInfo::current())
+}
+
+/// List all mappings, as CSV when the client accepts `text/csv`
+///
+/// `?fields=id,short_url` limits the JSON objects to the named fields.
+#[utoipa::path(
+    get,
+    path = "/mappings",
+    params(ListMappingsQuery),
+    responses(
+        (status = 200, body = ListMappingsResponse, content_type = ["application/json", "text/csv"]),
+        (status = 400, description = "Invalid filter or field name", body = String, content_type = "text/plain"),
+    )
+)]
+async fn list_mappings(
+    State(state): State<AppState>,
+    Query(query): Query<ListMappingsQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let status = match query.status.as_deref().map(str::parse).transpose() {
+        Ok(status) => status,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("{:#}", e)).into_response(),
+    };
+    let filter = ListFilter {
+        status,
+        limit: query.limit,
+        offset: query.offset,
+        cursor: query.cursor,
+    };
+    let page = match state.manager.list_mappings_page(filter).await {
+        Ok(page) => page,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("{:#}", e)).into_response(),
+    };
+    let mappings = page.mappings;
+
+    if accepts_csv(&headers) {
+        let mut body = format!("{}\n", Mapping::CSV_HEADER);
+        for mapping in &mappings {
+            body.push_str(&mapping.to_csv_row());
+            body.push('\n');
+        }
+        return ([(header::CONTENT_TYPE, "text/csv")], body).into_response();
+    }
+
+    if let Some(fields) = query.fields {
+        let fields: Vec<&str> = fields
+            .split(',')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .collect();
+        if let Some(unknown) = fields.iter().find(|f| !Mapping::FIELD_NAMES.contains(f)) {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Unknown field: {}", unknown),
+            )
+                .into_response();
+        }
+
+        let mappings: Vec<serde_json::Value> =
+            mappings.iter().map(|m| m.project(&fields)).collect();
+        let mut body = serde_json::json!({ "mappings": mappings });
+        if let Some(next_cursor) = page.next_cursor {
+            body["next_cursor"] = next_cursor.into();
+        }
+        if let Some(next_offset) = page.next_offset {
+            body["next_offset"] = next_offset.into();
+        }
+        body["total"] = page.total.into();
+        return Json(body).into_response();
+    }
+
+    Json(ListMappingsResponse { mappings, ..page }).into_response()
+}
+
+/// Whether the request's `Accept` header asks for CSV
+fn accepts_csv(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| {
+            accept
+                .split(',')
+                .any(|t| t.split(';').next().unwrap_or("").trim() == "text/csv")
+        })
 }
 
 /// Get a specific mapping
+#[utoipa::path(
+    get,
+    path = "/mappings/{id}",
+    params(("id" = String, Path, description = "Mapping ID or slug")),
+    responses(
+        (status = 200, body = Mapping),
+        (status = 404, description = "No mapping with this ID"),
+    )
+)]
 async fn get_mapping(
     State(state): State<AppState>,
-    Path(id): Path<Uuid>,
+    MappingId(id): MappingId,
 ) -> Result<Json<Mapping>, StatusCode> {
     state
         .manager
@@ -62,25 +441,122 @@ async fn get_mapping(
         .ok_or(StatusCode::NOT_FOUND)
 }
 
+/// Get a mapping by its slug
+#[utoipa::path(
+    get,
+    path = "/mappings/by-slug/{slug}",
+    params(("slug" = String, Path, description = "Mapping slug")),
+    responses(
+        (status = 200, body = Mapping),
+        (status = 404, description = "No mapping with this slug"),
+    )
+)]
+async fn get_mapping_by_slug(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Mapping>, StatusCode> {
+    state
+        .manager
+        .get_mapping_by_slug(&slug)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Get the effective configuration a mapping runs with
+async fn get_effective_config(
+    State(state): State<AppState>,
+    MappingId(id): MappingId,
+) -> Result<Json<EffectiveConfig>, (StatusCode, String)> {
+    state
+        .manager
+        .effective_config(&id)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))
+}
+
 /// Create a new mapping
+#[utoipa::path(
+    post,
+    path = "/mappings",
+    request_body = CreateMappingRequest,
+    responses(
+        (status = 200, body = Mapping),
+        (status = 400, description = "Invalid mapping", body = String, content_type = "text/plain"),
+        (status = 409, description = "Short URL or slug already taken", body = String, content_type = "text/plain"),
+    )
+)]
 async fn create_mapping(
     State(state): State<AppState>,
     Json(req): Json<CreateMappingRequest>,
 ) -> Result<Json<Mapping>, (StatusCode, String)> {
-    let mut mapping = Mapping::new(req.s3_url, req.short_url, req.hosted_zone_id);
-    mapping.presign_duration_secs = req.presign_duration_secs;
-    mapping.refresh_interval_secs = req.refresh_interval_secs;
+    let options = req.add_options();
+    let mapping = Mapping::from_request_with(req, state.manager.defaults());
 
-    match state.manager.add_mapping(mapping.clone()).await {
-        Ok(_) => Ok(Json(mapping)),
-        Err(e) => Err((StatusCode::BAD_REQUEST, e.to_string())),
+    match state
+        .manager
+        .add_mapping_with(mapping.clone(), options)
+        .await
+    {
+        // Stored as normalized, e.g. without a `/hostedzone/` prefix
+        Ok(id) => Ok(Json(
+            state.manager.get_mapping(&id).await.unwrap_or(mapping),
+        )),
+        Err(e) if e.is::<ShortUrlTaken>() || e.is::<SlugTaken>() => {
+            Err((StatusCode::CONFLICT, e.to_string()))
+        }
+        // AWS couldn't be asked, which isn't the request's fault
+        Err(e) => {
+            let status = match e.downcast_ref::<AwsCheckFailed>() {
+                Some(failed) if failed.transient => StatusCode::SERVICE_UNAVAILABLE,
+                Some(_) => StatusCode::BAD_GATEWAY,
+                None => StatusCode::BAD_REQUEST,
+            };
+            Err((status, format!("{:#}", e)))
+        }
     }
 }
 
+/// Set durations on every mapping in `?bucket=` and/or `?zone=`
+async fn bulk_update_mappings(
+    State(state): State<AppState>,
+    Query(query): Query<BulkUpdateQuery>,
+    Json(req): Json<BulkDurationsRequest>,
+) -> Result<Json<BulkUpdateReport>, (StatusCode, String)> {
+    state
+        .manager
+        .bulk_update_durations(&query, &req)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))
+}
+
+/// Dry-run a prospective mapping without storing it
+async fn validate_mapping(
+    State(state): State<AppState>,
+    Json(req): Json<CreateMappingRequest>,
+) -> Json<ValidationReport> {
+    let mapping = Mapping::from_request_with(req, state.manager.defaults());
+    Json(state.manager.validate_mapping(&mapping).await)
+}
+
 /// Update an existing mapping
+#[utoipa::path(
+    put,
+    path = "/mappings/{id}",
+    params(("id" = String, Path, description = "Mapping ID or slug")),
+    request_body = UpdateMappingRequest,
+    responses(
+        (status = 200, body = Mapping),
+        (status = 404, description = "No mapping with this ID", body = String, content_type = "text/plain"),
+        (status = 409, description = "Short URL or slug already taken", body = String, content_type = "text/plain"),
+        (status = 500, description = "Invalid update", body = String, content_type = "text/plain"),
+    )
+)]
 async fn update_mapping(
     State(state): State<AppState>,
-    Path(id): Path<Uuid>,
+    MappingId(id): MappingId,
     Json(req): Json<UpdateMappingRequest>,
 ) -> Result<Json<Mapping>, (StatusCode, String)> {
     // Get existing mapping
@@ -91,32 +567,50 @@ async fn update_mapping(
         .ok_or((StatusCode::NOT_FOUND, "Mapping not found".to_string()))?;
 
     // Apply updates
-    if let Some(s3_url) = req.s3_url {
-        mapping.s3_url = s3_url;
-    }
-    if let Some(short_url) = req.short_url {
-        mapping.short_url = short_url;
-    }
-    if let Some(hosted_zone_id) = req.hosted_zone_id {
-        mapping.hosted_zone_id = hosted_zone_id;
-    }
-    if let Some(presign_duration_secs) = req.presign_duration_secs {
-        mapping.presign_duration_secs = presign_duration_secs;
-    }
-    if let Some(refresh_interval_secs) = req.refresh_interval_secs {
-        mapping.refresh_interval_secs = refresh_interval_secs;
-    }
+    mapping.apply_update(req);
 
     match state.manager.update_mapping(&id, mapping.clone()).await {
-        Ok(_) => Ok(Json(mapping)),
+        Ok(_) => Ok(Json(
+            state.manager.get_mapping(&id).await.unwrap_or(mapping),
+        )),
+        Err(e) if e.is::<ShortUrlTaken>() || e.is::<SlugTaken>() => {
+            Err((StatusCode::CONFLICT, e.to_string()))
+        }
         Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
     }
 }
 
+/// Preview the changes an update would make without applying it
+async fn diff_mapping(
+    State(state): State<AppState>,
+    MappingId(id): MappingId,
+    Json(req): Json<UpdateMappingRequest>,
+) -> Result<Json<MappingDiff>, (StatusCode, String)> {
+    let current = state
+        .manager
+        .get_mapping(&id)
+        .await
+        .ok_or((StatusCode::NOT_FOUND, "Mapping not found".to_string()))?;
+
+    let mut proposed = current.clone();
+    proposed.apply_update(req);
+
+    Ok(Json(current.diff(&proposed)))
+}
+
 /// Delete a mapping
+#[utoipa::path(
+    delete,
+    path = "/mappings/{id}",
+    params(("id" = String, Path, description = "Mapping ID or slug")),
+    responses(
+        (status = 204, description = "Deleted; restorable until purged"),
+        (status = 404, description = "No mapping with this ID", body = String, content_type = "text/plain"),
+    )
+)]
 async fn delete_mapping(
     State(state): State<AppState>,
-    Path(id): Path<Uuid>,
+    MappingId(id): MappingId,
 ) -> Result<StatusCode, (StatusCode, String)> {
     match state.manager.delete_mapping(&id).await {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
@@ -124,10 +618,166 @@ async fn delete_mapping(
     }
 }
 
+/// Deleted mappings that can still be restored
+async fn list_deleted(State(state): State<AppState>) -> Json<ListDeletedResponse> {
+    Json(ListDeletedResponse {
+        deleted: state.manager.list_deleted().await,
+    })
+}
+
+/// Health scores of all mappings, worst first
+async fn list_health(State(state): State<AppState>) -> Json<Vec<MappingHealth>> {
+    Json(state.manager.health().await)
+}
+
+/// Mappings grouped by status, for dashboards
+async fn list_grouped(State(state): State<AppState>) -> Json<GroupedMappings> {
+    Json(state.manager.grouped_mappings().await)
+}
+
+/// Restore a deleted mapping
+async fn restore_mapping(
+    State(state): State<AppState>,
+    MappingId(id): MappingId,
+) -> Result<Json<Mapping>, (StatusCode, String)> {
+    state
+        .manager
+        .restore_mapping(&id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            let status = if e.is::<ShortUrlTaken>() {
+                StatusCode::CONFLICT
+            } else {
+                StatusCode::NOT_FOUND
+            };
+            (status, e.to_string())
+        })
+}
+
+/// Refresh a mapping now, debounced against recent refreshes
+async fn refresh_mapping(
+    State(state): State<AppState>,
+    MappingId(id): MappingId,
+) -> Result<Json<ManualRefresh>, (StatusCode, String)> {
+    match state.manager.get_mapping(&id).await {
+        None => Err((StatusCode::NOT_FOUND, "Mapping not found".to_string())),
+        Some(_) => state
+            .manager
+            .refresh_now(&id)
+            .await
+            .map(Json)
+            .map_err(|e| (StatusCode::CONFLICT, format!("{:#}", e))),
+    }
+}
+
+/// Live Route53 record of a mapping and whether it matches the expected target
+async fn get_dns_status(
+    State(state): State<AppState>,
+    MappingId(id): MappingId,
+) -> Result<Json<DnsStatus>, (StatusCode, String)> {
+    if state.manager.get_mapping(&id).await.is_none() {
+        return Err((StatusCode::NOT_FOUND, "Mapping not found".to_string()));
+    }
+    state
+        .manager
+        .dns_status(&id)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("{:#}", e)))
+}
+
+/// Temporarily publish another S3 object for a mapping
+async fn set_override(
+    State(state): State<AppState>,
+    MappingId(id): MappingId,
+    Json(req): Json<SetOverrideRequest>,
+) -> Result<Json<Mapping>, (StatusCode, String)> {
+    if state.manager.get_mapping(&id).await.is_none() {
+        return Err((StatusCode::NOT_FOUND, "Mapping not found".to_string()));
+    }
+    state
+        .manager
+        .set_override(&id, req.s3_url, Duration::from_secs(req.duration_secs))
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))
+}
+
+/// Drop a mapping's override
+async fn clear_override(
+    State(state): State<AppState>,
+    MappingId(id): MappingId,
+) -> Result<Json<Mapping>, (StatusCode, String)> {
+    if state.manager.get_mapping(&id).await.is_none() {
+        return Err((StatusCode::NOT_FOUND, "Mapping not found".to_string()));
+    }
+    state
+        .manager
+        .clear_override(&id)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Presign a fresh URL for a mapping's object (`?method=head` for HEAD,
+/// `?method=put` for an upload mapping, `?download=name.pdf` to download
+/// under another name)
+async fn presign_mapping(
+    State(state): State<AppState>,
+    MappingId(id): MappingId,
+    Query(query): Query<PresignQuery>,
+) -> Result<Json<PresignedUrl>, (StatusCode, String)> {
+    let Some(mapping) = state.manager.get_mapping(&id).await else {
+        return Err((StatusCode::NOT_FOUND, "Mapping not found".to_string()));
+    };
+    if !mapping.operation.allows(query.method) {
+        return Err((
+            StatusCode::METHOD_NOT_ALLOWED,
+            match mapping.operation {
+                Operation::Get => "Download mappings can't presign uploads".to_string(),
+                Operation::Put => "Upload mappings only presign PUT requests".to_string(),
+            },
+        ));
+    }
+    if query
+        .download
+        .as_deref()
+        .is_some_and(|filename| attachment_disposition(filename).is_none())
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Download filename is empty".to_string(),
+        ));
+    }
+    state
+        .manager
+        .presign_now(&id, query.method, query.ttl, query.download.as_deref())
+        .await
+        .map(Json)
+        .map_err(|e| {
+            let status = if e.is::<PresignBusy>() {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::BAD_GATEWAY
+            };
+            (status, format!("{:#}", e))
+        })
+}
+
 /// Pause a mapping
+#[utoipa::path(
+    post,
+    path = "/mappings/{id}/pause",
+    params(("id" = String, Path, description = "Mapping ID or slug")),
+    responses(
+        (status = 200, body = Mapping),
+        (status = 404, description = "No mapping with this ID", body = String, content_type = "text/plain"),
+    )
+)]
 async fn pause_mapping(
     State(state): State<AppState>,
-    Path(id): Path<Uuid>,
+    MappingId(id): MappingId,
 ) -> Result<Json<Mapping>, (StatusCode, String)> {
     match state.manager.pause_mapping(&id).await {
         Ok(_) => {
@@ -143,9 +793,18 @@ async fn pause_mapping(
 }
 
 /// Resume a paused mapping
+#[utoipa::path(
+    post,
+    path = "/mappings/{id}/resume",
+    params(("id" = String, Path, description = "Mapping ID or slug")),
+    responses(
+        (status = 200, body = Mapping),
+        (status = 404, description = "No mapping with this ID", body = String, content_type = "text/plain"),
+    )
+)]
 async fn resume_mapping(
     State(state): State<AppState>,
-    Path(id): Path<Uuid>,
+    MappingId(id): MappingId,
 ) -> Result<Json<Mapping>, (StatusCode, String)> {
     match state.manager.resume_mapping(&id).await {
         Ok(_) => {
@@ -159,3 +818,79 @@ async fn resume_mapping(
         Err(e) => Err((StatusCode::NOT_FOUND, e.to_string())),
     }
 }
+
+/// Pause every refreshing mapping, e.g. for S3 maintenance
+async fn pause_all(State(state): State<AppState>) -> Json<BulkStatusReport> {
+    Json(state.manager.pause_all().await)
+}
+
+/// Resume every paused mapping
+async fn resume_all(State(state): State<AppState>) -> Json<BulkStatusReport> {
+    Json(state.manager.resume_all().await)
+}
+
+/// Hosted zones in use and how many mappings each serves
+async fn list_zones(
+    State(state): State<AppState>,
+    Query(query): Query<ZonesQuery>,
+) -> Json<Vec<ZoneUsage>> {
+    Json(state.manager.zones(query.resolve).await)
+}
+
+/// Audit trail of mapping changes, oldest first
+async fn list_audit(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Json<Vec<AuditEntry>> {
+    Json(state.manager.audit(&query).await)
+}
+
+/// Recent refresh logs across all mappings, oldest first
+async fn list_logs(
+    State(state): State<AppState>,
+    Query(query): Query<LogQuery>,
+) -> Result<Json<ListLogsResponse>, (StatusCode, String)> {
+    state
+        .manager
+        .logs(&query)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))
+}
+
+/// Retained refresh logs of one mapping, oldest first
+async fn list_mapping_logs(
+    State(state): State<AppState>,
+    MappingId(id): MappingId,
+) -> Result<Json<ListLogsResponse>, (StatusCode, String)> {
+    state
+        .manager
+        .mapping_logs(&id)
+        .await
+        .map(|logs| {
+            Json(ListLogsResponse {
+                logs,
+                next_cursor: None,
+            })
+        })
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))
+}
+
+/// Re-upsert any Route53 records that have drifted from their expected target
+async fn reconcile(State(state): State<AppState>) -> Json<ReconcileReport> {
+    Json(state.manager.reconcile().await)
+}
+
+/// Rebuild the AWS clients from a freshly loaded config
+async fn reload_credentials(State(state): State<AppState>) -> StatusCode {
+    state
+        .manager
+        .replace_clients(ClientRegistry::from_env().await)
+        .await;
+    StatusCode::NO_CONTENT
+}
+
+/// Abort orphaned refresh tasks and start missing ones
+async fn gc(State(state): State<AppState>) -> Json<GcReport> {
+    Json(state.manager.gc().await)
+}
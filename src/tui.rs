@@ -1,7 +1,10 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton,
+        MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -10,23 +13,51 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
     Frame, Terminal,
 };
+use serde::{Deserialize, Serialize};
 use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
-use crate::types::{CreateMappingRequest, Mapping, MappingStatus};
+use crate::client::ApiClient;
+use crate::types::{normalize_short_url, CreateMappingRequest, Mapping, MappingStatus, RefreshLog};
 
 /// Main TUI application state
 pub struct App {
-    pub server_url: String,
+    pub api: ApiClient,
     pub mappings: Vec<Mapping>,
     pub table_state: TableState,
     pub current_view: View,
     pub form_state: FormState,
     pub status_message: Option<String>,
     pub should_quit: bool,
+    /// Column the dashboard is sorted by
+    pub sort_by: SortColumn,
+    /// Sort descending instead of ascending
+    pub sort_desc: bool,
+    /// Message of the day from the server, shown in the header
+    pub motd: Option<String>,
+    /// Configured staleness threshold for `last_refresh`
+    pub stale_after: Option<Duration>,
+    /// Dashboard columns, in display order
+    pub columns: Vec<Column>,
+    /// Highlighted row of the column settings screen
+    pub column_cursor: usize,
+    /// Refresh logs of the mapping shown in the logs view, oldest first
+    pub logs: Vec<RefreshLog>,
+    /// Lines scrolled down in the logs view
+    pub logs_scroll: u16,
+    /// Where the dashboard table was last drawn, for mapping clicks to rows
+    pub table_area: Rect,
+    /// Time and row of the last click, for detecting double-clicks
+    last_click: Option<(Instant, usize)>,
+    /// How often the dashboard refetches mappings on its own; never if unset
+    pub auto_refresh: Option<Duration>,
+    /// When mappings were last fetched, successfully or not
+    last_fetch: Instant,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -35,6 +66,22 @@ pub enum View {
     AddMapping,
     EditMapping(Uuid),
     DeleteConfirm(Uuid),
+    /// Details of a mapping, including its notes
+    Details(Uuid),
+    /// Choose and order the dashboard columns
+    Columns,
+    /// Recent refresh logs of a mapping
+    Logs(Uuid),
+    /// Confirm discarding a dirty form; `editing` is the mapping being edited, if any
+    DiscardConfirm {
+        editing: Option<Uuid>,
+        quit: bool,
+    },
+    /// Confirm pausing every mapping, or with `pause` false resuming the
+    /// ones paused in bulk
+    BulkConfirm {
+        pause: bool,
+    },
     Help,
 }
 
@@ -45,7 +92,13 @@ pub struct FormState {
     pub hosted_zone_id: String,
     pub presign_duration_hours: String,
     pub refresh_interval_hours: String,
+    pub notes: String,
     pub current_field: usize,
+    /// Whether any field has been edited since the form was opened
+    pub dirty: bool,
+    /// Durations of the mapping being edited, in seconds, kept while the
+    /// fields still show them so saving doesn't round them to whole hours
+    pub loaded_durations: Option<(u64, u64)>,
 }
 
 impl Default for FormState {
@@ -56,7 +109,10 @@ impl Default for FormState {
             hosted_zone_id: String::new(),
             presign_duration_hours: "12".to_string(),
             refresh_interval_hours: "11".to_string(),
+            notes: String::new(),
             current_field: 0,
+            dirty: false,
+            loaded_durations: None,
         }
     }
 }
@@ -66,6 +122,33 @@ impl FormState {
         *self = Self::default();
     }
 
+    fn current_value_mut(&mut self) -> Option<&mut String> {
+        match self.current_field {
+            0 => Some(&mut self.s3_url),
+            1 => Some(&mut self.short_url),
+            2 => Some(&mut self.hosted_zone_id),
+            3 => Some(&mut self.presign_duration_hours),
+            4 => Some(&mut self.refresh_interval_hours),
+            5 => Some(&mut self.notes),
+            _ => None,
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        if let Some(field) = self.current_value_mut() {
+            field.push(c);
+            self.dirty = true;
+        }
+    }
+
+    fn pop_char(&mut self) {
+        if let Some(field) = self.current_value_mut() {
+            if field.pop().is_some() {
+                self.dirty = true;
+            }
+        }
+    }
+
     fn to_request(&self) -> Result<CreateMappingRequest> {
         // Validate required fields
         if self.s3_url.trim().is_empty() {
@@ -82,39 +165,60 @@ impl FormState {
         if !self.s3_url.trim().starts_with("s3://") {
             anyhow::bail!("S3 URL must start with s3://");
         }
+        let short_url = normalize_short_url(&self.short_url)?;
 
         // Validate numeric fields
-        let presign_duration_secs = self
-            .presign_duration_hours
-            .parse::<u64>()
-            .map_err(|_| anyhow::anyhow!("Invalid presign duration (must be a number)"))?
-            * 3600;
+        let (loaded_presign, loaded_refresh) = self.loaded_durations.unzip();
+        let presign_duration_secs = duration_secs(&self.presign_duration_hours, loaded_presign)
+            .map_err(|_| anyhow::anyhow!("Invalid presign duration (must be a number)"))?;
 
         if presign_duration_secs == 0 {
             anyhow::bail!("Presign duration must be greater than 0");
         }
 
-        let refresh_interval_secs = self
-            .refresh_interval_hours
-            .parse::<u64>()
-            .map_err(|_| anyhow::anyhow!("Invalid refresh interval (must be a number)"))?
-            * 3600;
+        let refresh_interval_secs = duration_secs(&self.refresh_interval_hours, loaded_refresh)
+            .map_err(|_| anyhow::anyhow!("Invalid refresh interval (must be a number)"))?;
 
         if refresh_interval_secs == 0 {
             anyhow::bail!("Refresh interval must be greater than 0");
         }
 
-        // Validate that refresh interval is less than presign duration
-        if refresh_interval_secs >= presign_duration_secs {
-            anyhow::bail!("Refresh interval must be less than presign duration");
-        }
+        // Same margin the server enforces, so the form explains a rejection
+        // before sending
+        let mut durations = Mapping::new(
+            self.s3_url.trim().to_string(),
+            short_url.clone(),
+            self.hosted_zone_id.trim().to_string(),
+        );
+        durations.presign_duration_secs = presign_duration_secs;
+        durations.refresh_interval_secs = refresh_interval_secs;
+        durations.validate_durations()?;
 
         Ok(CreateMappingRequest {
             s3_url: self.s3_url.trim().to_string(),
-            short_url: self.short_url.trim().to_string(),
+            short_url,
             hosted_zone_id: self.hosted_zone_id.trim().to_string(),
-            presign_duration_secs,
-            refresh_interval_secs,
+            slug: None,
+            presign_duration_secs: Some(presign_duration_secs),
+            refresh_interval_secs: Some(refresh_interval_secs),
+            max_presign_duration_secs: None,
+            credentials_profile: None,
+            requester_pays: false,
+            use_accelerate: false,
+            extra_query_params: Default::default(),
+            access_point_arn: None,
+            addressing_style: Default::default(),
+            manage_dns: true,
+            operation: Default::default(),
+            dns_record_type: Default::default(),
+            dns_ttl_secs: None,
+            allowed_window: None,
+            force_content_type: None,
+            download_filename: None,
+            fallback_s3_urls: Vec::new(),
+            initial_refresh_delay_secs: None,
+            skip_validation: false,
+            notes: Some(self.notes.trim().to_string()).filter(|notes| !notes.is_empty()),
         })
     }
 
@@ -124,22 +228,138 @@ impl FormState {
         self.hosted_zone_id = mapping.hosted_zone_id.clone();
         self.presign_duration_hours = (mapping.presign_duration_secs / 3600).to_string();
         self.refresh_interval_hours = (mapping.refresh_interval_secs / 3600).to_string();
+        self.notes = mapping.notes.clone().unwrap_or_default();
+        self.loaded_durations =
+            Some((mapping.presign_duration_secs, mapping.refresh_interval_secs));
+        self.dirty = false;
     }
 }
 
+/// Seconds for a duration field holding whole hours; `loaded` is kept while
+/// the field still shows its hours
+fn duration_secs(hours: &str, loaded: Option<u64>) -> Result<u64, std::num::ParseIntError> {
+    let hours = hours.parse::<u64>()?;
+    Ok(match loaded {
+        Some(secs) if secs / 3600 == hours => secs,
+        _ => hours * 3600,
+    })
+}
+
 impl App {
     pub fn new(server_url: String) -> Self {
         Self {
-            server_url,
+            api: ApiClient::new(server_url),
             mappings: Vec::new(),
             table_state: TableState::default(),
             current_view: View::Dashboard,
             form_state: FormState::default(),
             status_message: None,
             should_quit: false,
+            sort_by: SortColumn::default(),
+            sort_desc: false,
+            motd: None,
+            stale_after: None,
+            columns: DEFAULT_COLUMNS.to_vec(),
+            column_cursor: 0,
+            logs: Vec::new(),
+            logs_scroll: 0,
+            table_area: Rect::default(),
+            last_click: None,
+            auto_refresh: Some(DEFAULT_AUTO_REFRESH),
+            last_fetch: Instant::now(),
+        }
+    }
+
+    /// Whether the dashboard is showing and its mappings are due for a
+    /// refetch; forms and popups are left alone
+    pub fn auto_refresh_due(&self, now: Instant) -> bool {
+        self.current_view == View::Dashboard
+            && self
+                .auto_refresh
+                .is_some_and(|interval| now.duration_since(self.last_fetch) >= interval)
+    }
+
+    /// Shown columns in order, then the hidden ones, as listed on the
+    /// column settings screen
+    pub fn column_choices(&self) -> Vec<(Column, bool)> {
+        let shown = self.columns.iter().map(|c| (*c, true));
+        let hidden = ALL_COLUMNS
+            .iter()
+            .filter(|c| !self.columns.contains(c))
+            .map(|c| (*c, false));
+        shown.chain(hidden).collect()
+    }
+
+    /// Show or hide the highlighted column; at least one stays shown
+    pub fn toggle_column(&mut self) {
+        let Some((column, shown)) = self.column_choices().get(self.column_cursor).copied() else {
+            return;
+        };
+        if !shown {
+            self.columns.push(column);
+            self.column_cursor = self.columns.len() - 1;
+        } else if self.columns.len() > 1 {
+            self.columns.retain(|c| *c != column);
         }
     }
 
+    /// Move the highlighted column earlier (`-1`) or later (`1`) among the
+    /// shown columns
+    pub fn move_column(&mut self, offset: isize) {
+        let from = self.column_cursor;
+        let Some(to) = from.checked_add_signed(offset) else {
+            return;
+        };
+        if from < self.columns.len() && to < self.columns.len() {
+            self.columns.swap(from, to);
+            self.column_cursor = to;
+        }
+    }
+
+    /// Order mappings by the sort column, breaking ties by short URL and ID
+    /// so rows don't jump between polls
+    pub fn sort_mappings(&mut self) {
+        let now = Utc::now();
+        let sort_by = self.sort_by;
+        self.mappings.sort_by(|a, b| {
+            let order = sort_by
+                .compare(a, b, now)
+                .then_with(|| a.short_url.cmp(&b.short_url))
+                .then_with(|| a.id.cmp(&b.id));
+            if self.sort_desc {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+    }
+
+    /// Sort by the next column, ascending
+    pub fn cycle_sort_column(&mut self) {
+        self.sort_by = self.sort_by.next();
+        self.sort_desc = false;
+        self.sort_mappings();
+    }
+
+    /// Flip the sort direction
+    pub fn toggle_sort_direction(&mut self) {
+        self.sort_desc = !self.sort_desc;
+        self.sort_mappings();
+    }
+
+    /// Describes the current sort for the status line
+    fn sort_description(&self) -> String {
+        format!(
+            "Sorted by {}, {}",
+            self.sort_by.column().title().to_lowercase(),
+            if self.sort_desc {
+                "descending"
+            } else {
+                "ascending"
+            }
+        )
+    }
+
     pub fn next_row(&mut self) {
         if self.mappings.is_empty() {
             return;
@@ -179,20 +399,379 @@ impl App {
             .selected()
             .and_then(|i| self.mappings.get(i))
     }
+
+    /// Open the edit form for the selected mapping
+    fn edit_selected(&mut self) {
+        if let Some(mapping) = self.selected_mapping().cloned() {
+            self.form_state.populate_from_mapping(&mapping);
+            self.status_message = None;
+            self.current_view = View::EditMapping(mapping.id);
+        }
+    }
+
+    /// Index of the mapping drawn at a terminal cell, if any: rows start
+    /// below the table's top border, its header and the header's margin
+    pub fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.table_area;
+        let first_row = area.y + TABLE_ROWS_OFFSET;
+        let inside = column > area.x
+            && column < area.right().saturating_sub(1)
+            && row >= first_row
+            && row < area.bottom().saturating_sub(1);
+        if !inside {
+            return None;
+        }
+        let index = self.table_state.offset() + usize::from(row - first_row);
+        (index < self.mappings.len()).then_some(index)
+    }
+
+    /// Select the row under a left click, returning whether it completes a
+    /// double-click on that row
+    pub fn click(&mut self, column: u16, row: u16, now: Instant) -> bool {
+        let Some(index) = self.row_at(column, row) else {
+            self.last_click = None;
+            return false;
+        };
+        self.table_state.select(Some(index));
+        let double = self.last_click.is_some_and(|(at, last)| {
+            last == index && now.duration_since(at) <= DOUBLE_CLICK_INTERVAL
+        });
+        // A third click starts a new pair rather than another double-click
+        self.last_click = if double { None } else { Some((now, index)) };
+        double
+    }
+}
+
+/// Lines between the top of the table area and its first row: the border,
+/// the header row and the margin below it
+const TABLE_ROWS_OFFSET: u16 = 3;
+
+/// Longest gap between the clicks of a double-click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Terminal setup options for the TUI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TuiOptions {
+    /// Capture mouse events; disabling keeps native terminal text selection
+    pub mouse_capture: bool,
+    /// Age past which `last_refresh` is flagged as stale; derived from each
+    /// mapping's refresh interval when unset
+    pub stale_after: Option<Duration>,
+    /// Dashboard columns; the saved client config or the defaults when unset
+    pub columns: Option<Vec<Column>>,
+    /// How often the dashboard refetches mappings; never if unset
+    pub auto_refresh: Option<Duration>,
+}
+
+impl Default for TuiOptions {
+    fn default() -> Self {
+        Self {
+            mouse_capture: true,
+            stale_after: None,
+            columns: None,
+            auto_refresh: Some(DEFAULT_AUTO_REFRESH),
+        }
+    }
+}
+
+impl TuiOptions {
+    /// Options from the `--no-mouse` flag or the `S3_BUDDY_NO_MOUSE` variable
+    pub fn from_args(args: &[String], no_mouse_env: Option<&str>) -> Self {
+        let env_no_mouse = no_mouse_env.is_some_and(|v| !v.is_empty() && v != "0" && v != "false");
+        Self {
+            mouse_capture: !(env_no_mouse || args.iter().any(|a| a == "--no-mouse")),
+            ..Default::default()
+        }
+    }
+
+    /// Flag refreshes older than `stale_after` as stale
+    pub fn with_stale_after(mut self, stale_after: Option<Duration>) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Show these columns instead of the saved ones
+    pub fn with_columns(mut self, columns: Option<Vec<Column>>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Refetch mappings every `auto_refresh`, or only on `r` when `None`
+    pub fn with_auto_refresh(mut self, auto_refresh: Option<Duration>) -> Self {
+        self.auto_refresh = auto_refresh;
+        self
+    }
+}
+
+/// A dashboard table column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Id,
+    S3Url,
+    ShortUrl,
+    Status,
+    Health,
+    LastRefresh,
+    NextRefresh,
+    Errors,
+    Notes,
+}
+
+/// Every column, in the order hidden ones are offered
+pub const ALL_COLUMNS: [Column; 9] = [
+    Column::Id,
+    Column::S3Url,
+    Column::ShortUrl,
+    Column::Status,
+    Column::Health,
+    Column::LastRefresh,
+    Column::NextRefresh,
+    Column::Errors,
+    Column::Notes,
+];
+
+/// Columns shown unless configured otherwise
+pub const DEFAULT_COLUMNS: [Column; 6] = [
+    Column::Id,
+    Column::S3Url,
+    Column::ShortUrl,
+    Column::Status,
+    Column::Health,
+    Column::LastRefresh,
+];
+
+/// Share of the table width split between the variable-width columns
+const FLEXIBLE_WIDTH_PERCENT: u16 = 75;
+
+impl Column {
+    /// Name used in `S3_BUDDY_COLUMNS` and the client config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::S3Url => "s3_url",
+            Column::ShortUrl => "short_url",
+            Column::Status => "status",
+            Column::Health => "health",
+            Column::LastRefresh => "last_refresh",
+            Column::NextRefresh => "next_refresh",
+            Column::Errors => "errors",
+            Column::Notes => "notes",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Id => "ID",
+            Column::S3Url => "S3 URL",
+            Column::ShortUrl => "Short URL",
+            Column::Status => "Status",
+            Column::Health => "Health",
+            Column::LastRefresh => "Last Refresh",
+            Column::NextRefresh => "Next Refresh",
+            Column::Errors => "Last Error",
+            Column::Notes => "Notes",
+        }
+    }
+
+    /// Fixed width, or `None` for columns sharing the remaining width
+    fn fixed_width(&self) -> Option<u16> {
+        match self {
+            Column::Id | Column::Status => Some(10),
+            Column::Health => Some(7),
+            _ => None,
+        }
+    }
+
+    /// Relative share of the flexible width
+    fn weight(&self) -> u16 {
+        match self {
+            Column::S3Url | Column::Errors => 30,
+            Column::ShortUrl | Column::Notes => 25,
+            _ => 20,
+        }
+    }
+}
+
+impl std::str::FromStr for Column {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ALL_COLUMNS
+            .into_iter()
+            .find(|c| c.name() == s.trim().to_ascii_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = ALL_COLUMNS.iter().map(Column::name).collect();
+                anyhow::anyhow!(
+                    "Unknown column {:?}; expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Parse a comma-separated column list such as `short_url,status,errors`,
+/// ignoring repeats
+pub fn parse_columns(list: &str) -> Result<Vec<Column>> {
+    let mut columns = Vec::new();
+    for name in list.split(',').filter(|name| !name.trim().is_empty()) {
+        let column: Column = name.parse()?;
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+    if columns.is_empty() {
+        anyhow::bail!("No columns given");
+    }
+    Ok(columns)
+}
+
+/// Column the dashboard can be sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortColumn {
+    #[default]
+    ShortUrl,
+    Status,
+    Health,
+    LastRefresh,
+    NextRefresh,
+}
+
+impl SortColumn {
+    /// Sort columns in the order `s` cycles through them
+    const ALL: [SortColumn; 5] = [
+        SortColumn::ShortUrl,
+        SortColumn::Status,
+        SortColumn::Health,
+        SortColumn::LastRefresh,
+        SortColumn::NextRefresh,
+    ];
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|c| *c == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Table column showing the sorted value
+    pub fn column(self) -> Column {
+        match self {
+            SortColumn::ShortUrl => Column::ShortUrl,
+            SortColumn::Status => Column::Status,
+            SortColumn::Health => Column::Health,
+            SortColumn::LastRefresh => Column::LastRefresh,
+            SortColumn::NextRefresh => Column::NextRefresh,
+        }
+    }
+
+    /// Ascending order of two mappings by this column alone; never-refreshed
+    /// mappings and the least healthy ones come first
+    fn compare(self, a: &Mapping, b: &Mapping, now: DateTime<Utc>) -> std::cmp::Ordering {
+        match self {
+            SortColumn::ShortUrl => a.short_url.cmp(&b.short_url),
+            SortColumn::Status => a.status.to_string().cmp(&b.status.to_string()),
+            SortColumn::Health => a.health_score(now).cmp(&b.health_score(now)),
+            SortColumn::LastRefresh => a.last_refresh.cmp(&b.last_refresh),
+            SortColumn::NextRefresh => a.next_refresh.cmp(&b.next_refresh),
+        }
+    }
+}
+
+/// Table widths for `columns`: fixed-width columns keep their width and the
+/// rest split the flexible share by weight
+pub fn column_constraints(columns: &[Column]) -> Vec<Constraint> {
+    let total_weight: u16 = columns
+        .iter()
+        .filter(|c| c.fixed_width().is_none())
+        .map(Column::weight)
+        .sum();
+    columns
+        .iter()
+        .map(|c| match c.fixed_width() {
+            Some(width) => Constraint::Length(width),
+            None => Constraint::Percentage(c.weight() * FLEXIBLE_WIDTH_PERCENT / total_weight),
+        })
+        .collect()
+}
+
+/// Settings persisted between TUI sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientConfig {
+    /// Dashboard columns, in display order
+    #[serde(default)]
+    pub columns: Option<Vec<Column>>,
+}
+
+impl ClientConfig {
+    /// `$S3_BUDDY_CLIENT_CONFIG`, else `s3-buddy/client.json` in the user's
+    /// config directory
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("S3_BUDDY_CLIENT_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("s3-buddy").join("client.json"))
+    }
+
+    /// Saved settings, or the defaults if there are none
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or_else(|| anyhow::anyhow!("No config directory"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Age past which a mapping's last refresh counts as stale: the configured
+/// threshold, or one and a half refresh intervals
+pub fn stale_threshold(mapping: &Mapping, configured: Option<Duration>) -> Duration {
+    configured.unwrap_or_else(|| mapping.refresh_interval().mul_f64(1.5))
+}
+
+/// Whether an unpaused mapping's last refresh is older than `threshold`
+///
+/// Mappings that have never refreshed are shown as "Never" instead.
+pub fn is_stale(mapping: &Mapping, now: DateTime<Utc>, threshold: Duration) -> bool {
+    if !mapping.status.is_scheduled() {
+        return false;
+    }
+    mapping
+        .last_refresh
+        .is_some_and(|last| (now - last).to_std().is_ok_and(|age| age > threshold))
 }
 
 /// Run the TUI
-pub async fn run_tui(server_url: String) -> Result<()> {
+pub async fn run_tui(server_url: String, options: TuiOptions) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen)?;
+    if options.mouse_capture {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     let mut app = App::new(server_url);
+    app.stale_after = options.stale_after;
+    app.auto_refresh = options.auto_refresh;
+    if let Some(columns) = options.columns.or_else(|| ClientConfig::load().columns) {
+        app.columns = columns;
+    }
 
-    // Initial data fetch
+    // Initial data fetch; servers without /info just show no banner
+    app.motd = app.api.info().await.ok().and_then(|info| info.motd);
     if let Err(e) = fetch_mappings(&mut app).await {
         app.status_message = Some(format!("Error: {}", e));
     }
@@ -201,11 +780,10 @@ pub async fn run_tui(server_url: String) -> Result<()> {
 
     // Restore terminal
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    if options.mouse_capture {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
     terminal.show_cursor()?;
 
     if let Err(err) = res {
@@ -215,23 +793,65 @@ pub async fn run_tui(server_url: String) -> Result<()> {
     Ok(())
 }
 
+/// Default interval between the dashboard's own refetches of mappings
+pub const DEFAULT_AUTO_REFRESH: Duration = Duration::from_secs(15);
+
+/// Start of the status message a failed auto-refresh leaves
+const AUTO_REFRESH_FAILED: &str = "Auto-refresh failed";
+
+/// Event poll timeout while the user is active
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Event poll timeout once the user has been idle for a while
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Event poll timeout after `idle_polls` polls in a row without input: doubles
+/// from the minimum up to the maximum, so an idle dashboard wakes up less often
+fn poll_interval(idle_polls: u32) -> Duration {
+    let factor = 1u32.checked_shl(idle_polls).unwrap_or(u32::MAX);
+    MIN_POLL_INTERVAL
+        .saturating_mul(factor)
+        .min(MAX_POLL_INTERVAL)
+}
+
 async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    let mut idle_polls = 0u32;
+
     loop {
         terminal.draw(|f| ui(f, app))?;
 
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
+        if !event::poll(poll_interval(idle_polls))? {
+            idle_polls = idle_polls.saturating_add(1);
+        } else {
+            // Any input, including resizes, tightens polling back up
+            idle_polls = 0;
+            let event = event::read()?;
+            if let Event::Mouse(mouse) = event {
+                if app.current_view == View::Dashboard {
+                    handle_dashboard_mouse(app, mouse);
+                }
+            }
+            if let Event::Key(key) = event {
                 match app.current_view {
                     View::Dashboard => handle_dashboard_input(app, key.code, key.modifiers).await?,
                     View::AddMapping | View::EditMapping(_) => {
                         handle_form_input(app, key.code, key.modifiers).await?
                     }
                     View::DeleteConfirm(_) => handle_delete_confirm_input(app, key.code).await?,
+                    View::Details(_) => handle_details_input(app, key.code)?,
+                    View::DiscardConfirm { .. } => handle_discard_confirm_input(app, key.code)?,
+                    View::BulkConfirm { .. } => handle_bulk_confirm_input(app, key.code).await,
                     View::Help => handle_help_input(app, key.code)?,
+                    View::Columns => handle_columns_input(app, key.code),
+                    View::Logs(_) => handle_logs_input(app, key.code).await,
                 }
             }
         }
 
+        if app.auto_refresh_due(Instant::now()) {
+            auto_refresh(app).await;
+        }
+
         if app.should_quit {
             return Ok(());
         }
@@ -244,83 +864,114 @@ fn ui(f: &mut Frame, app: &mut App) {
         View::AddMapping => draw_form(f, app, "Add New Mapping"),
         View::EditMapping(_) => draw_form(f, app, "Edit Mapping"),
         View::DeleteConfirm(id) => draw_delete_confirm(f, app, *id),
+        View::Details(id) => {
+            let id = *id;
+            draw_dashboard(f, app);
+            draw_details(f, app, id);
+        }
+        View::DiscardConfirm { editing, .. } => {
+            let title = if editing.is_some() {
+                "Edit Mapping"
+            } else {
+                "Add New Mapping"
+            };
+            draw_form(f, app, title);
+            draw_discard_confirm(f);
+        }
+        View::BulkConfirm { pause } => {
+            let pause = *pause;
+            draw_dashboard(f, app);
+            draw_bulk_confirm(f, pause);
+        }
         View::Help => draw_help(f),
+        View::Columns => {
+            draw_dashboard(f, app);
+            draw_columns(f, app);
+        }
+        View::Logs(id) => draw_logs(f, app, *id),
     }
 }
 
 fn draw_dashboard(f: &mut Frame, app: &mut App) {
+    let header_height = if app.motd.is_some() { 4 } else { 3 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),
+            Constraint::Length(header_height),
             Constraint::Min(0),
             Constraint::Length(3),
         ])
         .split(f.area());
 
-    // Header
-    let title = Paragraph::new("S3 Buddy - URL Mapping Manager")
-        .style(
+    // Header, with the server's message of the day below the title
+    let mut header_lines = vec![Line::styled(
+        "S3 Buddy - URL Mapping Manager",
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )];
+    if let Some(motd) = &app.motd {
+        header_lines.push(Line::styled(
+            motd.as_str(),
             Style::default()
-                .fg(Color::Cyan)
+                .fg(Color::Black)
+                .bg(Color::Yellow)
                 .add_modifier(Modifier::BOLD),
-        )
+        ));
+    }
+    let title = Paragraph::new(header_lines)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::ALL));
     f.render_widget(title, chunks[0]);
 
     // Table
-    let header_cells = ["ID", "S3 URL", "Short URL", "Status", "Last Refresh"]
-        .iter()
-        .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow)));
+    let now = Utc::now();
+    let sorted_column = app.sort_by.column();
+    let header_cells = app.columns.iter().map(|c| {
+        if *c == sorted_column {
+            let arrow = if app.sort_desc { "▼" } else { "▲" };
+            Cell::from(format!("{} {}", c.title(), arrow)).style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            )
+        } else {
+            Cell::from(c.title()).style(Style::default().fg(Color::Yellow))
+        }
+    });
     let header = Row::new(header_cells).height(1).bottom_margin(1);
 
     let rows = app.mappings.iter().map(|m| {
-        let status_color = match m.status {
-            MappingStatus::Active => Color::Green,
-            MappingStatus::Paused => Color::Yellow,
-            MappingStatus::Error => Color::Red,
-            MappingStatus::Pending => Color::Blue,
-        };
-
-        let last_refresh = m
-            .last_refresh
-            .map(format_datetime)
-            .unwrap_or_else(|| "Never".to_string());
-
-        Row::new(vec![
-            Cell::from(m.id.to_string().chars().take(8).collect::<String>()),
-            Cell::from(m.s3_url.clone()),
-            Cell::from(m.short_url.clone()),
-            Cell::from(m.status.to_string()).style(Style::default().fg(status_color)),
-            Cell::from(last_refresh),
-        ])
+        Row::new(
+            app.columns
+                .iter()
+                .map(|c| column_cell(*c, m, now, app.stale_after)),
+        )
     });
 
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(10),
-            Constraint::Percentage(30),
-            Constraint::Percentage(25),
-            Constraint::Length(10),
-            Constraint::Percentage(20),
-        ],
-    )
-    .header(header)
-    .block(Block::default().borders(Borders::ALL).title("Mappings"))
-    .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-    .highlight_symbol(">> ");
+    let table = Table::new(rows, column_constraints(&app.columns))
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("Mappings"))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(">> ");
 
+    app.table_area = chunks[1];
     f.render_stateful_widget(table, chunks[1], &mut app.table_state);
 
     // Footer with keybindings and status
     let keybindings = vec![
+        Span::raw("Enter: Details | "),
         Span::raw("a: Add | "),
         Span::raw("e: Edit | "),
         Span::raw("d: Delete | "),
         Span::raw("p: Pause/Resume | "),
+        Span::raw("P/R: Pause/Resume all | "),
+        Span::raw("f: Force refresh | "),
         Span::raw("r: Refresh | "),
+        Span::raw("s/S: Sort column/direction | "),
+        Span::raw("h: Sort by health | "),
+        Span::raw("l: Logs | "),
+        Span::raw("c: Columns | "),
         Span::raw("?: Help | "),
         Span::raw("q: Quit"),
     ];
@@ -368,6 +1019,7 @@ fn draw_form(f: &mut Frame, app: &mut App, title: &str) {
             Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Length(3),
+            Constraint::Length(3),
             Constraint::Min(0),
         ])
         .split(chunks[1]);
@@ -384,6 +1036,7 @@ fn draw_form(f: &mut Frame, app: &mut App, title: &str) {
             "Refresh Interval (hours)",
             &app.form_state.refresh_interval_hours,
         ),
+        ("Notes", &app.form_state.notes),
     ];
 
     for (i, (label, value)) in fields.iter().enumerate() {
@@ -400,10 +1053,11 @@ fn draw_form(f: &mut Frame, app: &mut App, title: &str) {
     }
 
     // Footer
-    let footer =
-        Paragraph::new("Tab: Next field | Shift+Tab: Previous | Enter: Submit | Esc: Cancel")
-            .block(Block::default().borders(Borders::ALL))
-            .alignment(Alignment::Center);
+    let footer = Paragraph::new(
+        "Tab: Next field | Shift+Tab: Previous | Ctrl+T: Preflight | Enter: Submit | Esc: Cancel",
+    )
+    .block(Block::default().borders(Borders::ALL))
+    .alignment(Alignment::Center);
     f.render_widget(footer, chunks[2]);
 }
 
@@ -434,6 +1088,216 @@ fn draw_delete_confirm(f: &mut Frame, app: &mut App, id: Uuid) {
     f.render_widget(paragraph, area);
 }
 
+fn draw_details(f: &mut Frame, app: &App, id: Uuid) {
+    let area = centered_rect(70, 60, f.area());
+
+    let text = match app.mappings.iter().find(|m| m.id == id) {
+        Some(m) => vec![
+            Line::from(format!("ID: {}", m.id)),
+            Line::from(format!("S3 URL: {}", m.s3_url)),
+            Line::from(format!("Short URL: {}", m.short_url)),
+            Line::from(format!("Hosted Zone ID: {}", m.hosted_zone_id)),
+            Line::from(format!("Status: {}", m.status)),
+            Line::from(format!(
+                "Presign Duration: {}h | Refresh Interval: {}h",
+                m.presign_duration_secs / 3600,
+                m.refresh_interval_secs / 3600
+            )),
+            Line::from(format!(
+                "Last Error: {}",
+                m.last_error.as_deref().unwrap_or("None")
+            )),
+            Line::from(""),
+            Line::styled("Notes:", Style::default().fg(Color::Yellow)),
+            Line::from(m.notes.clone().unwrap_or_else(|| "None".to_string())),
+            Line::from(""),
+            Line::from("Press any key to return to dashboard"),
+        ],
+        None => vec![Line::from("Mapping not found")],
+    };
+
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Mapping Details"),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// One line per log entry, newest first, with failures in red
+fn log_lines(logs: &[RefreshLog]) -> Vec<Line<'static>> {
+    logs.iter()
+        .rev()
+        .map(|log| {
+            let text = format!(
+                "{}  {:?}  {}",
+                format_datetime(log.timestamp),
+                log.event,
+                log.message
+            );
+            if log.success {
+                Line::from(text)
+            } else {
+                Line::styled(text, Style::default().fg(Color::Red))
+            }
+        })
+        .collect()
+}
+
+fn draw_logs(f: &mut Frame, app: &App, id: Uuid) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(f.area());
+
+    let short_url = app
+        .mappings
+        .iter()
+        .find(|m| m.id == id)
+        .map_or_else(|| id.to_string(), |m| m.short_url.clone());
+    let lines = if app.logs.is_empty() {
+        vec![Line::from("No refreshes logged yet")]
+    } else {
+        log_lines(&app.logs)
+    };
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Refresh Logs - {} (newest first)", short_url)),
+        )
+        .scroll((app.logs_scroll, 0));
+    f.render_widget(paragraph, chunks[0]);
+
+    let footer_text = app
+        .status_message
+        .clone()
+        .unwrap_or_else(|| "↑/↓: Scroll | PgUp/PgDn: Page | r: Reload | Esc: Back".to_string());
+    let footer = Paragraph::new(footer_text).block(Block::default().borders(Borders::ALL));
+    f.render_widget(footer, chunks[1]);
+}
+
+fn draw_discard_confirm(f: &mut Frame) {
+    let area = centered_rect(40, 20, f.area());
+
+    let paragraph =
+        Paragraph::new("Discard unsaved changes?\n\nPress 'y' to discard or 'n' to keep editing")
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Unsaved Changes")
+                    .style(Style::default().fg(Color::Yellow)),
+            )
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Center);
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn draw_bulk_confirm(f: &mut Frame, pause: bool) {
+    let area = centered_rect(50, 20, f.area());
+
+    let (question, title) = if pause {
+        ("Pause every refreshing mapping?", "Confirm Pause All")
+    } else {
+        ("Resume every mapping paused in bulk?", "Confirm Resume All")
+    };
+    let paragraph = Paragraph::new(format!(
+        "{}\n\nPress 'y' to confirm or 'n' to cancel",
+        question
+    ))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(Color::Red)),
+    )
+    .wrap(Wrap { trim: true })
+    .alignment(Alignment::Center);
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// A mapping's cell in `column`
+fn column_cell(
+    column: Column,
+    m: &Mapping,
+    now: DateTime<Utc>,
+    stale_after: Option<Duration>,
+) -> Cell<'static> {
+    match column {
+        Column::Id => Cell::from(m.id.to_string().chars().take(8).collect::<String>()),
+        Column::S3Url => Cell::from(m.s3_url.clone()),
+        Column::ShortUrl => Cell::from(m.short_url.clone()),
+        Column::Status => {
+            let status_color = match m.status {
+                MappingStatus::Active => Color::Green,
+                MappingStatus::Paused => Color::Yellow,
+                MappingStatus::Error => Color::Red,
+                MappingStatus::Pending => Color::Blue,
+                MappingStatus::Quarantined => Color::Magenta,
+            };
+            Cell::from(m.status.to_string()).style(Style::default().fg(status_color))
+        }
+        Column::Health => Cell::from(m.health_score(now).to_string()),
+        Column::LastRefresh => {
+            let last_refresh = m
+                .last_refresh
+                .map(format_datetime)
+                .unwrap_or_else(|| "Never".to_string());
+            let style = if is_stale(m, now, stale_threshold(m, stale_after)) {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            Cell::from(last_refresh).style(style)
+        }
+        Column::NextRefresh => Cell::from(
+            m.next_refresh
+                .map(|next| format_countdown(next, now))
+                .unwrap_or_else(|| "-".to_string()),
+        ),
+        Column::Errors => match &m.last_error {
+            Some(error) => Cell::from(error.clone()).style(Style::default().fg(Color::Red)),
+            None => Cell::from(""),
+        },
+        Column::Notes => Cell::from(m.notes.clone().unwrap_or_default()),
+    }
+}
+
+/// Column settings popup over the dashboard
+fn draw_columns(f: &mut Frame, app: &App) {
+    let mut lines: Vec<Line> = app
+        .column_choices()
+        .into_iter()
+        .enumerate()
+        .map(|(i, (column, shown))| {
+            let text = format!("[{}] {}", if shown { "x" } else { " " }, column.title());
+            if i == app.column_cursor {
+                Line::styled(text, Style::default().add_modifier(Modifier::REVERSED))
+            } else {
+                Line::from(text)
+            }
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from("Space: Show/Hide | K/J: Move | Enter/Esc: Save"));
+
+    let area = centered_rect(50, 60, f.area());
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Columns"))
+        .alignment(Alignment::Left);
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
 fn draw_help(f: &mut Frame) {
     let help_text = vec![
         Line::from(""),
@@ -446,11 +1310,20 @@ fn draw_help(f: &mut Frame) {
         Line::from(""),
         Line::from("Dashboard View:"),
         Line::from("  ↑/↓ or j/k    - Navigate mappings"),
+        Line::from("  Enter         - Show details and notes of selected mapping"),
         Line::from("  a             - Add new mapping"),
         Line::from("  e             - Edit selected mapping"),
         Line::from("  d             - Delete selected mapping"),
         Line::from("  p             - Pause/Resume selected mapping"),
+        Line::from("  P             - Pause all mappings"),
+        Line::from("  R             - Resume the mappings paused in bulk"),
+        Line::from("  f             - Refresh selected mapping's URL now"),
         Line::from("  r             - Refresh mappings list"),
+        Line::from("  s             - Sort by the next column"),
+        Line::from("  S             - Reverse the sort direction"),
+        Line::from("  h             - Toggle sorting by health (worst first)"),
+        Line::from("  l             - Show refresh logs of selected mapping"),
+        Line::from("  c             - Choose and order columns"),
         Line::from("  ?             - Show this help"),
         Line::from("  q             - Quit application"),
         Line::from(""),
@@ -458,6 +1331,7 @@ fn draw_help(f: &mut Frame) {
         Line::from("  Tab           - Next field"),
         Line::from("  Shift+Tab     - Previous field"),
         Line::from("  Enter         - Submit form"),
+        Line::from("  Ctrl+T        - Preflight: check the object and presigning"),
         Line::from("  Esc           - Cancel"),
         Line::from(""),
         Line::from("Press any key to return to dashboard"),
@@ -502,19 +1376,18 @@ async fn handle_dashboard_input(
         KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => app.should_quit = true,
         KeyCode::Down | KeyCode::Char('j') => app.next_row(),
         KeyCode::Up | KeyCode::Char('k') => app.previous_row(),
+        KeyCode::Enter => {
+            if let Some(mapping) = app.selected_mapping() {
+                app.current_view = View::Details(mapping.id);
+                app.status_message = None;
+            }
+        }
         KeyCode::Char('a') => {
             app.form_state.clear();
             app.status_message = None;
             app.current_view = View::AddMapping;
         }
-        KeyCode::Char('e') => {
-            if let Some(mapping) = app.selected_mapping().cloned() {
-                let id = mapping.id;
-                app.form_state.populate_from_mapping(&mapping);
-                app.status_message = None;
-                app.current_view = View::EditMapping(id);
-            }
-        }
+        KeyCode::Char('e') => app.edit_selected(),
         KeyCode::Char('d') => {
             if let Some(mapping) = app.selected_mapping().cloned() {
                 app.status_message = None;
@@ -532,6 +1405,22 @@ async fn handle_dashboard_input(
                 }
             }
         }
+        KeyCode::Char('P') => {
+            app.status_message = None;
+            app.current_view = View::BulkConfirm { pause: true };
+        }
+        KeyCode::Char('R') => {
+            app.status_message = None;
+            app.current_view = View::BulkConfirm { pause: false };
+        }
+        KeyCode::Char('f') => {
+            app.status_message = None;
+            if let Some(mapping) = app.selected_mapping().cloned() {
+                if let Err(e) = refresh_mapping_now(app, mapping.id).await {
+                    app.status_message = Some(format!("Error: {}", e));
+                }
+            }
+        }
         KeyCode::Char('r') => {
             app.status_message = None;
             if let Err(e) = fetch_mappings(app).await {
@@ -540,6 +1429,41 @@ async fn handle_dashboard_input(
                 app.status_message = Some("Mappings refreshed".to_string());
             }
         }
+        KeyCode::Char('s') => {
+            app.cycle_sort_column();
+            app.status_message = Some(app.sort_description());
+        }
+        KeyCode::Char('S') => {
+            app.toggle_sort_direction();
+            app.status_message = Some(app.sort_description());
+        }
+        KeyCode::Char('h') => {
+            app.sort_desc = false;
+            if app.sort_by == SortColumn::Health {
+                app.sort_by = SortColumn::default();
+                app.sort_mappings();
+                app.status_message = None;
+            } else {
+                app.sort_by = SortColumn::Health;
+                app.sort_mappings();
+                app.status_message = Some("Sorted by health, worst first".to_string());
+            }
+        }
+        KeyCode::Char('l') => {
+            app.status_message = None;
+            if let Some(mapping) = app.selected_mapping().cloned() {
+                app.logs_scroll = 0;
+                match fetch_mapping_logs(app, mapping.id).await {
+                    Ok(()) => app.current_view = View::Logs(mapping.id),
+                    Err(e) => app.status_message = Some(format!("Error fetching logs: {}", e)),
+                }
+            }
+        }
+        KeyCode::Char('c') => {
+            app.status_message = None;
+            app.column_cursor = 0;
+            app.current_view = View::Columns;
+        }
         KeyCode::Char('?') => {
             app.status_message = None;
             app.current_view = View::Help;
@@ -549,22 +1473,64 @@ async fn handle_dashboard_input(
     Ok(())
 }
 
+/// Click to select a row, double-click to edit it, scroll to move the selection
+fn handle_dashboard_mouse(app: &mut App, mouse: MouseEvent) {
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            let double_click = app.click(mouse.column, mouse.row, Instant::now());
+            if double_click {
+                app.edit_selected();
+            }
+        }
+        MouseEventKind::ScrollDown => app.next_row(),
+        MouseEventKind::ScrollUp => app.previous_row(),
+        _ => {}
+    }
+}
+
 async fn handle_form_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) -> Result<()> {
+    let editing = match app.current_view {
+        View::EditMapping(id) => Some(id),
+        _ => None,
+    };
+
     match key {
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+            if app.form_state.dirty {
+                app.current_view = View::DiscardConfirm {
+                    editing,
+                    quit: true,
+                };
+            } else {
+                app.should_quit = true;
+            }
+        }
         KeyCode::Esc => {
-            app.current_view = View::Dashboard;
-            app.form_state.clear();
-            app.status_message = None;
+            if app.form_state.dirty {
+                app.current_view = View::DiscardConfirm {
+                    editing,
+                    quit: false,
+                };
+            } else {
+                app.current_view = View::Dashboard;
+                app.form_state.clear();
+                app.status_message = None;
+            }
+        }
+        KeyCode::Char('t') if modifiers.contains(KeyModifiers::CONTROL) => {
+            if let Err(e) = preflight_mapping(app).await {
+                app.status_message = Some(format!("Error: {}", e));
+            }
         }
         KeyCode::Tab => {
             if modifiers.contains(KeyModifiers::SHIFT) {
                 if app.form_state.current_field > 0 {
                     app.form_state.current_field -= 1;
                 } else {
-                    app.form_state.current_field = 4;
+                    app.form_state.current_field = 5;
                 }
             } else {
-                app.form_state.current_field = (app.form_state.current_field + 1) % 5;
+                app.form_state.current_field = (app.form_state.current_field + 1) % 6;
             }
         }
         KeyCode::Enter => {
@@ -592,28 +1558,8 @@ async fn handle_form_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers)
                 _ => {}
             }
         }
-        KeyCode::Char(c) => {
-            let field = match app.form_state.current_field {
-                0 => &mut app.form_state.s3_url,
-                1 => &mut app.form_state.short_url,
-                2 => &mut app.form_state.hosted_zone_id,
-                3 => &mut app.form_state.presign_duration_hours,
-                4 => &mut app.form_state.refresh_interval_hours,
-                _ => return Ok(()),
-            };
-            field.push(c);
-        }
-        KeyCode::Backspace => {
-            let field = match app.form_state.current_field {
-                0 => &mut app.form_state.s3_url,
-                1 => &mut app.form_state.short_url,
-                2 => &mut app.form_state.hosted_zone_id,
-                3 => &mut app.form_state.presign_duration_hours,
-                4 => &mut app.form_state.refresh_interval_hours,
-                _ => return Ok(()),
-            };
-            field.pop();
-        }
+        KeyCode::Char(c) => app.form_state.push_char(c),
+        KeyCode::Backspace => app.form_state.pop_char(),
         _ => {}
     }
     Ok(())
@@ -640,24 +1586,131 @@ async fn handle_delete_confirm_input(app: &mut App, key: KeyCode) -> Result<()>
     Ok(())
 }
 
+async fn handle_bulk_confirm_input(app: &mut App, key: KeyCode) {
+    let View::BulkConfirm { pause } = app.current_view else {
+        return;
+    };
+
+    match key {
+        KeyCode::Char('y') => {
+            app.current_view = View::Dashboard;
+            let result = if pause {
+                pause_all(app).await
+            } else {
+                resume_all(app).await
+            };
+            if let Err(e) = result {
+                app.status_message = Some(format!("Error: {}", e));
+            }
+        }
+        KeyCode::Char('n') | KeyCode::Esc => {
+            app.current_view = View::Dashboard;
+            app.status_message = None;
+        }
+        _ => {}
+    }
+}
+
+fn handle_discard_confirm_input(app: &mut App, key: KeyCode) -> Result<()> {
+    let View::DiscardConfirm { editing, quit } = app.current_view else {
+        return Ok(());
+    };
+
+    match key {
+        KeyCode::Char('y') => {
+            app.form_state.clear();
+            app.status_message = None;
+            app.current_view = View::Dashboard;
+            app.should_quit = quit;
+        }
+        KeyCode::Char('n') | KeyCode::Esc => {
+            app.current_view = match editing {
+                Some(id) => View::EditMapping(id),
+                None => View::AddMapping,
+            };
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_details_input(app: &mut App, _key: KeyCode) -> Result<()> {
+    app.current_view = View::Dashboard;
+    Ok(())
+}
+
 fn handle_help_input(app: &mut App, _key: KeyCode) -> Result<()> {
     app.current_view = View::Dashboard;
     Ok(())
 }
 
-// API client functions
-async fn fetch_mappings(app: &mut App) -> Result<()> {
-    let url = format!("{}/mappings", app.server_url);
-    let client = reqwest::Client::new();
-    let response = client.get(&url).send().await?;
+async fn handle_logs_input(app: &mut App, key: KeyCode) {
+    let last_line = u16::try_from(app.logs.len().saturating_sub(1)).unwrap_or(u16::MAX);
+    match key {
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.logs_scroll = app.logs_scroll.saturating_add(1).min(last_line);
+        }
+        KeyCode::Up | KeyCode::Char('k') => app.logs_scroll = app.logs_scroll.saturating_sub(1),
+        KeyCode::PageDown => app.logs_scroll = app.logs_scroll.saturating_add(10).min(last_line),
+        KeyCode::PageUp => app.logs_scroll = app.logs_scroll.saturating_sub(10),
+        KeyCode::Char('r') => {
+            if let View::Logs(id) = app.current_view {
+                app.status_message = fetch_mapping_logs(app, id)
+                    .await
+                    .err()
+                    .map(|e| format!("Error fetching logs: {}", e));
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.status_message = None;
+            app.current_view = View::Dashboard;
+        }
+        _ => {}
+    }
+}
 
-    if !response.status().is_success() {
-        anyhow::bail!("Failed to fetch mappings: {}", response.status());
+fn handle_columns_input(app: &mut App, key: KeyCode) {
+    let choices = app.column_choices().len();
+    match key {
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.column_cursor = (app.column_cursor + 1).min(choices - 1);
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.column_cursor = app.column_cursor.saturating_sub(1);
+        }
+        KeyCode::Char(' ') => app.toggle_column(),
+        KeyCode::Char('K') => app.move_column(-1),
+        KeyCode::Char('J') => app.move_column(1),
+        KeyCode::Enter | KeyCode::Esc => {
+            let config = ClientConfig {
+                columns: Some(app.columns.clone()),
+            };
+            app.status_message = match config.save() {
+                Ok(()) => None,
+                Err(e) => Some(format!("Error saving columns: {:#}", e)),
+            };
+            app.current_view = View::Dashboard;
+        }
+        _ => {}
     }
+}
 
-    let data: crate::types::ListMappingsResponse = response.json().await?;
-    app.mappings = data.mappings;
+// API client functions
+async fn fetch_mapping_logs(app: &mut App, id: Uuid) -> Result<()> {
+    app.logs = app.api.mapping_logs(id).await?;
+    Ok(())
+}
 
+async fn fetch_mappings(app: &mut App) -> Result<()> {
+    app.last_fetch = Instant::now();
+    let selected = app.selected_mapping().map(|m| m.id);
+    app.mappings = app.api.list_mappings().await?;
+    app.sort_mappings();
+
+    // Keep the same mapping selected even if it moved
+    if let Some(row) = selected.and_then(|id| app.mappings.iter().position(|m| m.id == id)) {
+        app.table_state.select(Some(row));
+    }
     // Ensure table state is valid
     if !app.mappings.is_empty() && app.table_state.selected().is_none() {
         app.table_state.select(Some(0));
@@ -666,88 +1719,641 @@ async fn fetch_mappings(app: &mut App) -> Result<()> {
     Ok(())
 }
 
+/// Refetch mappings in the background, reporting failures in the status
+/// line instead of ending the session
+async fn auto_refresh(app: &mut App) {
+    match fetch_mappings(app).await {
+        Ok(()) => {
+            let failed_before = app
+                .status_message
+                .as_deref()
+                .is_some_and(|m| m.starts_with(AUTO_REFRESH_FAILED));
+            if failed_before {
+                app.status_message = None;
+            }
+        }
+        Err(e) => app.status_message = Some(format!("{}: {}", AUTO_REFRESH_FAILED, e)),
+    }
+}
+
 async fn create_mapping(app: &mut App) -> Result<()> {
     let request = app.form_state.to_request()?;
-    let url = format!("{}/mappings", app.server_url);
-    let client = reqwest::Client::new();
-    let response = client.post(&url).json(&request).send().await?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        anyhow::bail!("Failed to create mapping: {}", error_text);
-    }
+    app.api.create_mapping(&request).await?;
 
     fetch_mappings(app).await?;
     Ok(())
 }
 
+/// Check the form's object can be read and presigned, without saving
+async fn preflight_mapping(app: &mut App) -> Result<()> {
+    let request = app.form_state.to_request()?;
+    let report = app.api.validate_mapping(&request).await?;
+
+    app.status_message = Some(if report.ok {
+        format!(
+            "Preflight OK: object readable, presigned via {}",
+            report.url_sample_host.as_deref().unwrap_or("S3")
+        )
+    } else {
+        format!("Preflight failed: {}", report.errors.join("; "))
+    });
+    Ok(())
+}
+
 async fn update_mapping(app: &mut App, id: Uuid) -> Result<()> {
     let request = app.form_state.to_request()?;
     let update_request = crate::types::UpdateMappingRequest {
         s3_url: Some(request.s3_url),
         short_url: Some(request.short_url),
         hosted_zone_id: Some(request.hosted_zone_id),
-        presign_duration_secs: Some(request.presign_duration_secs),
-        refresh_interval_secs: Some(request.refresh_interval_secs),
+        slug: None,
+        presign_duration_secs: request.presign_duration_secs,
+        refresh_interval_secs: request.refresh_interval_secs,
+        max_presign_duration_secs: None,
+        credentials_profile: None,
+        requester_pays: None,
+        use_accelerate: None,
+        extra_query_params: None,
+        access_point_arn: None,
+        addressing_style: None,
+        manage_dns: None,
+        operation: None,
+        dns_record_type: None,
+        dns_ttl_secs: None,
+        allowed_window: None,
+        force_content_type: None,
+        download_filename: None,
+        fallback_s3_urls: None,
+        // Always sent so clearing the field clears the notes
+        notes: Some(request.notes.unwrap_or_default()),
     };
-
-    let url = format!("{}/mappings/{}", app.server_url, id);
-    let client = reqwest::Client::new();
-    let response = client.put(&url).json(&update_request).send().await?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        anyhow::bail!("Failed to update mapping: {}", error_text);
-    }
+    app.api.update_mapping(id, &update_request).await?;
 
     fetch_mappings(app).await?;
     Ok(())
 }
 
 async fn delete_mapping(app: &mut App, id: Uuid) -> Result<()> {
-    let url = format!("{}/mappings/{}", app.server_url, id);
-    let client = reqwest::Client::new();
-    let response = client.delete(&url).send().await?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        anyhow::bail!("Failed to delete mapping: {}", error_text);
-    }
+    app.api.delete_mapping(id).await?;
 
     fetch_mappings(app).await?;
     Ok(())
 }
 
 async fn pause_mapping(app: &mut App, id: Uuid) -> Result<()> {
-    let url = format!("{}/mappings/{}/pause", app.server_url, id);
-    let client = reqwest::Client::new();
-    let response = client.post(&url).send().await?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        anyhow::bail!("Failed to pause mapping: {}", error_text);
-    }
+    app.api.pause_mapping(id).await?;
 
     fetch_mappings(app).await?;
     app.status_message = Some("Mapping paused".to_string());
     Ok(())
 }
 
-async fn resume_mapping(app: &mut App, id: Uuid) -> Result<()> {
-    let url = format!("{}/mappings/{}/resume", app.server_url, id);
-    let client = reqwest::Client::new();
-    let response = client.post(&url).send().await?;
+/// Refresh a mapping's presigned URL now, leaving its schedule alone
+async fn refresh_mapping_now(app: &mut App, id: Uuid) -> Result<()> {
+    let result = app.api.refresh_mapping(id).await?;
+    fetch_mappings(app).await?;
+    app.status_message = Some(if !result.refreshed {
+        format!("{} was refreshed moments ago", result.mapping.short_url)
+    } else if result.mapping.status == MappingStatus::Error {
+        format!(
+            "Refresh of {} failed: {}",
+            result.mapping.short_url,
+            result
+                .mapping
+                .last_error
+                .as_deref()
+                .unwrap_or("unknown error")
+        )
+    } else {
+        format!("Refreshed {}", result.mapping.short_url)
+    });
+    Ok(())
+}
 
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        anyhow::bail!("Failed to resume mapping: {}", error_text);
-    }
+async fn resume_mapping(app: &mut App, id: Uuid) -> Result<()> {
+    app.api.resume_mapping(id).await?;
 
     fetch_mappings(app).await?;
     app.status_message = Some("Mapping resumed".to_string());
     Ok(())
 }
 
+async fn pause_all(app: &mut App) -> Result<()> {
+    let report = app.api.pause_all().await?;
+
+    fetch_mappings(app).await?;
+    app.status_message = Some(format!("Paused {} mapping(s)", report.changed));
+    Ok(())
+}
+
+async fn resume_all(app: &mut App) -> Result<()> {
+    let report = app.api.resume_all().await?;
+
+    fetch_mappings(app).await?;
+    app.status_message = Some(format!("Resumed {} mapping(s)", report.changed));
+    Ok(())
+}
+
 fn format_datetime(dt: DateTime<Utc>) -> String {
     dt.format("%Y-%m-%d %H:%M:%S").to_string()
 }
+
+/// Time until `next` in its two largest units, e.g. `in 1h 5m` or `in 42m`;
+/// `due` once it has passed
+fn format_countdown(next: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let secs = (next - now).num_seconds();
+    if secs <= 0 {
+        return "due".to_string();
+    }
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("in {}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("in {}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("in {}m", minutes)
+    } else {
+        format!("in {}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_lines_newest_first_with_failures_in_red() {
+        let log = |success, event, message: &str| RefreshLog {
+            mapping_id: Uuid::nil(),
+            timestamp: Utc::now(),
+            success,
+            event,
+            message: message.to_string(),
+        };
+        let lines = log_lines(&[
+            log(
+                false,
+                crate::types::RefreshEvent::PresignFailed,
+                "AccessDenied",
+            ),
+            log(true, crate::types::RefreshEvent::Success, "Refreshed"),
+        ]);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].to_string().ends_with("Success  Refreshed"));
+        assert_eq!(lines[0].style.fg, None);
+        assert!(lines[1]
+            .to_string()
+            .ends_with("PresignFailed  AccessDenied"));
+        assert_eq!(lines[1].style.fg, Some(Color::Red));
+    }
+
+    #[test]
+    fn test_default_columns_keep_original_widths() {
+        assert_eq!(
+            column_constraints(&DEFAULT_COLUMNS),
+            vec![
+                Constraint::Length(10),
+                Constraint::Percentage(30),
+                Constraint::Percentage(25),
+                Constraint::Length(10),
+                Constraint::Length(7),
+                Constraint::Percentage(20),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_column_widths_follow_selection() {
+        let columns = parse_columns("short_url, status,errors,short_url").unwrap();
+        assert_eq!(
+            columns,
+            vec![Column::ShortUrl, Column::Status, Column::Errors]
+        );
+        // The two flexible columns split the flexible share 25:30
+        assert_eq!(
+            column_constraints(&columns),
+            vec![
+                Constraint::Percentage(34),
+                Constraint::Length(10),
+                Constraint::Percentage(40),
+            ]
+        );
+
+        assert!(parse_columns("id,owner").is_err());
+        assert!(parse_columns(" , ").is_err());
+    }
+
+    #[test]
+    fn test_toggle_and_move_columns() {
+        let mut app = App::new("http://localhost:3000".to_string());
+        app.columns = vec![Column::Id, Column::Status];
+
+        // Hidden columns follow the shown ones; showing one appends it
+        app.column_cursor = 2;
+        assert_eq!(app.column_choices()[2], (Column::S3Url, false));
+        app.toggle_column();
+        assert_eq!(app.columns, vec![Column::Id, Column::Status, Column::S3Url]);
+
+        app.move_column(-1);
+        assert_eq!(app.columns, vec![Column::Id, Column::S3Url, Column::Status]);
+        assert_eq!(app.column_cursor, 1);
+
+        app.column_cursor = 0;
+        app.toggle_column();
+        app.toggle_column();
+        app.toggle_column();
+        // The last shown column can't be hidden
+        assert_eq!(app.columns, vec![Column::Status]);
+    }
+
+    #[test]
+    fn test_sort_mappings() {
+        let mut app = App::new("http://localhost:3000".to_string());
+        let mapping = |short_url: &str, status| {
+            let mut m = Mapping::new(
+                "s3://bucket/key".to_string(),
+                short_url.to_string(),
+                "Z1".to_string(),
+            );
+            m.status = status;
+            m
+        };
+        app.mappings = vec![
+            mapping("c.example.com", MappingStatus::Active),
+            mapping("a.example.com", MappingStatus::Paused),
+            mapping("b.example.com", MappingStatus::Active),
+        ];
+        let order = |app: &App| -> Vec<String> {
+            app.mappings.iter().map(|m| m.short_url.clone()).collect()
+        };
+
+        // Short URL ascending by default
+        app.sort_mappings();
+        assert_eq!(
+            order(&app),
+            ["a.example.com", "b.example.com", "c.example.com"]
+        );
+
+        // Ties on status fall back to the short URL
+        app.cycle_sort_column();
+        assert_eq!(app.sort_by, SortColumn::Status);
+        assert_eq!(
+            order(&app),
+            ["b.example.com", "c.example.com", "a.example.com"]
+        );
+
+        app.toggle_sort_direction();
+        assert_eq!(
+            order(&app),
+            ["a.example.com", "c.example.com", "b.example.com"]
+        );
+
+        // Cycling resets the direction and wraps around
+        for _ in 0..SortColumn::ALL.len() - 1 {
+            app.cycle_sort_column();
+        }
+        assert_eq!(app.sort_by, SortColumn::ShortUrl);
+        assert!(!app.sort_desc);
+    }
+
+    #[test]
+    fn test_click_selects_row_and_double_click() {
+        let mut app = App::new("http://localhost:3000".to_string());
+        app.mappings = ["a", "b", "c"]
+            .iter()
+            .map(|name| {
+                Mapping::new(
+                    "s3://bucket/key".to_string(),
+                    format!("{}.example.com", name),
+                    "Z1".to_string(),
+                )
+            })
+            .collect();
+        // Border at y=3, header at y=4, margin at y=5, rows from y=6
+        app.table_area = Rect::new(0, 3, 80, 10);
+
+        assert_eq!(app.row_at(10, 4), None);
+        assert_eq!(app.row_at(10, 6), Some(0));
+        assert_eq!(app.row_at(10, 8), Some(2));
+        // Below the last mapping, and on the left border
+        assert_eq!(app.row_at(10, 9), None);
+        assert_eq!(app.row_at(0, 6), None);
+
+        let now = Instant::now();
+        assert!(!app.click(10, 7, now));
+        assert_eq!(app.table_state.selected(), Some(1));
+        assert!(app.click(10, 7, now + Duration::from_millis(200)));
+        // Too slow, or on another row, isn't a double-click
+        assert!(!app.click(10, 7, now + Duration::from_secs(2)));
+        assert!(!app.click(10, 8, now + Duration::from_millis(2100)));
+        assert_eq!(app.table_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let now = Utc::now();
+        let threshold = Duration::from_secs(3600);
+        let mut mapping = Mapping::new(
+            "s3://bucket/key".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        mapping.status = MappingStatus::Active;
+
+        // Never refreshed: shown as "Never", not stale
+        assert!(!is_stale(&mapping, now, threshold));
+
+        // Fresh
+        mapping.last_refresh = Some(now - chrono::Duration::minutes(5));
+        assert!(!is_stale(&mapping, now, threshold));
+
+        // Borderline: exactly at the threshold is not yet stale
+        mapping.last_refresh = Some(now - chrono::Duration::hours(1));
+        assert!(!is_stale(&mapping, now, threshold));
+        mapping.last_refresh = Some(now - chrono::Duration::seconds(3601));
+        assert!(is_stale(&mapping, now, threshold));
+
+        // Stale, unless paused
+        mapping.last_refresh = Some(now - chrono::Duration::days(2));
+        assert!(is_stale(&mapping, now, threshold));
+        mapping.status = MappingStatus::Paused;
+        assert!(!is_stale(&mapping, now, threshold));
+    }
+
+    #[test]
+    fn test_stale_threshold_defaults_to_refresh_interval() {
+        let mut mapping = Mapping::new(
+            "s3://bucket/key".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        mapping.refresh_interval_secs = 3600;
+
+        assert_eq!(stale_threshold(&mapping, None), Duration::from_secs(5400));
+        assert_eq!(
+            stale_threshold(&mapping, Some(Duration::from_secs(60))),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_poll_interval_backs_off_when_idle() {
+        let ms = |idle_polls| poll_interval(idle_polls).as_millis();
+
+        // Active: poll quickly
+        assert_eq!(ms(0), 100);
+
+        // Idle: back off, capped at the maximum
+        assert_eq!(ms(1), 200);
+        assert_eq!(ms(2), 400);
+        assert_eq!(ms(3), 500);
+        assert_eq!(ms(40), 500);
+        assert_eq!(ms(u32::MAX), 500);
+    }
+
+    #[test]
+    fn test_auto_refresh_only_on_dashboard() {
+        let mut app = App::new("http://localhost:3000".to_string());
+        let start = app.last_fetch;
+        assert!(!app.auto_refresh_due(start + Duration::from_secs(14)));
+        assert!(app.auto_refresh_due(start + DEFAULT_AUTO_REFRESH));
+
+        // An open form isn't disturbed
+        app.current_view = View::AddMapping;
+        assert!(!app.auto_refresh_due(start + Duration::from_secs(60)));
+
+        app.current_view = View::Dashboard;
+        app.auto_refresh = None;
+        assert!(!app.auto_refresh_due(start + Duration::from_secs(3600)));
+    }
+
+    #[tokio::test]
+    async fn test_bulk_pause_and_resume_ask_first() {
+        let mut app = App::new("http://localhost:3000".to_string());
+        handle_dashboard_input(&mut app, KeyCode::Char('P'), KeyModifiers::NONE)
+            .await
+            .unwrap();
+        assert_eq!(app.current_view, View::BulkConfirm { pause: true });
+
+        // Other keys leave the prompt up; 'n' backs out without a request
+        handle_bulk_confirm_input(&mut app, KeyCode::Char('x')).await;
+        assert_eq!(app.current_view, View::BulkConfirm { pause: true });
+        handle_bulk_confirm_input(&mut app, KeyCode::Char('n')).await;
+        assert_eq!(app.current_view, View::Dashboard);
+        assert!(app.status_message.is_none());
+
+        handle_dashboard_input(&mut app, KeyCode::Char('R'), KeyModifiers::NONE)
+            .await
+            .unwrap();
+        assert_eq!(app.current_view, View::BulkConfirm { pause: false });
+        handle_bulk_confirm_input(&mut app, KeyCode::Esc).await;
+        assert_eq!(app.current_view, View::Dashboard);
+    }
+
+    #[test]
+    fn test_format_countdown() {
+        let now: DateTime<Utc> = "2024-05-01T12:00:00Z".parse().unwrap();
+        let at = |secs| now + chrono::Duration::seconds(secs);
+        assert_eq!(format_countdown(at(42 * 60 + 10), now), "in 42m");
+        assert_eq!(format_countdown(at(3600 + 5 * 60), now), "in 1h 5m");
+        assert_eq!(format_countdown(at(2 * 86400 + 3 * 3600), now), "in 2d 3h");
+        assert_eq!(format_countdown(at(30), now), "in 30s");
+        assert_eq!(format_countdown(at(0), now), "due");
+        assert_eq!(format_countdown(at(-90), now), "due");
+    }
+
+    #[test]
+    fn test_dashboard_shows_motd() {
+        let backend = ratatui::backend::TestBackend::new(80, 20);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new("http://localhost:3000".to_string());
+        app.motd = Some("Prod instance - changes are live".to_string());
+
+        terminal.draw(|f| draw_dashboard(f, &mut app)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("Prod instance - changes are live"));
+    }
+
+    #[test]
+    fn test_form_dirty_tracking() {
+        let mut form = FormState::default();
+        assert!(!form.dirty);
+
+        form.push_char('s');
+        assert!(form.dirty);
+
+        form.clear();
+        assert!(!form.dirty);
+
+        // Backspace on an empty field changes nothing
+        form.pop_char();
+        assert!(!form.dirty);
+
+        form.current_field = 3;
+        form.pop_char();
+        assert!(form.dirty);
+
+        let mapping = Mapping::new(
+            "s3://bucket/key".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        form.populate_from_mapping(&mapping);
+        assert!(!form.dirty);
+    }
+
+    #[test]
+    fn test_form_rejects_invalid_short_url() {
+        let mut form = FormState {
+            s3_url: "s3://bucket/key".to_string(),
+            short_url: "https://files.example.com/report".to_string(),
+            hosted_zone_id: "Z1234567890ABC".to_string(),
+            ..Default::default()
+        };
+        let err = form.to_request().unwrap_err();
+        assert!(err.to_string().contains("remove the scheme"), "{}", err);
+
+        form.short_url = "Files.Example.com".to_string();
+        assert_eq!(form.to_request().unwrap().short_url, "files.example.com");
+    }
+
+    #[test]
+    fn test_form_rejects_zero_and_empty_durations() {
+        let mut form = FormState {
+            s3_url: "s3://bucket/key".to_string(),
+            short_url: "files.example.com".to_string(),
+            hosted_zone_id: "Z1234567890ABC".to_string(),
+            presign_duration_hours: "0".to_string(),
+            refresh_interval_hours: "11".to_string(),
+            ..Default::default()
+        };
+        let err = form.to_request().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Presign duration must be greater than 0"));
+
+        form.presign_duration_hours = "".to_string();
+        let err = form.to_request().unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid presign duration"),
+            "{}",
+            err
+        );
+
+        form.presign_duration_hours = "12".to_string();
+        form.refresh_interval_hours = "0".to_string();
+        let err = form.to_request().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Refresh interval must be greater than 0"));
+
+        form.refresh_interval_hours = " ".to_string();
+        let err = form.to_request().unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid refresh interval"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_form_requires_refresh_before_expiry() {
+        let mut form = FormState {
+            s3_url: "s3://bucket/key".to_string(),
+            short_url: "files.example.com".to_string(),
+            hosted_zone_id: "Z1234567890ABC".to_string(),
+            presign_duration_hours: "12".to_string(),
+            ..Default::default()
+        };
+
+        form.refresh_interval_hours = "12".to_string();
+        let err = form.to_request().unwrap_err();
+        assert!(
+            err.to_string().contains("shorter than the presign"),
+            "{}",
+            err
+        );
+
+        for ok in ["11", "6"] {
+            form.refresh_interval_hours = ok.to_string();
+            assert!(form.to_request().is_ok(), "{}", ok);
+        }
+
+        // Presigned URLs are capped at 7 days however long is asked for
+        form.presign_duration_hours = "200".to_string();
+        form.refresh_interval_hours = "180".to_string();
+        assert!(form.to_request().is_err());
+        form.refresh_interval_hours = "160".to_string();
+        assert!(form.to_request().is_ok());
+    }
+
+    #[test]
+    fn test_form_notes_field() {
+        let mut form = FormState {
+            s3_url: "s3://bucket/key".to_string(),
+            short_url: "short.example.com".to_string(),
+            hosted_zone_id: "Z1234567890ABC".to_string(),
+            current_field: 5,
+            ..Default::default()
+        };
+        assert_eq!(form.to_request().unwrap().notes, None);
+
+        for c in "Q3 launch".chars() {
+            form.push_char(c);
+        }
+        assert_eq!(
+            form.to_request().unwrap().notes.as_deref(),
+            Some("Q3 launch")
+        );
+
+        let mut mapping = Mapping::new(
+            "s3://bucket/key".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        mapping.notes = Some("remove after".to_string());
+        form.populate_from_mapping(&mapping);
+        assert_eq!(form.notes, "remove after");
+    }
+
+    #[test]
+    fn test_form_keeps_durations_that_arent_whole_hours() {
+        let mut mapping = Mapping::new(
+            "s3://bucket/key".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        mapping.presign_duration_secs = 90 * 60;
+        mapping.refresh_interval_secs = 30 * 60;
+        let mut form = FormState::default();
+        form.populate_from_mapping(&mapping);
+        assert_eq!(form.presign_duration_hours, "1");
+        assert_eq!(form.refresh_interval_hours, "0");
+
+        // Editing only the notes sends the durations back unchanged
+        form.current_field = 5;
+        form.push_char('x');
+        let request = form.to_request().unwrap();
+        assert_eq!(request.presign_duration_secs, Some(90 * 60));
+        assert_eq!(request.refresh_interval_secs, Some(30 * 60));
+
+        // A changed field is taken as whole hours
+        form.presign_duration_hours = "2".to_string();
+        let request = form.to_request().unwrap();
+        assert_eq!(request.presign_duration_secs, Some(2 * 3600));
+        assert_eq!(request.refresh_interval_secs, Some(30 * 60));
+    }
+
+    #[test]
+    fn test_no_mouse_option() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert!(TuiOptions::from_args(&args(&[]), None).mouse_capture);
+        assert!(!TuiOptions::from_args(&args(&["--no-mouse"]), None).mouse_capture);
+        assert!(!TuiOptions::from_args(&args(&[]), Some("1")).mouse_capture);
+        assert!(TuiOptions::from_args(&args(&[]), Some("false")).mouse_capture);
+    }
+}
@@ -1,27 +1,103 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Datelike, NaiveTime, Timelike, Utc, Weekday};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use crate::config::{split_version_id, Config};
+use crate::route53::{DnsRecordConfig, DnsRecordType, RecordValue, DEFAULT_TTL, MAX_TTL};
+use crate::s3::{
+    attachment_disposition, AddressingStyle, PresignMethod, PresignOptions, MAX_PRESIGN_DURATION,
+    MIN_PRESIGN_DURATION,
+};
+
 /// Represents a single S3 URL mapping
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Mapping {
     /// Unique identifier for this mapping
     pub id: Uuid,
+    /// Unique human-readable identifier, accepted in place of `id` in API
+    /// paths; derived from the short URL when not given
+    #[serde(default)]
+    pub slug: String,
     /// S3 URL (e.g., s3://bucket-name/path/to/object)
     pub s3_url: String,
+    /// S3 URLs of replicas, tried in order when `s3_url`'s object can't be
+    /// read at refresh time
+    #[serde(default)]
+    pub fallback_s3_urls: Vec<String>,
+    /// S3 URL the last successful refresh published; one of the fallbacks
+    /// after a failover
+    #[serde(default)]
+    pub serving_s3_url: Option<String>,
     /// Short URL hostname (e.g., short.example.com)
     pub short_url: String,
     /// Route53 hosted zone ID
     pub hosted_zone_id: String,
     /// Current status of the mapping
     pub status: MappingStatus,
+    /// When the mapping entered its current status
+    #[serde(default = "Utc::now")]
+    pub status_since: DateTime<Utc>,
     /// Presigned URL duration in seconds (default: 12 hours)
     #[serde(default = "default_presign_duration")]
     pub presign_duration_secs: u64,
     /// Refresh interval in seconds (default: 11 hours)
     #[serde(default = "default_refresh_interval")]
     pub refresh_interval_secs: u64,
+    /// Longest `ttl` accepted for on-demand URLs (default: the AWS maximum)
+    #[serde(default)]
+    pub max_presign_duration_secs: Option<u64>,
+    /// Named AWS credentials profile to use (default clients if unset)
+    #[serde(default)]
+    pub credentials_profile: Option<String>,
+    /// Sign presigned URLs for a requester-pays bucket
+    #[serde(default)]
+    pub requester_pays: bool,
+    /// Presign against the S3 Transfer Acceleration endpoint
+    #[serde(default)]
+    pub use_accelerate: bool,
+    /// Extra query parameters signed into the presigned URL
+    #[serde(default)]
+    pub extra_query_params: BTreeMap<String, String>,
+    /// S3 Access Point ARN to presign through instead of the bucket
+    #[serde(default)]
+    pub access_point_arn: Option<String>,
+    /// Path-style or virtual-hosted-style bucket addressing in presigned URLs
+    #[serde(default)]
+    pub addressing_style: AddressingStyle,
+    /// Upsert the Route53 record on refresh; off when DNS is managed elsewhere
+    #[serde(default = "default_manage_dns")]
+    pub manage_dns: bool,
+    /// Whether presigned URLs download or upload the object
+    #[serde(default)]
+    pub operation: Operation,
+    /// Kind of Route53 record written (default: CNAME)
+    #[serde(default)]
+    pub dns_record_type: DnsRecordType,
+    /// TTL of the Route53 record in seconds (default: 5 minutes)
+    #[serde(default = "default_dns_ttl")]
+    pub dns_ttl_secs: i64,
+    /// When set, DNS updates only happen inside this window
+    #[serde(default)]
+    pub allowed_window: Option<RefreshWindow>,
+    /// `Content-Type` forced on responses, e.g. `application/pdf` so browsers
+    /// render an object stored without one inline
+    #[serde(default)]
+    pub force_content_type: Option<String>,
+    /// Name browsers save the object as instead of the key's basename
+    #[serde(default)]
+    pub download_filename: Option<String>,
+    /// Freeform operator notes; informational only
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// S3 URL published instead of `s3_url` until the override expires
+    #[serde(default)]
+    pub override_s3_url: Option<String>,
+    /// When the override lapses and `s3_url` is published again
+    #[serde(default)]
+    pub override_expires_at: Option<DateTime<Utc>>,
     /// When this mapping was created
     pub created_at: DateTime<Utc>,
     /// When this mapping was last updated
@@ -32,8 +108,15 @@ pub struct Mapping {
     pub next_refresh: Option<DateTime<Utc>>,
     /// Last error message if any
     pub last_error: Option<String>,
+    /// Refreshes failed in a row since the last success
+    #[serde(default)]
+    pub consecutive_failures: u32,
 }
 
+/// Time a URL must stay valid after its replacement is due; smaller for
+/// presign durations under 50 minutes
+const MIN_REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
 fn default_presign_duration() -> u64 {
     12 * 60 * 60 // 12 hours
 }
@@ -42,36 +125,962 @@ fn default_refresh_interval() -> u64 {
     11 * 60 * 60 // 11 hours
 }
 
+fn default_manage_dns() -> bool {
+    true
+}
+
+fn default_dns_ttl() -> i64 {
+    DEFAULT_TTL
+}
+
 impl Mapping {
     pub fn new(s3_url: String, short_url: String, hosted_zone_id: String) -> Self {
         let now = Utc::now();
         Self {
             id: Uuid::new_v4(),
+            slug: String::new(),
             s3_url,
+            fallback_s3_urls: Vec::new(),
+            serving_s3_url: None,
             short_url,
             hosted_zone_id,
             status: MappingStatus::Pending,
+            status_since: now,
             presign_duration_secs: default_presign_duration(),
             refresh_interval_secs: default_refresh_interval(),
+            max_presign_duration_secs: None,
+            credentials_profile: None,
+            requester_pays: false,
+            use_accelerate: false,
+            extra_query_params: BTreeMap::new(),
+            access_point_arn: None,
+            addressing_style: AddressingStyle::Auto,
+            manage_dns: true,
+            operation: Operation::Get,
+            dns_record_type: DnsRecordType::Cname,
+            dns_ttl_secs: DEFAULT_TTL,
+            allowed_window: None,
+            force_content_type: None,
+            download_filename: None,
+            notes: None,
+            override_s3_url: None,
+            override_expires_at: None,
             created_at: now,
             updated_at: now,
             last_refresh: None,
             next_refresh: None,
             last_error: None,
+            consecutive_failures: 0,
+        }
+    }
+
+    /// Check the durations are usable before scheduling refreshes
+    pub fn validate_durations(&self) -> anyhow::Result<()> {
+        if self.presign_duration() < MIN_PRESIGN_DURATION {
+            anyhow::bail!(
+                "Presign duration must be at least {} second(s)",
+                MIN_PRESIGN_DURATION.as_secs()
+            );
+        }
+        if self.refresh_interval_secs == 0 {
+            anyhow::bail!("Refresh interval must be greater than 0");
+        }
+        // Leave time for a slow or retried refresh before the URL expires
+        let presign = self.effective_presign_duration();
+        let margin = (presign / 10).min(MIN_REFRESH_MARGIN);
+        if self.refresh_interval() + margin > presign {
+            anyhow::bail!(
+                "Refresh interval ({:?}) must be at least {:?} shorter than the presign duration ({:?}), or URLs expire before they are replaced",
+                self.refresh_interval(),
+                margin,
+                presign
+            );
+        }
+        if self
+            .max_presign_duration_secs
+            .is_some_and(|max| Duration::from_secs(max) < MIN_PRESIGN_DURATION)
+        {
+            anyhow::bail!(
+                "Maximum presign duration must be at least {} second(s)",
+                MIN_PRESIGN_DURATION.as_secs()
+            );
+        }
+        Ok(())
+    }
+
+    /// Check a random delay before the first refresh of up to `delay` ends
+    /// within one refresh interval, so the mapping isn't left unpublished
+    /// for longer than it would be between refreshes
+    pub fn validate_initial_delay(&self, delay: Duration) -> anyhow::Result<()> {
+        if delay > self.refresh_interval() {
+            anyhow::bail!(
+                "Initial refresh delay of {}s exceeds the refresh interval of {}s",
+                delay.as_secs(),
+                self.refresh_interval_secs
+            );
+        }
+        Ok(())
+    }
+
+    /// Check the allowed window, if any, is ever open, and never closed for
+    /// longer than a presigned URL lasts, or the published URL would expire
+    /// while its DNS update waits
+    pub fn validate_allowed_window(&self) -> anyhow::Result<()> {
+        if let Some(window) = &self.allowed_window {
+            if window.start == window.end {
+                anyhow::bail!("Allowed window must not start and end at the same time");
+            }
+            let closed = window.longest_closed_gap();
+            if closed > self.effective_presign_duration() {
+                anyhow::bail!(
+                    "Allowed window ({}) stays closed for up to {}s, longer than the presign duration of {}s",
+                    window,
+                    closed.as_secs(),
+                    self.effective_presign_duration().as_secs()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the forced content type looks like a media type
+    pub fn validate_force_content_type(&self) -> anyhow::Result<()> {
+        if let Some(content_type) = &self.force_content_type {
+            let valid = content_type
+                .split(';')
+                .next()
+                .and_then(|media_type| media_type.trim().split_once('/'))
+                .is_some_and(|(kind, subtype)| !kind.is_empty() && !subtype.is_empty())
+                && !content_type.chars().any(|c| c.is_control());
+            if !valid {
+                anyhow::bail!("Invalid content type {:?}", content_type);
+            }
+        }
+        Ok(())
+    }
+
+    /// How this mapping's Route53 record is written
+    pub fn dns_record(&self) -> DnsRecordConfig {
+        DnsRecordConfig {
+            record_type: self.dns_record_type,
+            ttl: self.dns_ttl_secs,
+        }
+    }
+
+    /// Check uploads and pinned versions name a single object, and uploads
+    /// pin no version
+    pub fn validate_operation(&self) -> anyhow::Result<()> {
+        let (s3_url, version_id) = split_version_id(&self.s3_url);
+        if self.operation == Operation::Put && version_id.is_some() {
+            anyhow::bail!("Upload mappings can't pin an object version");
+        }
+        if s3_url.ends_with('/') {
+            if self.operation == Operation::Put {
+                anyhow::bail!("Upload mappings need an object key, not a prefix");
+            }
+            if version_id.is_some() {
+                anyhow::bail!("Only an object key, not a prefix, can pin a version");
+            }
+        }
+        Ok(())
+    }
+
+    /// Check fallback S3 URLs parse and name the same kind of target, object
+    /// or prefix, as `s3_url`
+    pub fn validate_fallbacks(&self) -> anyhow::Result<()> {
+        if self.fallback_s3_urls.is_empty() {
+            return Ok(());
+        }
+        if self.operation == Operation::Put {
+            anyhow::bail!("Upload mappings can't have fallback S3 URLs");
+        }
+        let is_prefix = |s3_url: &str| split_version_id(s3_url).0.ends_with('/');
+        for fallback in &self.fallback_s3_urls {
+            if *fallback == self.s3_url {
+                anyhow::bail!("Fallback S3 URL {} repeats s3_url", fallback);
+            }
+            if is_prefix(fallback) != is_prefix(&self.s3_url) {
+                anyhow::bail!(
+                    "Fallback S3 URL {} must be a {} like s3_url",
+                    fallback,
+                    if is_prefix(&self.s3_url) {
+                        "prefix"
+                    } else {
+                        "object key"
+                    }
+                );
+            }
+            self.with_s3_url(fallback).presign_target()?;
+        }
+        Ok(())
+    }
+
+    /// Mappings to presign at refresh, in order of preference: this one, then
+    /// one per fallback S3 URL. An active override is published alone.
+    pub fn failover_targets(&self) -> Vec<Mapping> {
+        let mut targets = vec![self.clone()];
+        if self.active_override(Utc::now()).is_none() {
+            targets.extend(
+                self.fallback_s3_urls
+                    .iter()
+                    .map(|url| self.with_s3_url(url)),
+            );
+        }
+        targets
+    }
+
+    /// The target the last refresh published: the fallback named by
+    /// `serving_s3_url` after a failover, otherwise this mapping
+    pub fn serving_target(&self) -> Mapping {
+        self.failover_targets()
+            .into_iter()
+            .skip(1)
+            .find(|target| self.serving_s3_url.as_ref() == Some(&target.s3_url))
+            .unwrap_or_else(|| self.clone())
+    }
+
+    /// A copy presigning `s3_url` directly, bypassing any access point or
+    /// override
+    fn with_s3_url(&self, s3_url: &str) -> Mapping {
+        Mapping {
+            s3_url: s3_url.to_string(),
+            access_point_arn: None,
+            override_s3_url: None,
+            override_expires_at: None,
+            fallback_s3_urls: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Check the DNS TTL is one Route53 accepts
+    pub fn validate_dns_ttl(&self) -> anyhow::Result<()> {
+        if !(0..=MAX_TTL).contains(&self.dns_ttl_secs) {
+            anyhow::bail!(
+                "DNS TTL must be between 0 and {} seconds, got {}",
+                MAX_TTL,
+                self.dns_ttl_secs
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether a refresh now would have to defer its DNS update
+    pub fn dns_update_deferred(&self, now: DateTime<Utc>) -> bool {
+        self.manage_dns
+            && self
+                .allowed_window
+                .as_ref()
+                .is_some_and(|window| !window.contains(now))
+    }
+
+    /// Check the extra query parameters don't collide with ones S3 or the
+    /// signer set
+    pub fn validate_query_params(&self) -> anyhow::Result<()> {
+        for key in self.extra_query_params.keys() {
+            let lower = key.to_ascii_lowercase();
+            if key.is_empty() || lower.starts_with("x-amz-") || lower == "x-id" {
+                anyhow::bail!("Query parameter {:?} is reserved", key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the access point ARN, if any, looks like
+    /// `arn:<partition>:s3:<region>:<account>:accesspoint/<name>`
+    ///
+    /// The region is empty for Multi-Region Access Points.
+    pub fn validate_access_point(&self) -> anyhow::Result<()> {
+        let Some(arn) = &self.access_point_arn else {
+            return Ok(());
+        };
+
+        let parts: Vec<&str> = arn.splitn(6, ':').collect();
+        let valid = match parts.as_slice() {
+            ["arn", partition, "s3", _region, account, resource] => {
+                partition.starts_with("aws")
+                    && account.len() == 12
+                    && account.bytes().all(|b| b.is_ascii_digit())
+                    && resource
+                        .strip_prefix("accesspoint/")
+                        .is_some_and(|name| !name.is_empty() && !name.contains('/'))
+            }
+            _ => false,
+        };
+        if !valid {
+            anyhow::bail!(
+                "Invalid access point ARN {:?}: expected arn:aws:s3:<region>:<account>:accesspoint/<name>",
+                arn
+            );
+        }
+        Ok(())
+    }
+
+    /// The override S3 URL, unless there is none or it has expired
+    pub fn active_override(&self, now: DateTime<Utc>) -> Option<&str> {
+        self.override_expires_at
+            .filter(|expires_at| now < *expires_at)
+            .and(self.override_s3_url.as_deref())
+    }
+
+    /// Whether an override is set but has expired
+    pub fn override_expired(&self, now: DateTime<Utc>) -> bool {
+        self.override_s3_url.is_some() && self.active_override(now).is_none()
+    }
+
+    /// Drop the override, publishing `s3_url` again
+    pub fn clear_override(&mut self) {
+        self.override_s3_url = None;
+        self.override_expires_at = None;
+    }
+
+    /// Bucket (or access point ARN) and key to presign for this mapping
+    ///
+    /// An active override is presigned directly, bypassing any access point.
+    pub fn presign_target(&self) -> anyhow::Result<(String, String)> {
+        let (s3_url, access_point_arn) = match self.active_override(Utc::now()) {
+            Some(s3_url) => (s3_url, None),
+            None => (self.s3_url.as_str(), self.access_point_arn.clone()),
+        };
+        let config = Config::new(
+            s3_url.to_string(),
+            self.short_url.clone(),
+            self.hosted_zone_id.clone(),
+        )?;
+        let (bucket, key) = config.parse_s3_url()?;
+        Ok((access_point_arn.unwrap_or(bucket), key))
+    }
+
+    /// Bucket named in `s3_url`, ignoring any override or access point
+    pub fn bucket(&self) -> Option<String> {
+        let config = Config::new(
+            self.s3_url.clone(),
+            self.short_url.clone(),
+            self.hosted_zone_id.clone(),
+        )
+        .ok()?;
+        config.parse_s3_url().ok().map(|(bucket, _)| bucket)
+    }
+
+    /// Build a new mapping from a create request, using the built-in defaults
+    pub fn from_request(req: CreateMappingRequest) -> Self {
+        Self::from_request_with(req, &MappingDefaults::default())
+    }
+
+    /// Build a new mapping from a create request, filling omitted durations
+    /// from `defaults`
+    pub fn from_request_with(req: CreateMappingRequest, defaults: &MappingDefaults) -> Self {
+        let mut mapping = Self::new(req.s3_url, req.short_url, req.hosted_zone_id);
+        mapping.slug = req.slug.unwrap_or_default();
+        mapping.presign_duration_secs = req
+            .presign_duration_secs
+            .unwrap_or(defaults.presign_duration_secs);
+        mapping.refresh_interval_secs = req
+            .refresh_interval_secs
+            .unwrap_or_else(|| defaults.refresh_interval_for(mapping.presign_duration_secs));
+        mapping.max_presign_duration_secs = req.max_presign_duration_secs;
+        mapping.credentials_profile = req.credentials_profile;
+        mapping.requester_pays = req.requester_pays;
+        mapping.use_accelerate = req.use_accelerate;
+        mapping.extra_query_params = req.extra_query_params;
+        mapping.access_point_arn = req.access_point_arn;
+        mapping.addressing_style = req.addressing_style;
+        mapping.manage_dns = req.manage_dns;
+        mapping.operation = req.operation;
+        mapping.dns_record_type = req.dns_record_type;
+        mapping.dns_ttl_secs = req.dns_ttl_secs.unwrap_or(DEFAULT_TTL);
+        mapping.allowed_window = req.allowed_window;
+        mapping.force_content_type = req.force_content_type;
+        mapping.download_filename = req.download_filename;
+        mapping.fallback_s3_urls = req.fallback_s3_urls;
+        mapping.notes = req.notes.filter(|notes| !notes.trim().is_empty());
+        mapping
+    }
+
+    /// A create request reproducing this mapping's configuration, with every
+    /// duration explicit so the receiving server's defaults don't apply
+    pub fn to_create_request(&self) -> CreateMappingRequest {
+        CreateMappingRequest {
+            s3_url: self.s3_url.clone(),
+            short_url: self.short_url.clone(),
+            hosted_zone_id: self.hosted_zone_id.clone(),
+            slug: (!self.slug.is_empty()).then(|| self.slug.clone()),
+            presign_duration_secs: Some(self.presign_duration_secs),
+            refresh_interval_secs: Some(self.refresh_interval_secs),
+            max_presign_duration_secs: self.max_presign_duration_secs,
+            credentials_profile: self.credentials_profile.clone(),
+            requester_pays: self.requester_pays,
+            use_accelerate: self.use_accelerate,
+            extra_query_params: self.extra_query_params.clone(),
+            access_point_arn: self.access_point_arn.clone(),
+            addressing_style: self.addressing_style,
+            manage_dns: self.manage_dns,
+            operation: self.operation,
+            dns_record_type: self.dns_record_type,
+            dns_ttl_secs: Some(self.dns_ttl_secs),
+            allowed_window: self.allowed_window.clone(),
+            force_content_type: self.force_content_type.clone(),
+            download_filename: self.download_filename.clone(),
+            fallback_s3_urls: self.fallback_s3_urls.clone(),
+            initial_refresh_delay_secs: None,
+            skip_validation: false,
+            notes: self.notes.clone(),
+        }
+    }
+
+    /// Apply the fields set in an update request
+    pub fn apply_update(&mut self, req: UpdateMappingRequest) {
+        if let Some(s3_url) = req.s3_url {
+            self.s3_url = s3_url;
+        }
+        if let Some(short_url) = req.short_url {
+            self.short_url = short_url;
+        }
+        if let Some(slug) = req.slug {
+            self.slug = slug;
+        }
+        if let Some(hosted_zone_id) = req.hosted_zone_id {
+            self.hosted_zone_id = hosted_zone_id;
+        }
+        if let Some(presign_duration_secs) = req.presign_duration_secs {
+            self.presign_duration_secs = presign_duration_secs;
+        }
+        if let Some(refresh_interval_secs) = req.refresh_interval_secs {
+            self.refresh_interval_secs = refresh_interval_secs;
+        }
+        if let Some(max_presign_duration_secs) = req.max_presign_duration_secs {
+            self.max_presign_duration_secs = Some(max_presign_duration_secs);
+        }
+        if let Some(credentials_profile) = req.credentials_profile {
+            self.credentials_profile = Some(credentials_profile);
+        }
+        if let Some(requester_pays) = req.requester_pays {
+            self.requester_pays = requester_pays;
+        }
+        if let Some(use_accelerate) = req.use_accelerate {
+            self.use_accelerate = use_accelerate;
+        }
+        if let Some(extra_query_params) = req.extra_query_params {
+            self.extra_query_params = extra_query_params;
+        }
+        if let Some(access_point_arn) = req.access_point_arn {
+            self.access_point_arn = Some(access_point_arn);
+        }
+        if let Some(addressing_style) = req.addressing_style {
+            self.addressing_style = addressing_style;
+        }
+        if let Some(manage_dns) = req.manage_dns {
+            self.manage_dns = manage_dns;
+        }
+        if let Some(operation) = req.operation {
+            self.operation = operation;
+        }
+        if let Some(dns_record_type) = req.dns_record_type {
+            self.dns_record_type = dns_record_type;
+        }
+        if let Some(dns_ttl_secs) = req.dns_ttl_secs {
+            self.dns_ttl_secs = dns_ttl_secs;
+        }
+        if let Some(allowed_window) = req.allowed_window {
+            self.allowed_window = allowed_window;
+        }
+        if let Some(force_content_type) = req.force_content_type {
+            self.force_content_type = force_content_type;
+        }
+        if let Some(download_filename) = req.download_filename {
+            self.download_filename = download_filename;
+        }
+        if let Some(fallback_s3_urls) = req.fallback_s3_urls {
+            self.fallback_s3_urls = fallback_s3_urls;
+        }
+        // Blank notes clear them
+        if let Some(notes) = req.notes {
+            self.notes = Some(notes).filter(|notes| !notes.trim().is_empty());
+        }
+    }
+
+    /// Compare the user-editable fields of this mapping against `proposed`
+    pub fn diff(&self, proposed: &Mapping) -> MappingDiff {
+        let mut changes = Vec::new();
+        let mut compare = |field: &str, current: serde_json::Value, new: serde_json::Value| {
+            if current != new {
+                changes.push(FieldChange {
+                    field: field.to_string(),
+                    current,
+                    proposed: new,
+                });
+            }
+        };
+
+        compare(
+            "s3_url",
+            self.s3_url.clone().into(),
+            proposed.s3_url.clone().into(),
+        );
+        compare(
+            "short_url",
+            self.short_url.clone().into(),
+            proposed.short_url.clone().into(),
+        );
+        compare(
+            "slug",
+            self.slug.clone().into(),
+            proposed.slug.clone().into(),
+        );
+        compare(
+            "hosted_zone_id",
+            self.hosted_zone_id.clone().into(),
+            proposed.hosted_zone_id.clone().into(),
+        );
+        compare(
+            "presign_duration_secs",
+            self.presign_duration_secs.into(),
+            proposed.presign_duration_secs.into(),
+        );
+        compare(
+            "refresh_interval_secs",
+            self.refresh_interval_secs.into(),
+            proposed.refresh_interval_secs.into(),
+        );
+        compare(
+            "max_presign_duration_secs",
+            self.max_presign_duration_secs.into(),
+            proposed.max_presign_duration_secs.into(),
+        );
+        compare(
+            "credentials_profile",
+            self.credentials_profile.clone().into(),
+            proposed.credentials_profile.clone().into(),
+        );
+        compare(
+            "requester_pays",
+            self.requester_pays.into(),
+            proposed.requester_pays.into(),
+        );
+        compare(
+            "use_accelerate",
+            self.use_accelerate.into(),
+            proposed.use_accelerate.into(),
+        );
+        compare(
+            "extra_query_params",
+            serde_json::json!(self.extra_query_params),
+            serde_json::json!(proposed.extra_query_params),
+        );
+        compare(
+            "access_point_arn",
+            self.access_point_arn.clone().into(),
+            proposed.access_point_arn.clone().into(),
+        );
+        compare(
+            "addressing_style",
+            serde_json::json!(self.addressing_style),
+            serde_json::json!(proposed.addressing_style),
+        );
+        compare(
+            "manage_dns",
+            self.manage_dns.into(),
+            proposed.manage_dns.into(),
+        );
+        compare(
+            "operation",
+            serde_json::json!(self.operation),
+            serde_json::json!(proposed.operation),
+        );
+        compare(
+            "dns_record_type",
+            serde_json::json!(self.dns_record_type),
+            serde_json::json!(proposed.dns_record_type),
+        );
+        compare(
+            "dns_ttl_secs",
+            self.dns_ttl_secs.into(),
+            proposed.dns_ttl_secs.into(),
+        );
+        compare(
+            "allowed_window",
+            serde_json::json!(self.allowed_window),
+            serde_json::json!(proposed.allowed_window),
+        );
+        compare(
+            "force_content_type",
+            self.force_content_type.clone().into(),
+            proposed.force_content_type.clone().into(),
+        );
+        compare(
+            "download_filename",
+            self.download_filename.clone().into(),
+            proposed.download_filename.clone().into(),
+        );
+        compare(
+            "fallback_s3_urls",
+            self.fallback_s3_urls.clone().into(),
+            proposed.fallback_s3_urls.clone().into(),
+        );
+        compare(
+            "notes",
+            self.notes.clone().into(),
+            proposed.notes.clone().into(),
+        );
+
+        // Updates stop and restart the refresh task of an active mapping,
+        // unless they only touch the informational notes
+        let restart_required = changes.iter().any(|change| change.field != "notes")
+            && self.status == MappingStatus::Active;
+
+        MappingDiff {
+            mapping_id: self.id,
+            changes,
+            restart_required,
+        }
+    }
+
+    /// Serialized field names, accepted by `GET /mappings?fields=`
+    pub const FIELD_NAMES: &'static [&'static str] = &[
+        "id",
+        "slug",
+        "s3_url",
+        "fallback_s3_urls",
+        "serving_s3_url",
+        "short_url",
+        "hosted_zone_id",
+        "status",
+        "status_since",
+        "presign_duration_secs",
+        "refresh_interval_secs",
+        "max_presign_duration_secs",
+        "credentials_profile",
+        "requester_pays",
+        "use_accelerate",
+        "extra_query_params",
+        "access_point_arn",
+        "addressing_style",
+        "manage_dns",
+        "operation",
+        "dns_record_type",
+        "dns_ttl_secs",
+        "allowed_window",
+        "force_content_type",
+        "download_filename",
+        "notes",
+        "override_s3_url",
+        "override_expires_at",
+        "created_at",
+        "updated_at",
+        "last_refresh",
+        "next_refresh",
+        "last_error",
+        "consecutive_failures",
+    ];
+
+    /// Serialize only the named fields
+    pub fn project(&self, fields: &[&str]) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(object) = value.as_object_mut() {
+            object.retain(|key, _| fields.contains(&key.as_str()));
+        }
+        value
+    }
+
+    /// Column names matching `to_csv_row`
+    pub const CSV_HEADER: &'static str =
+        "id,s3_url,short_url,status,presign_duration_secs,last_refresh";
+
+    /// Render this mapping as a single CSV row (without trailing newline)
+    pub fn to_csv_row(&self) -> String {
+        let last_refresh = self
+            .last_refresh
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default();
+
+        [
+            self.id.to_string(),
+            csv_escape(&self.s3_url),
+            csv_escape(&self.short_url),
+            self.status.to_string(),
+            self.presign_duration_secs.to_string(),
+            last_refresh,
+        ]
+        .join(",")
+    }
+
+    /// Change the status, restarting `status_since` only on an actual transition
+    pub fn set_status(&mut self, status: MappingStatus) {
+        if self.status != status {
+            self.status = status;
+            self.status_since = Utc::now();
+        }
+    }
+
+    /// How long the mapping has been in its current status
+    pub fn status_duration(&self, now: DateTime<Utc>) -> Duration {
+        (now - self.status_since).to_std().unwrap_or_default()
+    }
+
+    /// Health gauge and time in the current status, for uptime panels
+    pub fn uptime(&self, now: DateTime<Utc>) -> MappingUptime {
+        MappingUptime {
+            mapping_id: self.id,
+            short_url: self.short_url.clone(),
+            status: self.status,
+            healthy: self.status == MappingStatus::Active,
+            status_since: self.status_since,
+            status_secs: self.status_duration(now).as_secs(),
         }
     }
 
+    /// Health from 0 (worst) to 100 (healthy), combining consecutive
+    /// failures, the last error and how overdue the next refresh is
+    pub fn health_score(&self, now: DateTime<Utc>) -> u8 {
+        let mut score: i64 = 100;
+
+        // Up to 60 points for repeated failures
+        score -= i64::from(self.consecutive_failures.min(5)) * 12;
+        if self.last_error.is_some() {
+            score -= 10;
+        }
+
+        // Up to 30 points for staleness, reached at twice the refresh interval
+        if self.status != MappingStatus::Paused {
+            let interval = self.refresh_interval_secs.max(1) as i64;
+            let since = self.last_refresh.unwrap_or(self.created_at);
+            let overdue = (now - since).num_seconds() - interval;
+            if overdue > 0 {
+                score -= (overdue * 30 / interval).min(30);
+            }
+        }
+
+        score.clamp(0, 100) as u8
+    }
+
     pub fn presign_duration(&self) -> Duration {
         Duration::from_secs(self.presign_duration_secs)
     }
 
+    /// Options applied when presigning this mapping's object
+    ///
+    /// Uploads must be sent with the forced content type, if any.
+    pub fn presign_options(&self) -> PresignOptions {
+        let s3_url = self.active_override(Utc::now()).unwrap_or(&self.s3_url);
+        let options = PresignOptions {
+            method: self.operation.presign_method(),
+            version_id: split_version_id(s3_url).1.map(str::to_string),
+            requester_pays: self.requester_pays,
+            accelerate: self.use_accelerate,
+            extra_query_params: self.extra_query_params.clone(),
+            addressing_style: self.addressing_style,
+            ..Default::default()
+        };
+        match self.operation {
+            Operation::Get => PresignOptions {
+                response_content_type: self.force_content_type.clone(),
+                response_content_disposition: self
+                    .download_filename
+                    .as_deref()
+                    .and_then(attachment_disposition),
+                ..options
+            },
+            Operation::Put => PresignOptions {
+                content_type: self.force_content_type.clone(),
+                ..options
+            },
+        }
+    }
+
+    /// Cap the presign duration at the AWS maximum, returning whether it was
+    /// above it
+    pub fn clamp_presign_duration(&mut self) -> bool {
+        let max = MAX_PRESIGN_DURATION.as_secs();
+        if self.presign_duration_secs <= max {
+            return false;
+        }
+        self.presign_duration_secs = max;
+        true
+    }
+
+    /// Presign duration actually used, clamped to the AWS maximum
+    pub fn effective_presign_duration(&self) -> Duration {
+        self.presign_duration().min(MAX_PRESIGN_DURATION)
+    }
+
+    /// Longest on-demand URL lifetime, clamped to the AWS maximum
+    pub fn max_presign_duration(&self) -> Duration {
+        self.max_presign_duration_secs
+            .map_or(MAX_PRESIGN_DURATION, Duration::from_secs)
+            .min(MAX_PRESIGN_DURATION)
+    }
+
+    /// Lifetime of an on-demand URL: the requested `ttl` clamped to the
+    /// mapping's maximum, or the mapping's own presign duration
+    pub fn presign_duration_for(&self, ttl: Option<u64>) -> Duration {
+        match ttl {
+            Some(ttl) => Duration::from_secs(ttl)
+                .min(self.max_presign_duration())
+                .max(MIN_PRESIGN_DURATION),
+            None => self.effective_presign_duration(),
+        }
+    }
+
     pub fn refresh_interval(&self) -> Duration {
         Duration::from_secs(self.refresh_interval_secs)
     }
 }
 
+/// Quote a CSV field if it contains a delimiter, quote or newline
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Check a short URL is a bare hostname like `files.example.com`, which is
+/// what browsers send as the `Host` header: no scheme, port or path, and only
+/// letters, digits and hyphens in each label
+pub fn validate_short_url(short_url: &str) -> anyhow::Result<()> {
+    let trimmed = short_url.trim();
+    let host = trimmed.strip_suffix('.').unwrap_or(trimmed);
+
+    let problem = if host.is_empty() {
+        Some("it is empty")
+    } else if host.contains("://") {
+        Some("remove the scheme")
+    } else if host.contains('/') {
+        Some("remove the path")
+    } else if host.contains(':') {
+        Some("remove the port")
+    } else if host.len() > 253 {
+        Some("it is longer than 253 characters")
+    } else if host
+        .split('.')
+        .any(|label| label.is_empty() || label.len() > 63)
+    {
+        Some("each label must be 1 to 63 characters")
+    } else if host
+        .split('.')
+        .any(|label| label.starts_with('-') || label.ends_with('-'))
+    {
+        Some("labels must not start or end with a hyphen")
+    } else if !host
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+    {
+        Some("only letters, digits, hyphens and dots are allowed")
+    } else {
+        None
+    };
+    if let Some(problem) = problem {
+        anyhow::bail!(
+            "Invalid short URL {:?}: expected a hostname like files.example.com ({})",
+            short_url,
+            problem
+        );
+    }
+    Ok(())
+}
+
+/// Short URL lowercased and without a trailing dot, after checking it with
+/// [`validate_short_url`]
+pub fn normalize_short_url(short_url: &str) -> anyhow::Result<String> {
+    validate_short_url(short_url)?;
+    let trimmed = short_url.trim();
+    Ok(trimmed
+        .strip_suffix('.')
+        .unwrap_or(trimmed)
+        .to_ascii_lowercase())
+}
+
+/// Path segments under `/mappings/` that are routes of their own, so can't
+/// be slugs
+const RESERVED_SLUGS: &[&str] = &[
+    "by-slug",
+    "deleted",
+    "grouped",
+    "health",
+    "pause-all",
+    "resume-all",
+    "validate",
+];
+
+/// Longest slug accepted, the length of a DNS label
+pub const MAX_SLUG_LENGTH: usize = 63;
+
+/// Check a slug is lowercase letters, digits and single inner hyphens, and
+/// can't be mistaken for a mapping ID or another route
+pub fn validate_slug(slug: &str) -> anyhow::Result<()> {
+    let problem = if slug.is_empty() || slug.len() > MAX_SLUG_LENGTH {
+        Some("it must be 1 to 63 characters")
+    } else if !slug
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+    {
+        Some("only lowercase letters, digits and hyphens are allowed")
+    } else if slug.starts_with('-') || slug.ends_with('-') || slug.contains("--") {
+        Some("hyphens must separate letters or digits")
+    } else if Uuid::parse_str(slug).is_ok() {
+        Some("it must not look like a mapping ID")
+    } else if RESERVED_SLUGS.contains(&slug) {
+        Some("it is a reserved path")
+    } else {
+        None
+    };
+    if let Some(problem) = problem {
+        anyhow::bail!(
+            "Invalid slug {:?}: expected something like files-example-com ({})",
+            slug,
+            problem
+        );
+    }
+    Ok(())
+}
+
+/// Slug for a short URL: lowercased, with each run of other characters
+/// (such as dots) turned into one hyphen, e.g. `files-example-com`
+pub fn derive_slug(short_url: &str) -> String {
+    let mut slug = String::new();
+    for c in short_url.trim().to_ascii_lowercase().chars() {
+        if c.is_ascii_lowercase() || c.is_ascii_digit() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(MAX_SLUG_LENGTH);
+    slug.trim_end_matches('-').to_string()
+}
+
+/// What the short URL lets its users do with the object
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    /// Download it
+    #[default]
+    Get,
+    /// Upload it, e.g. for collaborators sending in files
+    Put,
+}
+
+impl Operation {
+    /// Method presigned URLs of the mapping are signed for
+    pub fn presign_method(self) -> PresignMethod {
+        match self {
+            Operation::Get => PresignMethod::Get,
+            Operation::Put => PresignMethod::Put,
+        }
+    }
+
+    /// Whether a URL signed for `method` may be handed out for this
+    /// operation: download mappings also allow HEAD, uploads only PUT
+    pub fn allows(self, method: PresignMethod) -> bool {
+        match self {
+            Operation::Get => matches!(method, PresignMethod::Get | PresignMethod::Head),
+            Operation::Put => method == PresignMethod::Put,
+        }
+    }
+}
+
 /// Status of a mapping
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 pub enum MappingStatus {
     /// Waiting to be started
     Pending,
@@ -81,6 +1090,31 @@ pub enum MappingStatus {
     Paused,
     /// Error state
     Error,
+    /// Failed validation when loaded; not started until fixed and resumed
+    Quarantined,
+}
+
+impl MappingStatus {
+    /// Whether mappings in this status have a refresh task running
+    pub fn is_scheduled(&self) -> bool {
+        !matches!(self, MappingStatus::Paused | MappingStatus::Quarantined)
+    }
+}
+
+impl std::str::FromStr for MappingStatus {
+    type Err = anyhow::Error;
+
+    /// Parse a status name, ignoring case
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "pending" => Ok(MappingStatus::Pending),
+            "active" => Ok(MappingStatus::Active),
+            "paused" => Ok(MappingStatus::Paused),
+            "error" => Ok(MappingStatus::Error),
+            "quarantined" => Ok(MappingStatus::Quarantined),
+            _ => anyhow::bail!("Unknown status: {}", s),
+        }
+    }
 }
 
 impl std::fmt::Display for MappingStatus {
@@ -90,36 +1124,596 @@ impl std::fmt::Display for MappingStatus {
             MappingStatus::Active => write!(f, "Active"),
             MappingStatus::Paused => write!(f, "Paused"),
             MappingStatus::Error => write!(f, "Error"),
+            MappingStatus::Quarantined => write!(f, "Quarantined"),
         }
     }
 }
 
 /// Request to create a new mapping
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct CreateMappingRequest {
     pub s3_url: String,
     pub short_url: String,
     pub hosted_zone_id: String,
-    #[serde(default = "default_presign_duration")]
+    /// Omitted to derive one from the short URL
+    #[serde(default)]
+    pub slug: Option<String>,
+    /// Omitted to use the server's default
+    #[serde(default)]
+    pub presign_duration_secs: Option<u64>,
+    /// Omitted to use the server's default, or to derive one from the
+    /// presign duration
+    #[serde(default)]
+    pub refresh_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub max_presign_duration_secs: Option<u64>,
+    #[serde(default)]
+    pub credentials_profile: Option<String>,
+    #[serde(default)]
+    pub requester_pays: bool,
+    #[serde(default)]
+    pub use_accelerate: bool,
+    #[serde(default)]
+    pub extra_query_params: BTreeMap<String, String>,
+    #[serde(default)]
+    pub access_point_arn: Option<String>,
+    #[serde(default)]
+    pub addressing_style: AddressingStyle,
+    #[serde(default = "default_manage_dns")]
+    pub manage_dns: bool,
+    #[serde(default)]
+    pub operation: Operation,
+    #[serde(default)]
+    pub dns_record_type: DnsRecordType,
+    /// Omitted for the default of 5 minutes
+    #[serde(default)]
+    pub dns_ttl_secs: Option<i64>,
+    #[serde(default)]
+    pub allowed_window: Option<RefreshWindow>,
+    #[serde(default)]
+    pub force_content_type: Option<String>,
+    #[serde(default)]
+    pub download_filename: Option<String>,
+    /// Replicas to fail over to when `s3_url` can't be read
+    #[serde(default)]
+    pub fallback_s3_urls: Vec<String>,
+    /// Longest random delay before the first refresh, overriding the
+    /// server's setting; 0 refreshes immediately
+    #[serde(default)]
+    pub initial_refresh_delay_secs: Option<u64>,
+    /// Create the mapping without checking its S3 object exists
+    #[serde(default)]
+    pub skip_validation: bool,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl CreateMappingRequest {
+    /// How the server should add the mapping this request describes
+    pub fn add_options(&self) -> AddOptions {
+        AddOptions {
+            initial_delay: self.initial_refresh_delay_secs.map(Duration::from_secs),
+            skip_validation: self.skip_validation,
+            paused: false,
+        }
+    }
+}
+
+/// Request-only settings for adding a mapping
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AddOptions {
+    /// Longest random delay before the first refresh; the server-wide
+    /// setting if `None`
+    pub initial_delay: Option<Duration>,
+    /// Accept the mapping without checking its S3 object and hosted zone
+    /// exist
+    pub skip_validation: bool,
+    /// Add the mapping paused, without refreshing it
+    pub paused: bool,
+}
+
+/// Server-wide defaults for durations omitted from create requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingDefaults {
     pub presign_duration_secs: u64,
-    #[serde(default = "default_refresh_interval")]
-    pub refresh_interval_secs: u64,
+    /// When unset, the refresh interval is derived from the presign duration
+    pub refresh_interval_secs: Option<u64>,
+}
+
+impl Default for MappingDefaults {
+    fn default() -> Self {
+        Self {
+            presign_duration_secs: default_presign_duration(),
+            refresh_interval_secs: None,
+        }
+    }
+}
+
+impl MappingDefaults {
+    /// Defaults from `DEFAULT_PRESIGN_SECS` and `DEFAULT_REFRESH_SECS`, read
+    /// through `lookup`; unset or unparseable values keep the built-in
+    /// defaults, and the presign duration is capped at the AWS maximum
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut defaults = Self::default();
+        if let Some(secs) = lookup("DEFAULT_PRESIGN_SECS").and_then(|v| v.parse().ok()) {
+            defaults.presign_duration_secs = MAX_PRESIGN_DURATION.as_secs().min(secs);
+        }
+        defaults.refresh_interval_secs =
+            lookup("DEFAULT_REFRESH_SECS").and_then(|v| v.parse().ok());
+        defaults
+    }
+
+    /// Refresh interval for a mapping with the given presign duration: the
+    /// configured default, or 90% of the (capped) presign duration so the
+    /// URL is replaced before it expires
+    pub fn refresh_interval_for(&self, presign_duration_secs: u64) -> u64 {
+        self.refresh_interval_secs.unwrap_or_else(|| {
+            let presign = presign_duration_secs.min(MAX_PRESIGN_DURATION.as_secs());
+            (presign / 10 * 9).max(1)
+        })
+    }
 }
 
 /// Request to update an existing mapping
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct UpdateMappingRequest {
     pub s3_url: Option<String>,
     pub short_url: Option<String>,
     pub hosted_zone_id: Option<String>,
+    pub slug: Option<String>,
     pub presign_duration_secs: Option<u64>,
     pub refresh_interval_secs: Option<u64>,
+    pub max_presign_duration_secs: Option<u64>,
+    pub credentials_profile: Option<String>,
+    pub requester_pays: Option<bool>,
+    pub use_accelerate: Option<bool>,
+    pub extra_query_params: Option<BTreeMap<String, String>>,
+    pub access_point_arn: Option<String>,
+    pub addressing_style: Option<AddressingStyle>,
+    pub manage_dns: Option<bool>,
+    pub operation: Option<Operation>,
+    pub dns_record_type: Option<DnsRecordType>,
+    pub dns_ttl_secs: Option<i64>,
+    /// `null` removes the window
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<RefreshWindow>)]
+    pub allowed_window: Option<Option<RefreshWindow>>,
+    /// `null` stops overriding the content type
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>)]
+    pub force_content_type: Option<Option<String>>,
+    /// `null` goes back to the key's own filename
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>)]
+    pub download_filename: Option<Option<String>>,
+    /// Empty removes the fallbacks
+    pub fallback_s3_urls: Option<Vec<String>>,
+    /// Empty clears the notes
+    pub notes: Option<String>,
 }
 
-/// Response containing a list of mappings
+/// Read an update field that can be cleared: absent is `None`, leaving the
+/// value alone, and `null` is `Some(None)`, clearing it
+fn nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Daily UTC window in which a mapping's DNS record may change, e.g.
+/// business hours for change management
+///
+/// A window whose `end` is before its `start` runs overnight, belonging to
+/// the day it starts on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RefreshWindow {
+    /// Opening time, e.g. `"09:00"`
+    #[schema(value_type = String, example = "09:00")]
+    pub start: NaiveTime,
+    /// Closing time (exclusive), e.g. `"17:00"`
+    #[schema(value_type = String, example = "17:00")]
+    pub end: NaiveTime,
+    /// Days the window opens on, e.g. `["Mon", "Tue"]`; every day if empty
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    pub days: Vec<Weekday>,
+}
+
+impl RefreshWindow {
+    fn opens_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    /// Whether the window is open at `at`
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let time = at.time();
+        let day = at.weekday();
+        if self.start <= self.end {
+            self.opens_on(day) && self.start <= time && time < self.end
+        } else if time >= self.start {
+            self.opens_on(day)
+        } else {
+            // Early hours of an overnight window opened the day before
+            time < self.end && self.opens_on(day.pred())
+        }
+    }
+
+    /// Longest stretch the window stays closed over a week
+    pub fn longest_closed_gap(&self) -> Duration {
+        const DAY: u64 = 24 * 60 * 60;
+        const WEEK: u64 = 7 * DAY;
+        let start = u64::from(self.start.num_seconds_from_midnight());
+        let end = u64::from(self.end.num_seconds_from_midnight());
+        let open_for = (end + DAY - start) % DAY;
+        // Opening times as seconds into the week, starting on Monday
+        let week = std::iter::successors(Some(Weekday::Mon), |day| Some(day.succ()));
+        let opens: Vec<u64> = (0..7)
+            .zip(week)
+            .filter(|(_, day)| self.opens_on(*day))
+            .map(|(i, _)| i * DAY + start)
+            .collect();
+        let gap = opens
+            .iter()
+            .enumerate()
+            .map(|(i, open)| {
+                let next = opens.get(i + 1).copied().unwrap_or(opens[0] + WEEK);
+                next - open - open_for
+            })
+            .max()
+            .unwrap_or(WEEK);
+        Duration::from_secs(gap)
+    }
+
+    /// When the window next opens after `at`
+    pub fn next_open(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (0..=7)
+            .filter_map(|days| at.date_naive().checked_add_days(chrono::Days::new(days)))
+            .map(|date| date.and_time(self.start).and_utc())
+            .find(|open| *open > at && self.opens_on(open.weekday()))
+    }
+}
+
+impl std::fmt::Display for RefreshWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{} UTC",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )?;
+        if !self.days.is_empty() {
+            let days: Vec<String> = self.days.iter().map(|day| day.to_string()).collect();
+            write!(f, " on {}", days.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Mappings a bulk update applies to; at least one filter is required and
+/// all given filters must match
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkUpdateQuery {
+    /// Bucket named in the mapping's `s3_url`
+    pub bucket: Option<String>,
+    /// Route53 hosted zone ID
+    pub zone: Option<String>,
+}
+
+/// Durations set on every mapping matched by a bulk update
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkDurationsRequest {
+    pub presign_duration_secs: Option<u64>,
+    pub refresh_interval_secs: Option<u64>,
+}
+
+/// Outcome of a bulk update
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkUpdateReport {
+    /// Mappings matching the filters
+    pub matched: usize,
+    /// Mappings whose durations changed; their refresh tasks were restarted
+    pub updated: usize,
+    pub updated_ids: Vec<Uuid>,
+}
+
+/// Outcome of pausing or resuming every mapping
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkStatusReport {
+    /// Mappings whose status changed; ones already in the target status are
+    /// left alone
+    pub changed: usize,
+    pub changed_ids: Vec<Uuid>,
+}
+
+/// Request to temporarily publish another S3 object for a mapping
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetOverrideRequest {
+    pub s3_url: String,
+    /// How long the override lasts before `s3_url` is published again
+    pub duration_secs: u64,
+}
+
+/// A single field that an update would change
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub current: serde_json::Value,
+    pub proposed: serde_json::Value,
+}
+
+/// Preview of what an update would change, without applying it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappingDiff {
+    pub mapping_id: Uuid,
+    pub changes: Vec<FieldChange>,
+    /// Whether applying the update would restart the refresh task
+    pub restart_required: bool,
+}
+
+/// A deleted mapping awaiting purge, restorable until then
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedMapping {
+    pub mapping: Mapping,
+    pub deleted_at: DateTime<Utc>,
+    /// When the mapping will be permanently removed
+    pub purge_at: DateTime<Utc>,
+}
+
+/// Response containing recoverable deleted mappings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListDeletedResponse {
+    pub deleted: Vec<DeletedMapping>,
+}
+
+/// Health score of a mapping, for triage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappingHealth {
+    pub mapping_id: Uuid,
+    pub short_url: String,
+    /// 0 (worst) to 100 (healthy)
+    pub score: u8,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+/// Estimated refresh duration percentiles, in milliseconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    /// Refreshes measured
+    pub count: u64,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+}
+
+/// Refresh duration percentiles across all mappings and per mapping
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub global: LatencyPercentiles,
+    pub mappings: BTreeMap<Uuid, LatencyPercentiles>,
+}
+
+/// Whether a mapping is healthy (active) and how long it has been in its status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappingUptime {
+    pub mapping_id: Uuid,
+    pub short_url: String,
+    pub status: MappingStatus,
+    pub healthy: bool,
+    pub status_since: DateTime<Utc>,
+    pub status_secs: u64,
+}
+
+/// Scheduler counts and per-mapping uptime, served by `/stats`
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stats {
+    /// Refresh tasks currently running
+    pub active_tasks: usize,
+    /// Mappings that should have a running refresh task; a mismatch with
+    /// `active_tasks` means leaked or missing tasks (see `/admin/gc`)
+    pub active_mappings: usize,
+    pub mappings: Vec<MappingUptime>,
+}
+
+/// Whether every scheduled mapping has refreshed successfully at least once
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Readiness {
+    pub ready: bool,
+    /// Scheduled mappings still waiting for their first successful refresh
+    pub waiting: Vec<Uuid>,
+}
+
+/// Whether the server can reach S3 and Route53
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AwsReadiness {
+    pub ready: bool,
+    pub s3: AccessCheck,
+    pub route53: AccessCheck,
+}
+
+/// Outcome of probing one AWS service
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccessCheck {
+    pub ok: bool,
+    /// Why the probe failed or timed out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub latency_ms: u64,
+}
+
+/// A hosted zone referenced by mappings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneUsage {
+    pub hosted_zone_id: String,
+    pub mapping_count: usize,
+    /// Zone domain name, when resolved via Route53
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Query parameters for listing hosted zones
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ZonesQuery {
+    /// Look up each zone's name in Route53
+    #[serde(default)]
+    pub resolve: bool,
+}
+
+/// Every mapping's configuration, without runtime state such as
+/// `last_refresh` or `last_error`, for moving mappings between servers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappingExport {
+    #[serde(default = "Utc::now")]
+    pub exported_at: DateTime<Utc>,
+    /// Sorted by short URL
+    pub mappings: Vec<ExportedMapping>,
+}
+
+/// One exported mapping: the request that recreates it, and whether it's paused
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedMapping {
+    #[serde(flatten)]
+    pub request: CreateMappingRequest,
+    #[serde(default)]
+    pub paused: bool,
+}
+
+/// How an import treats the mappings already on the server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Keep them; imported short URLs must not collide with theirs
+    #[default]
+    Merge,
+    /// Delete them (into the recycle bin) before creating the imported ones
+    Replace,
+}
+
+/// Query parameters for importing mappings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+/// Outcome of an import, entry by entry in document order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// False when some entry failed validation, in which case nothing was
+    /// changed
+    pub applied: bool,
+    /// Existing mappings deleted by a `replace` import
+    pub deleted: usize,
+    pub created: usize,
+    pub results: Vec<ImportResult>,
+}
+
+/// Outcome of importing one entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub short_url: String,
+    /// ID of the created mapping
+    pub id: Option<Uuid>,
+    pub error: Option<String>,
+}
+
+/// Final state of a mapping, logged when the server stops
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappingSummary {
+    pub id: Uuid,
+    pub short_url: String,
+    pub status: MappingStatus,
+    pub last_error: Option<String>,
+}
+
+/// Outcome of a dry run of a prospective mapping
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub ok: bool,
+    /// The S3 object (or prefix) exists and is readable
+    #[serde(default)]
+    pub s3_ok: bool,
+    /// A presigned URL could be generated
+    #[serde(default)]
+    pub presign_ok: bool,
+    /// A presigned URL generated during the dry run
+    pub url_sample: Option<String>,
+    /// Host of `url_sample`, which the short URL's record would point at
+    #[serde(default)]
+    pub url_sample_host: Option<String>,
+    pub errors: Vec<String>,
+}
+
+/// Response containing a list of mappings
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ListMappingsResponse {
     pub mappings: Vec<Mapping>,
+    /// Cursor for the next page, if more mappings remain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// `offset` for the next page when paging by offset, if more remain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+    /// Mappings matching the filter across all pages
+    #[serde(default)]
+    pub total: usize,
+}
+
+/// Query parameters for listing mappings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListMappingsQuery {
+    /// Comma-separated field names to include in each mapping
+    pub fields: Option<String>,
+    /// Page size; all mappings are returned if unset
+    pub limit: Option<usize>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    /// Mappings to skip, ordered by creation; can't be combined with `cursor`
+    pub offset: Option<usize>,
+    /// Only mappings with this status; quarantined mappings are only listed
+    /// when asked for
+    pub status: Option<String>,
+}
+
+/// Which mappings to list, and which page of them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListFilter {
+    /// Only mappings with this status; all but quarantined ones if unset
+    pub status: Option<MappingStatus>,
+    /// Page size; all matching mappings if unset
+    pub limit: Option<usize>,
+    /// Skip this many mappings ordered by creation, then ID
+    pub offset: Option<usize>,
+    /// Continue after this cursor, ordered by last update, then ID
+    pub cursor: Option<String>,
+}
+
+/// Machine-readable outcome of a refresh operation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RefreshEvent {
+    /// Presigned URL generated and DNS updated
+    Success,
+    /// The presigned URL could not be generated
+    PresignFailed,
+    /// The Route53 update failed
+    DnsFailed,
+    /// Outside the mapping's allowed window; the DNS update waits for it to open
+    Deferred,
 }
 
 /// Log entry for refresh operations
@@ -128,5 +1722,719 @@ pub struct RefreshLog {
     pub mapping_id: Uuid,
     pub timestamp: DateTime<Utc>,
     pub success: bool,
+    /// Structured outcome for programmatic consumers
+    pub event: RefreshEvent,
+    /// Human-readable description
     pub message: String,
 }
+
+/// Fully-resolved values a mapping currently runs with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveConfig {
+    pub mapping_id: Uuid,
+    /// Presign duration after clamping to the AWS maximum
+    pub presign_duration_secs: u64,
+    pub refresh_interval_secs: u64,
+    /// TTL written to the Route53 record
+    pub dns_ttl_secs: i64,
+    pub credentials_profile: Option<String>,
+    /// Region the S3 client signs for
+    pub region: Option<String>,
+}
+
+/// Filters for querying refresh logs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogQuery {
+    /// Maximum number of entries to return (most recent first are kept)
+    pub limit: Option<usize>,
+    /// Only entries at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only successful (or only failed) refreshes
+    pub success: Option<bool>,
+    /// `next_cursor` from the previous page; pages walk back in time
+    pub cursor: Option<String>,
+}
+
+/// Response containing a list of refresh logs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListLogsResponse {
+    pub logs: Vec<RefreshLog>,
+    /// Cursor for the page of older entries, if any remain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Kind of change recorded in the audit trail
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    Pause,
+    Resume,
+}
+
+/// One change to a mapping, as recorded in the audit trail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub action: AuditAction,
+    pub mapping_id: Uuid,
+    /// Identity that made the change, when requests are authenticated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    /// Status before the change (`None` when the mapping didn't exist)
+    pub before: Option<MappingStatus>,
+    /// Status after the change (`None` when the mapping was deleted)
+    pub after: Option<MappingStatus>,
+}
+
+/// Filters for querying the audit trail
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditQuery {
+    /// Only entries for this mapping
+    pub mapping_id: Option<Uuid>,
+    /// Maximum number of entries to return (most recent are kept)
+    pub limit: Option<usize>,
+}
+
+/// Outcome of a Route53 reconciliation pass
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconcileReport {
+    /// Active mappings whose records were checked
+    pub checked: usize,
+    /// Records that had drifted and were re-upserted
+    pub fixed: usize,
+    /// Mappings that could not be checked or fixed
+    pub failed: usize,
+}
+
+/// Live Route53 record of a mapping's short URL, compared with its expected target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsStatus {
+    pub mapping_id: Uuid,
+    pub short_url: String,
+    /// Record as Route53 returns it, if any
+    pub record: Option<RecordValue>,
+    /// Hostname the record should point at
+    pub expected: String,
+    /// Whether the record is a CNAME pointing at `expected`
+    pub matches: bool,
+}
+
+/// Mappings grouped by status, each group sorted by short URL
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GroupedMappings {
+    pub active: Vec<Mapping>,
+    pub paused: Vec<Mapping>,
+    pub error: Vec<Mapping>,
+    pub pending: Vec<Mapping>,
+    pub quarantined: Vec<Mapping>,
+}
+
+/// Query for an on-demand presigned URL
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PresignQuery {
+    #[serde(default)]
+    pub method: PresignMethod,
+    /// Lifetime in seconds instead of the mapping's presign duration, capped
+    /// at its maximum
+    #[serde(default)]
+    pub ttl: Option<u64>,
+    /// Download filename instead of the mapping's `download_filename`
+    #[serde(default)]
+    pub download: Option<String>,
+}
+
+/// An on-demand presigned URL for a mapping's object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresignedUrl {
+    pub method: PresignMethod,
+    pub url: String,
+    /// Lifetime the URL was signed for
+    pub expires_in_secs: u64,
+}
+
+/// Result of a manual refresh request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManualRefresh {
+    /// False when skipped because the mapping had just been refreshed
+    pub refreshed: bool,
+    /// The mapping after the refresh (or as of the recent one)
+    pub mapping: Mapping,
+}
+
+/// Server details shown to clients
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerInfo {
+    /// Message of the day, e.g. which environment this server manages
+    #[serde(default)]
+    pub motd: Option<String>,
+}
+
+/// Which build of the server is running
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    /// Abbreviated commit the server was built from, or `unknown`
+    pub git_sha: String,
+    pub build_timestamp: Option<DateTime<Utc>>,
+    pub rustc_version: String,
+}
+
+impl BuildInfo {
+    /// Details embedded at compile time by the build script
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("S3_BUDDY_GIT_SHA").to_string(),
+            build_timestamp: env!("S3_BUDDY_BUILD_TIMESTAMP")
+                .parse()
+                .ok()
+                .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+            rustc_version: env!("S3_BUDDY_RUSTC_VERSION").to_string(),
+        }
+    }
+}
+
+/// Outcome of loading a batch of mappings at startup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadReport {
+    /// Mappings that passed validation and were stored
+    pub loaded: usize,
+    /// Mappings that failed validation and were quarantined
+    pub quarantined: usize,
+}
+
+/// Outcome of reconciling refresh tasks with mappings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GcReport {
+    /// Tasks aborted because no unpaused mapping owned them
+    pub aborted: usize,
+    /// Tasks started for unpaused mappings that had none running
+    pub started: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping() -> Mapping {
+        let mut mapping = Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        mapping.status = MappingStatus::Active;
+        mapping.refresh_interval_secs = 3600;
+        mapping
+    }
+
+    fn create_request(presign: Option<u64>, refresh: Option<u64>) -> CreateMappingRequest {
+        CreateMappingRequest {
+            s3_url: "s3://my-bucket/file.pdf".to_string(),
+            short_url: "short.example.com".to_string(),
+            hosted_zone_id: "Z1234567890ABC".to_string(),
+            slug: None,
+            presign_duration_secs: presign,
+            refresh_interval_secs: refresh,
+            max_presign_duration_secs: None,
+            credentials_profile: None,
+            requester_pays: false,
+            use_accelerate: false,
+            extra_query_params: BTreeMap::new(),
+            access_point_arn: None,
+            addressing_style: AddressingStyle::Auto,
+            manage_dns: true,
+            operation: Default::default(),
+            dns_record_type: Default::default(),
+            dns_ttl_secs: None,
+            allowed_window: None,
+            force_content_type: None,
+            download_filename: None,
+            fallback_s3_urls: Vec::new(),
+            initial_refresh_delay_secs: None,
+            skip_validation: false,
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_omitted_durations_use_defaults() {
+        // Neither given: the refresh interval is derived from the presign duration
+        let mapping = Mapping::from_request(create_request(None, None));
+        assert_eq!(mapping.presign_duration_secs, 12 * 60 * 60);
+        assert_eq!(mapping.refresh_interval_secs, 38_880);
+
+        let mapping = Mapping::from_request(create_request(Some(3600), None));
+        assert_eq!(mapping.refresh_interval_secs, 3240);
+
+        // Server defaults fill in omitted fields
+        let defaults = MappingDefaults {
+            presign_duration_secs: 7200,
+            refresh_interval_secs: Some(1800),
+        };
+        let mapping = Mapping::from_request_with(create_request(None, None), &defaults);
+        assert_eq!(mapping.presign_duration_secs, 7200);
+        assert_eq!(mapping.refresh_interval_secs, 1800);
+    }
+
+    #[test]
+    fn test_defaults_from_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert_eq!(
+            MappingDefaults::from_lookup(env(&[])),
+            MappingDefaults::default()
+        );
+
+        let defaults = MappingDefaults::from_lookup(env(&[
+            ("DEFAULT_PRESIGN_SECS", "3600"),
+            ("DEFAULT_REFRESH_SECS", "1800"),
+        ]));
+        assert_eq!(defaults.presign_duration_secs, 3600);
+        assert_eq!(defaults.refresh_interval_secs, Some(1800));
+        let mapping = Mapping::from_request_with(create_request(None, None), &defaults);
+        assert_eq!(mapping.presign_duration_secs, 3600);
+
+        // Capped at the AWS maximum; garbage is ignored
+        let defaults = MappingDefaults::from_lookup(env(&[("DEFAULT_PRESIGN_SECS", "2592000")]));
+        assert_eq!(
+            defaults.presign_duration_secs,
+            MAX_PRESIGN_DURATION.as_secs()
+        );
+        let defaults = MappingDefaults::from_lookup(env(&[("DEFAULT_PRESIGN_SECS", "soon")]));
+        assert_eq!(defaults, MappingDefaults::default());
+    }
+
+    #[test]
+    fn test_explicit_durations_override_defaults() {
+        let defaults = MappingDefaults {
+            presign_duration_secs: 7200,
+            refresh_interval_secs: Some(1800),
+        };
+        let mapping =
+            Mapping::from_request_with(create_request(Some(86_400), Some(600)), &defaults);
+        assert_eq!(mapping.presign_duration_secs, 86_400);
+        assert_eq!(mapping.refresh_interval_secs, 600);
+    }
+
+    #[test]
+    fn test_refresh_interval_must_leave_a_margin() {
+        let mut mapping = mapping();
+        mapping.presign_duration_secs = 3600;
+        mapping.refresh_interval_secs = 3300;
+        assert!(mapping.validate_durations().is_ok());
+
+        for too_long in [3400, 3600, 7200] {
+            mapping.refresh_interval_secs = too_long;
+            assert!(mapping.validate_durations().is_err(), "{}", too_long);
+        }
+
+        // The margin shrinks with the presign duration
+        mapping.presign_duration_secs = 60;
+        mapping.refresh_interval_secs = 54;
+        assert!(mapping.validate_durations().is_ok());
+        mapping.refresh_interval_secs = 55;
+        assert!(mapping.validate_durations().is_err());
+    }
+
+    #[test]
+    fn test_reserved_query_params_are_rejected() {
+        let mut mapping = mapping();
+        mapping
+            .extra_query_params
+            .insert("utm_source".to_string(), "newsletter".to_string());
+        assert!(mapping.validate_query_params().is_ok());
+
+        for reserved in ["X-Amz-Signature", "x-id", ""] {
+            let mut bad = mapping.clone();
+            bad.extra_query_params
+                .insert(reserved.to_string(), "1".to_string());
+            assert!(bad.validate_query_params().is_err(), "{:?}", reserved);
+        }
+    }
+
+    #[test]
+    fn test_short_url_validation() {
+        for valid in [
+            "files.example.com",
+            "a.b-c.example.com.",
+            "localhost",
+            "x1.io",
+        ] {
+            assert!(validate_short_url(valid).is_ok(), "{}", valid);
+        }
+        assert_eq!(
+            normalize_short_url(" Files.Example.COM. ").unwrap(),
+            "files.example.com"
+        );
+
+        for invalid in [
+            "",
+            "http://files.example.com",
+            "files.example.com/path",
+            "files.example.com:8080",
+            "files..example.com",
+            "-files.example.com",
+            "files_1.example.com",
+            "files example.com",
+            "*.example.com",
+        ] {
+            assert!(validate_short_url(invalid).is_err(), "{:?}", invalid);
+        }
+        assert!(validate_short_url(&format!("{}.com", "a".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn test_slugs() {
+        assert_eq!(derive_slug("files.example.com"), "files-example-com");
+        assert_eq!(
+            derive_slug(" Files--1.Example.COM. "),
+            "files-1-example-com"
+        );
+        // Cut to length without leaving a trailing hyphen
+        assert_eq!(derive_slug(&"ab.".repeat(30)).len(), MAX_SLUG_LENGTH - 1);
+
+        for valid in ["files-example-com", "q3-report", "x"] {
+            assert!(validate_slug(valid).is_ok(), "{}", valid);
+        }
+        for invalid in [
+            "",
+            "Files",
+            "files.example.com",
+            "-files",
+            "files-",
+            "files--1",
+            "pause-all",
+            "5f0c6c3e-2b1a-4c8e-9f5d-1a2b3c4d5e6f",
+        ] {
+            assert!(validate_slug(invalid).is_err(), "{:?}", invalid);
+        }
+        assert!(validate_slug(&"a".repeat(MAX_SLUG_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_access_point_arn_validation() {
+        let mut mapping = mapping();
+        assert!(mapping.validate_access_point().is_ok());
+
+        for valid in [
+            "arn:aws:s3:us-west-2:123456789012:accesspoint/my-ap",
+            "arn:aws:s3::123456789012:accesspoint/mfzwi23gnjvgw.mrap",
+        ] {
+            mapping.access_point_arn = Some(valid.to_string());
+            assert!(mapping.validate_access_point().is_ok(), "{}", valid);
+        }
+
+        for invalid in [
+            "my-bucket",
+            "arn:aws:s3:us-west-2:12345:accesspoint/my-ap",
+            "arn:aws:s3:us-west-2:123456789012:bucket/my-ap",
+            "arn:aws:s3:us-west-2:123456789012:accesspoint/",
+        ] {
+            mapping.access_point_arn = Some(invalid.to_string());
+            assert!(mapping.validate_access_point().is_err(), "{}", invalid);
+        }
+
+        // The ARN replaces the bucket as the presign target
+        mapping.access_point_arn =
+            Some("arn:aws:s3:us-west-2:123456789012:accesspoint/my-ap".to_string());
+        let (target, key) = mapping.presign_target().unwrap();
+        assert_eq!(
+            target,
+            "arn:aws:s3:us-west-2:123456789012:accesspoint/my-ap"
+        );
+        assert_eq!(key, "file.pdf");
+    }
+
+    #[test]
+    fn test_health_score() {
+        let now = Utc::now();
+
+        // Freshly refreshed, no failures
+        let mut healthy = mapping();
+        healthy.last_refresh = Some(now);
+        assert_eq!(healthy.health_score(now), 100);
+
+        // Failures and an error lower the score, capped at five failures
+        let mut failing = healthy.clone();
+        failing.consecutive_failures = 2;
+        failing.last_error = Some("AccessDenied".to_string());
+        assert_eq!(failing.health_score(now), 66);
+        failing.consecutive_failures = 50;
+        assert_eq!(failing.health_score(now), 30);
+
+        // Staleness counts once the refresh interval has passed
+        let mut stale = healthy.clone();
+        stale.last_refresh = Some(now - chrono::Duration::minutes(90));
+        assert_eq!(stale.health_score(now), 85);
+        stale.last_refresh = Some(now - chrono::Duration::hours(5));
+        assert_eq!(stale.health_score(now), 70);
+
+        // Paused mappings are not expected to refresh
+        stale.status = MappingStatus::Paused;
+        assert_eq!(stale.health_score(now), 100);
+
+        // Everything wrong at once bottoms out at zero
+        failing.last_refresh = Some(now - chrono::Duration::days(1));
+        assert_eq!(failing.health_score(now), 0);
+    }
+
+    #[test]
+    fn test_on_demand_ttl_is_clamped() {
+        let mut mapping = mapping();
+        mapping.presign_duration_secs = 3600;
+        mapping.max_presign_duration_secs = Some(24 * 3600);
+
+        // No ttl: the mapping's own duration
+        assert_eq!(
+            mapping.presign_duration_for(None),
+            Duration::from_secs(3600)
+        );
+
+        // Within the maximum: used as is
+        assert_eq!(
+            mapping.presign_duration_for(Some(600)),
+            Duration::from_secs(600)
+        );
+
+        // Beyond it: clamped
+        assert_eq!(
+            mapping.presign_duration_for(Some(30 * 24 * 3600)),
+            Duration::from_secs(24 * 3600)
+        );
+
+        // Without a maximum, the AWS limit applies
+        mapping.max_presign_duration_secs = None;
+        assert_eq!(
+            mapping.presign_duration_for(Some(30 * 24 * 3600)),
+            MAX_PRESIGN_DURATION
+        );
+        assert_eq!(mapping.presign_duration_for(Some(0)), MIN_PRESIGN_DURATION);
+    }
+
+    #[test]
+    fn test_status_change_resets_status_since() {
+        let mut mapping = mapping();
+        let since = Utc::now() - chrono::Duration::hours(2);
+        mapping.status_since = since;
+        let now = Utc::now();
+        assert!(mapping.status_duration(now) >= Duration::from_secs(7200));
+        assert!(mapping.uptime(now).healthy);
+
+        // Setting the same status is not a transition
+        mapping.set_status(MappingStatus::Active);
+        assert_eq!(mapping.status_since, since);
+
+        mapping.set_status(MappingStatus::Error);
+        assert!(mapping.status_since > since);
+        let uptime = mapping.uptime(Utc::now());
+        assert!(!uptime.healthy);
+        assert!(uptime.status_secs < 5);
+    }
+
+    #[test]
+    fn test_refresh_window() {
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let window: RefreshWindow =
+            serde_json::from_str(r#"{"start": "09:00", "end": "17:00", "days": ["Mon", "Fri"]}"#)
+                .unwrap();
+        // 2024-05-06 is a Monday
+        assert!(window.contains(at("2024-05-06T09:00:00Z")));
+        assert!(!window.contains(at("2024-05-06T17:00:00Z")));
+        assert!(!window.contains(at("2024-05-07T12:00:00Z")));
+        assert_eq!(
+            window.next_open(at("2024-05-06T18:00:00Z")),
+            Some(at("2024-05-10T09:00:00Z"))
+        );
+        assert_eq!(window.to_string(), "09:00-17:00 UTC on Mon, Fri");
+        // Monday 17:00 to Friday 09:00
+        assert_eq!(
+            window.longest_closed_gap(),
+            Duration::from_secs(88 * 60 * 60)
+        );
+
+        // Overnight windows belong to the day they open on
+        let overnight: RefreshWindow =
+            serde_json::from_str(r#"{"start": "22:00", "end": "02:00", "days": ["Sun"]}"#).unwrap();
+        assert!(overnight.contains(at("2024-05-05T23:00:00Z")));
+        assert!(overnight.contains(at("2024-05-06T01:00:00Z")));
+        assert!(!overnight.contains(at("2024-05-05T01:00:00Z")));
+        assert_eq!(
+            overnight.next_open(at("2024-05-06T01:00:00Z")),
+            Some(at("2024-05-12T22:00:00Z"))
+        );
+        assert_eq!(
+            overnight.longest_closed_gap(),
+            Duration::from_secs((7 * 24 - 4) * 60 * 60)
+        );
+
+        // The published URL has to outlast the window being closed
+        let mut weekly = mapping();
+        weekly.allowed_window = Some(window.clone());
+        assert!(weekly.validate_allowed_window().is_err());
+        weekly.presign_duration_secs = 4 * 24 * 60 * 60;
+        assert!(weekly.validate_allowed_window().is_ok());
+
+        let mut mapping = mapping();
+        mapping.allowed_window = Some(window);
+        assert!(mapping.dns_update_deferred(at("2024-05-07T12:00:00Z")));
+        mapping.manage_dns = false;
+        assert!(!mapping.dns_update_deferred(at("2024-05-07T12:00:00Z")));
+
+        // An update leaves the window alone unless it sends `null`
+        let update = |json: &str| serde_json::from_str::<UpdateMappingRequest>(json).unwrap();
+        mapping.apply_update(update(r#"{"notes": "weekly"}"#));
+        assert!(mapping.allowed_window.is_some());
+        mapping.apply_update(update(r#"{"allowed_window": null}"#));
+        assert_eq!(mapping.allowed_window, None);
+        // Requests without a window don't send one
+        let json = serde_json::to_value(UpdateMappingRequest::default()).unwrap();
+        assert!(json.get("allowed_window").is_none());
+    }
+
+    #[test]
+    fn test_notes_round_trip() {
+        let mut mapping = mapping();
+        mapping.notes = Some("temporary for Q3 launch, remove after".to_string());
+
+        let json = serde_json::to_string(&mapping).unwrap();
+        let parsed: Mapping = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.notes, mapping.notes);
+
+        // Mappings stored before notes existed have none
+        let mut value = serde_json::to_value(&mapping).unwrap();
+        value.as_object_mut().unwrap().remove("notes");
+        let parsed: Mapping = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.notes, None);
+
+        // Blank notes in an update clear them
+        mapping.apply_update(UpdateMappingRequest {
+            notes: Some("  ".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(mapping.notes, None);
+    }
+
+    #[test]
+    fn test_update_can_clear_forced_content_type() {
+        let mut mapping = mapping();
+        let update = |json: &str| serde_json::from_str::<UpdateMappingRequest>(json).unwrap();
+        mapping.apply_update(update(r#"{"force_content_type": "application/pdf"}"#));
+        assert_eq!(
+            mapping.force_content_type.as_deref(),
+            Some("application/pdf")
+        );
+        mapping.apply_update(update(r#"{"notes": "pdf"}"#));
+        assert!(mapping.force_content_type.is_some());
+        mapping.apply_update(update(r#"{"force_content_type": null}"#));
+        assert_eq!(mapping.force_content_type, None);
+        assert_eq!(mapping.presign_options().response_content_type, None);
+    }
+
+    #[test]
+    fn test_update_can_clear_download_filename() {
+        let mut mapping = mapping();
+        let update = |json: &str| serde_json::from_str::<UpdateMappingRequest>(json).unwrap();
+        mapping.apply_update(update(r#"{"download_filename": "Q3 report.pdf"}"#));
+        assert!(mapping
+            .presign_options()
+            .response_content_disposition
+            .is_some());
+        mapping.apply_update(update(r#"{"notes": "report"}"#));
+        assert!(mapping.download_filename.is_some());
+        mapping.apply_update(update(r#"{"download_filename": null}"#));
+        assert_eq!(mapping.download_filename, None);
+        assert_eq!(mapping.presign_options().response_content_disposition, None);
+    }
+
+    #[test]
+    fn test_upload_mappings_presign_puts() {
+        let mut mapping = mapping();
+        mapping.force_content_type = Some("application/zip".to_string());
+        mapping.download_filename = Some("upload.zip".to_string());
+        let options = mapping.presign_options();
+        assert_eq!(options.method, PresignMethod::Get);
+        assert_eq!(options.content_type, None);
+
+        mapping.operation = Operation::Put;
+        let options = mapping.presign_options();
+        assert_eq!(options.method, PresignMethod::Put);
+        assert_eq!(options.content_type.as_deref(), Some("application/zip"));
+        assert_eq!(options.response_content_type, None);
+        assert_eq!(options.response_content_disposition, None);
+
+        assert!(Operation::Get.allows(PresignMethod::Head));
+        assert!(!Operation::Get.allows(PresignMethod::Put));
+        assert!(Operation::Put.allows(PresignMethod::Put));
+        assert!(!Operation::Put.allows(PresignMethod::Get));
+
+        assert!(mapping.validate_operation().is_ok());
+        mapping.s3_url = "s3://my-bucket/inbox/".to_string();
+        assert!(mapping.validate_operation().is_err());
+    }
+
+    #[test]
+    fn test_pinned_version_is_presigned() {
+        let mut mapping = mapping();
+        assert_eq!(mapping.presign_options().version_id, None);
+
+        mapping.s3_url = "s3://my-bucket/report.pdf?versionId=v2".to_string();
+        assert_eq!(mapping.presign_target().unwrap().1, "report.pdf");
+        assert_eq!(mapping.presign_options().version_id.as_deref(), Some("v2"));
+        assert!(mapping.validate_operation().is_ok());
+
+        mapping.operation = Operation::Put;
+        assert!(mapping.validate_operation().is_err());
+
+        mapping.operation = Operation::Get;
+        mapping.s3_url = "s3://my-bucket/reports/?versionId=v2".to_string();
+        assert!(mapping.validate_operation().is_err());
+    }
+
+    #[test]
+    fn test_failover_targets() {
+        let mut mapping = mapping();
+        mapping.access_point_arn =
+            Some("arn:aws:s3:us-east-1:123456789012:accesspoint/reports".to_string());
+        mapping.fallback_s3_urls = vec!["s3://replica-bucket/file.pdf?versionId=v1".to_string()];
+        assert!(mapping.validate_fallbacks().is_ok());
+
+        // Replicas are presigned directly, with their own version
+        let targets = mapping.failover_targets();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].s3_url, mapping.s3_url);
+        assert_eq!(
+            targets[1].presign_target().unwrap(),
+            ("replica-bucket".to_string(), "file.pdf".to_string())
+        );
+        assert_eq!(
+            targets[1].presign_options().version_id.as_deref(),
+            Some("v1")
+        );
+        assert_eq!(mapping.serving_target().s3_url, mapping.s3_url);
+        mapping.serving_s3_url = Some(mapping.fallback_s3_urls[0].clone());
+        assert_eq!(mapping.serving_target().s3_url, mapping.fallback_s3_urls[0]);
+
+        // An override is published without failover
+        mapping.override_s3_url = Some("s3://my-bucket/maintenance.html".to_string());
+        mapping.override_expires_at = Some(Utc::now() + chrono::Duration::hours(1));
+        assert_eq!(mapping.failover_targets().len(), 1);
+        assert_eq!(mapping.serving_target().s3_url, mapping.s3_url);
+
+        mapping.fallback_s3_urls = vec!["s3://replica-bucket/files/".to_string()];
+        assert!(mapping.validate_fallbacks().is_err());
+        mapping.fallback_s3_urls = vec![mapping.s3_url.clone()];
+        assert!(mapping.validate_fallbacks().is_err());
+        mapping.fallback_s3_urls = vec!["https://replica-bucket/file.pdf".to_string()];
+        assert!(mapping.validate_fallbacks().is_err());
+    }
+}
@@ -0,0 +1,2921 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use s3_buddy::notify::{
+    Notification, Notifier, Notifiers, ReqwestTransport, Transition, WebhookNotifier,
+};
+use s3_buddy::retry::mark_transient;
+use s3_buddy::route53::{DnsRecordConfig, RecordValue};
+use s3_buddy::s3::{HeadInfo, ObjectNotFound, PresignMethod, PresignOptions};
+use s3_buddy::{
+    AddOptions, AwsReadiness, BuildInfo, BulkStatusReport, BulkUpdateReport, DnsBackend, DnsStatus,
+    GroupedMappings, ImportMode, ImportReport, ListDeletedResponse, ListLogsResponse,
+    ListMappingsResponse, ManualRefresh, Mapping, MappingDiff, MappingExport, MappingManager,
+    MappingStatus, Operation, PresignedUrl, Readiness, RefreshEvent, RefreshWindow, ServerInfo,
+    Stats, StorageBackend, ValidationReport, ZoneUsage,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+/// Storage double that signs nothing and returns a predictable URL
+struct FakeStorage;
+
+#[async_trait]
+impl StorageBackend for FakeStorage {
+    async fn generate_presigned_url(
+        &self,
+        bucket: &str,
+        key: &str,
+        _duration: Duration,
+        _options: &PresignOptions,
+    ) -> Result<String> {
+        Ok(format!(
+            "https://{}.s3.amazonaws.com/{}?X-Amz-Signature=fake",
+            bucket, key
+        ))
+    }
+
+    async fn head_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        _options: &PresignOptions,
+    ) -> Result<HeadInfo> {
+        if bucket == "missing-bucket" {
+            anyhow::bail!("NoSuchBucket: {}", bucket);
+        }
+        if bucket == "throttled-bucket" {
+            return Err(mark_transient(anyhow::anyhow!("SlowDown"), true));
+        }
+        if key.starts_with("missing") {
+            return Err(ObjectNotFound {
+                s3_url: format!("s3://{}/{}", bucket, key),
+            }
+            .into());
+        }
+        Ok(HeadInfo {
+            content_length: Some(1024),
+            last_modified: None,
+        })
+    }
+
+    async fn prefix_exists(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        _options: &PresignOptions,
+    ) -> Result<bool> {
+        if bucket == "missing-bucket" {
+            anyhow::bail!("NoSuchBucket: {}", bucket);
+        }
+        Ok(!prefix.starts_with("missing"))
+    }
+}
+
+/// Storage double presigning like `FakeStorage`, optionally slowly, with
+/// failures or with short-lived credentials, and recording its presigns
+#[derive(Clone, Default)]
+struct ScriptedStorage {
+    /// Presigns that fail, numbered from 1, and their errors
+    failures: Vec<(u32, &'static str)>,
+    /// How long each presign takes
+    presign_delay: Duration,
+    /// Reported lifetime of the signing credentials
+    credentials_lifetime: Option<Duration>,
+    /// Presigns made so far
+    calls: Arc<Mutex<u32>>,
+    /// Presigns running now, and the most that ever were
+    running: Arc<Mutex<(u32, u32)>>,
+    /// `Content-Disposition` requested by each presign
+    dispositions: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+#[async_trait]
+impl StorageBackend for ScriptedStorage {
+    async fn generate_presigned_url(
+        &self,
+        bucket: &str,
+        key: &str,
+        duration: Duration,
+        options: &PresignOptions,
+    ) -> Result<String> {
+        let call = {
+            let mut calls = self.calls.lock().unwrap();
+            *calls += 1;
+            *calls
+        };
+        self.dispositions
+            .lock()
+            .unwrap()
+            .push(options.response_content_disposition.clone());
+        {
+            let mut running = self.running.lock().unwrap();
+            running.0 += 1;
+            running.1 = running.1.max(running.0);
+        }
+        if !self.presign_delay.is_zero() {
+            tokio::time::sleep(self.presign_delay).await;
+        }
+        self.running.lock().unwrap().0 -= 1;
+
+        if let Some((_, error)) = self.failures.iter().find(|(n, _)| *n == call) {
+            anyhow::bail!("{}", error);
+        }
+        FakeStorage
+            .generate_presigned_url(bucket, key, duration, options)
+            .await
+    }
+
+    async fn head_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        options: &PresignOptions,
+    ) -> Result<HeadInfo> {
+        FakeStorage.head_object(bucket, key, options).await
+    }
+
+    async fn prefix_exists(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        options: &PresignOptions,
+    ) -> Result<bool> {
+        FakeStorage.prefix_exists(bucket, prefix, options).await
+    }
+
+    async fn credentials_lifetime(&self) -> Option<Duration> {
+        self.credentials_lifetime
+    }
+}
+
+/// Fails its first presign, then succeeds
+fn flaky_storage() -> ScriptedStorage {
+    ScriptedStorage {
+        failures: vec![(1, "AccessDenied: bucket policy still propagating")],
+        ..Default::default()
+    }
+}
+
+/// Presigns slowly
+fn slow_storage() -> ScriptedStorage {
+    ScriptedStorage {
+        presign_delay: Duration::from_millis(100),
+        ..Default::default()
+    }
+}
+
+/// DNS double that records every upsert
+#[derive(Clone, Default)]
+struct FakeDns {
+    upserts: Arc<Mutex<Vec<(String, String, String)>>>,
+    /// Zone names by ID; zones not listed are `example.com.`
+    zone_names: Arc<Mutex<std::collections::HashMap<String, Option<String>>>>,
+    /// Fail access checks, as if Route53 couldn't be reached
+    unreachable: bool,
+    /// Number of access checks made
+    access_checks: Arc<Mutex<u32>>,
+}
+
+#[async_trait]
+impl DnsBackend for FakeDns {
+    /// The latest upsert for the name, as Route53 would return it
+    async fn get_record(&self, hosted_zone_id: &str, name: &str) -> Result<Option<RecordValue>> {
+        let upserts = self.upserts.lock().unwrap();
+        let latest = upserts
+            .iter()
+            .rev()
+            .find(|(zone, short_url, _)| zone == hosted_zone_id && short_url == name);
+        Ok(latest.map(|(_, _, url)| RecordValue {
+            record_type: "CNAME".to_string(),
+            values: vec![format!("{}.", url.split('/').nth(2).unwrap_or_default())],
+            ttl: Some(300),
+        }))
+    }
+
+    async fn get_zone_name(&self, hosted_zone_id: &str) -> Result<Option<String>> {
+        if self.unreachable {
+            anyhow::bail!("Route53 unreachable");
+        }
+        Ok(self
+            .zone_names
+            .lock()
+            .unwrap()
+            .get(hosted_zone_id)
+            .cloned()
+            .unwrap_or_else(|| Some("example.com.".to_string())))
+    }
+
+    async fn update_dns_record(
+        &self,
+        hosted_zone_id: &str,
+        short_url: &str,
+        presigned_url: &str,
+        _record: DnsRecordConfig,
+        _mapping_id: Option<Uuid>,
+    ) -> Result<()> {
+        self.upserts.lock().unwrap().push((
+            hosted_zone_id.to_string(),
+            short_url.to_string(),
+            presigned_url.to_string(),
+        ));
+        Ok(())
+    }
+
+    async fn check_access(&self) -> Result<()> {
+        *self.access_checks.lock().unwrap() += 1;
+        if self.unreachable {
+            anyhow::bail!("Route53 unreachable");
+        }
+        Ok(())
+    }
+}
+
+/// Move paused time on by `by`, then let the tasks it woke run until
+/// they wait on something else
+async fn advance(by: Duration) {
+    tokio::time::advance(by).await;
+    for _ in 0..10 {
+        tokio::task::yield_now().await;
+    }
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(body: Body) -> T {
+    let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_create_refresh_delete_with_mock_backends() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    // Create
+    let request = Request::post("/mappings")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            serde_json::json!({
+                "s3_url": "s3://my-bucket/file.pdf",
+                "short_url": "short.example.com",
+                "hosted_zone_id": "Z1234567890ABC"
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let created: Mapping = body_json(response.into_body()).await;
+
+    // The initial refresh runs in the background
+    for _ in 0..50 {
+        if !dns.upserts.lock().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let upserts = dns.upserts.lock().unwrap().clone();
+    assert_eq!(
+        upserts,
+        vec![(
+            "Z1234567890ABC".to_string(),
+            "short.example.com".to_string(),
+            "https://my-bucket.s3.amazonaws.com/file.pdf?X-Amz-Signature=fake".to_string(),
+        )]
+    );
+
+    // The refresh is reflected on the mapping
+    let request = Request::get(format!("/mappings/{}", created.id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let fetched: Mapping = body_json(response.into_body()).await;
+    assert!(fetched.last_refresh.is_some());
+
+    // Delete
+    let request = Request::delete(format!("/mappings/{}", created.id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let request = Request::get(format!("/mappings/{}", created.id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_openapi_document_describes_management_api() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let request = Request::get("/openapi.json").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let doc: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+    assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+    let paths = &doc["paths"];
+    for (path, method) in [
+        ("/health", "get"),
+        ("/mappings", "get"),
+        ("/mappings", "post"),
+        ("/mappings/{id}", "get"),
+        ("/mappings/{id}", "put"),
+        ("/mappings/{id}", "delete"),
+        ("/mappings/{id}/pause", "post"),
+        ("/mappings/{id}/resume", "post"),
+    ] {
+        assert!(paths[path][method].is_object(), "{} {}", method, path);
+    }
+    let params = paths["/mappings"]["get"]["parameters"].as_array().unwrap();
+    assert!(params
+        .iter()
+        .any(|p| p["name"] == "status" && p["in"] == "query"));
+    assert_eq!(
+        paths["/mappings/{id}"]["put"]["responses"]["409"]["content"]["text/plain"]["schema"]
+            ["type"],
+        "string"
+    );
+
+    let schemas = &doc["components"]["schemas"];
+    let mapping = &schemas["Mapping"];
+    for field in ["id", "s3_url", "short_url", "status", "fallback_s3_urls"] {
+        assert!(mapping["properties"][field].is_object(), "{}", field);
+    }
+    assert_eq!(
+        schemas["MappingStatus"]["enum"],
+        serde_json::json!(["Pending", "Active", "Paused", "Error", "Quarantined"])
+    );
+}
+
+#[tokio::test]
+async fn test_list_mappings_as_csv() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let request = Request::get("/mappings")
+        .header("accept", "text/csv")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/csv");
+
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = String::from_utf8(bytes.to_vec()).unwrap();
+    let mut lines = body.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        "id,s3_url,short_url,status,presign_duration_secs,last_refresh"
+    );
+    let row = lines.next().unwrap();
+    assert!(row.starts_with(&format!(
+        "{},s3://my-bucket/file.pdf,short.example.com,",
+        id
+    )));
+}
+
+#[tokio::test]
+async fn test_logs_merged_across_mappings() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mut ids = Vec::new();
+    for short_url in ["a.example.com", "b.example.com"] {
+        let mapping = Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            short_url.to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        ids.push(manager.add_mapping(mapping).await.unwrap());
+    }
+    let manager = Arc::new(manager);
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+
+    // Wait for both initial refreshes to be logged
+    for _ in 0..50 {
+        if manager.logs(&Default::default()).await.unwrap().logs.len() >= 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let request = Request::get("/logs?success=true")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ListLogsResponse = body_json(response.into_body()).await;
+
+    assert_eq!(body.logs.len(), 2);
+    for id in &ids {
+        assert!(body.logs.iter().any(|log| log.mapping_id == *id));
+    }
+    assert!(body.logs[0].timestamp <= body.logs[1].timestamp);
+
+    // Per mapping
+    let request = Request::get(format!("/mappings/{}/logs", ids[0]))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ListLogsResponse = body_json(response.into_body()).await;
+    assert_eq!(body.logs.len(), 1);
+    assert_eq!(body.logs[0].mapping_id, ids[0]);
+
+    let request = Request::get(format!("/mappings/{}/logs", Uuid::new_v4()))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_diff_reports_changes_without_applying() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    let manager = Arc::new(manager);
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+
+    let request = Request::post(format!("/mappings/{}/diff", id))
+        .header("content-type", "application/json")
+        .body(Body::from(
+            serde_json::json!({
+                "short_url": "short.example.com",
+                "presign_duration_secs": 3600
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let diff: MappingDiff = body_json(response.into_body()).await;
+
+    // Unchanged fields are not reported
+    assert_eq!(diff.changes.len(), 1);
+    assert_eq!(diff.changes[0].field, "presign_duration_secs");
+    assert_eq!(diff.changes[0].current, serde_json::json!(12 * 60 * 60));
+    assert_eq!(diff.changes[0].proposed, serde_json::json!(3600));
+    assert!(diff.restart_required);
+
+    // Nothing was applied
+    let stored = manager.get_mapping(&id).await.unwrap();
+    assert_eq!(stored.presign_duration_secs, 12 * 60 * 60);
+}
+
+async fn validate(app: axum::Router, s3_url: &str) -> ValidationReport {
+    let request = Request::post("/mappings/validate")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            serde_json::json!({
+                "s3_url": s3_url,
+                "short_url": "short.example.com",
+                "hosted_zone_id": "Z1234567890ABC"
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response.into_body()).await
+}
+
+#[tokio::test]
+async fn test_validate_mapping_dry_run() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let manager = Arc::new(manager);
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+
+    let report = validate(app.clone(), "s3://my-bucket/file.pdf").await;
+    assert!(report.ok);
+    assert!(report.s3_ok && report.presign_ok);
+    assert!(report.errors.is_empty());
+    assert_eq!(
+        report.url_sample.as_deref(),
+        Some("https://my-bucket.s3.amazonaws.com/file.pdf?X-Amz-Signature=fake")
+    );
+    assert_eq!(
+        report.url_sample_host.as_deref(),
+        Some("my-bucket.s3.amazonaws.com")
+    );
+
+    // Presigning doesn't need the object, so the report tells them apart
+    let report = validate(app, "s3://missing-bucket/file.pdf").await;
+    assert!(!report.ok);
+    assert!(!report.s3_ok);
+    assert!(report.presign_ok);
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].contains("NoSuchBucket"));
+
+    // Nothing is stored either way
+    assert!(manager.list_mappings().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_list_mappings_field_projection() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let request = Request::get("/mappings?fields=id,short_url,status")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = body_json(response.into_body()).await;
+
+    let mapping = body["mappings"][0].as_object().unwrap();
+    let mut keys: Vec<&str> = mapping.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(keys, vec!["id", "short_url", "status"]);
+    assert_eq!(mapping["id"], serde_json::json!(id));
+
+    let request = Request::get("/mappings?fields=id,bogus")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn test_field_names_match_serialized_mapping() {
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let value = serde_json::to_value(&mapping).unwrap();
+    let mut keys: Vec<&str> = value
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    let mut names = Mapping::FIELD_NAMES.to_vec();
+    keys.sort();
+    names.sort();
+    assert_eq!(keys, names);
+}
+
+#[tokio::test]
+async fn test_mapping_cursor_visits_each_item_once_despite_deletes() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        let mapping = Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            format!("{}.example.com", i),
+            "Z1234567890ABC".to_string(),
+        );
+        ids.push(manager.add_mapping(mapping).await.unwrap());
+    }
+    let manager = Arc::new(manager);
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let uri = match &cursor {
+            Some(cursor) => format!("/mappings?limit=2&cursor={}", cursor),
+            None => "/mappings?limit=2".to_string(),
+        };
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let page: ListMappingsResponse = body_json(response.into_body()).await;
+        seen.extend(page.mappings.iter().map(|m| m.id));
+
+        // Deleting an already-visited mapping must not shift later pages
+        if cursor.is_none() {
+            manager.delete_mapping(&seen[0]).await.unwrap();
+        }
+
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen.len(), 5);
+    let mut unique = seen.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), 5);
+    for id in &ids {
+        assert!(seen.contains(id));
+    }
+
+    let request = Request::get("/mappings?cursor=bogus")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_list_mappings_by_offset() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mut ids = Vec::new();
+    for i in 0..3 {
+        let mapping = Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            format!("{}.example.com", i),
+            "Z1234567890ABC".to_string(),
+        );
+        ids.push(manager.add_mapping(mapping).await.unwrap());
+    }
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let request = Request::get("/mappings?offset=1&limit=1")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: ListMappingsResponse = body_json(response.into_body()).await;
+    assert_eq!(page.mappings.len(), 1);
+    assert_eq!(page.mappings[0].id, ids[1]);
+    assert_eq!(page.total, 3);
+    assert_eq!(page.next_offset, Some(2));
+    assert_eq!(page.next_cursor, None);
+
+    let request = Request::get("/mappings?offset=0&cursor=abc")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_zones_counts_mappings_per_zone() {
+    let dns = FakeDns::default();
+    dns.zone_names
+        .lock()
+        .unwrap()
+        .insert("ZONEB".to_string(), Some("example.org.".to_string()));
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns);
+    for (short_url, zone) in [
+        ("a.example.com", "ZONEA"),
+        ("b.example.com", "ZONEA"),
+        ("c.example.org", "ZONEB"),
+    ] {
+        let mapping = Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            short_url.to_string(),
+            zone.to_string(),
+        );
+        manager.add_mapping(mapping).await.unwrap();
+    }
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let request = Request::get("/zones").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let zones: Vec<ZoneUsage> = body_json(response.into_body()).await;
+    let counts: Vec<(&str, usize)> = zones
+        .iter()
+        .map(|z| (z.hosted_zone_id.as_str(), z.mapping_count))
+        .collect();
+    assert_eq!(counts, vec![("ZONEA", 2), ("ZONEB", 1)]);
+    assert!(zones.iter().all(|z| z.name.is_none()));
+
+    let request = Request::get("/zones?resolve=true")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let zones: Vec<ZoneUsage> = body_json(response.into_body()).await;
+    assert_eq!(zones[0].name.as_deref(), Some("example.com."));
+    assert_eq!(zones[1].name.as_deref(), Some("example.org."));
+}
+
+#[tokio::test]
+async fn test_create_rejects_short_url_outside_zone() {
+    let dns = FakeDns::default();
+    dns.zone_names
+        .lock()
+        .unwrap()
+        .insert("ZMISSING".to_string(), None);
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns);
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+    let create = |short_url: &str, zone: &str| {
+        Request::post("/mappings")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "s3_url": "s3://my-bucket/file.pdf",
+                    "short_url": short_url,
+                    "hosted_zone_id": zone,
+                })
+                .to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(create("short.example.org", "Z1234567890ABC"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&body),
+        "short.example.org is not in hosted zone Z1234567890ABC (example.com)"
+    );
+
+    let response = app
+        .clone()
+        .oneshot(create("short.example.com", "ZMISSING"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .oneshot(create("short.example.com", "Z1234567890ABC"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_create_tells_aws_failures_from_invalid_mappings() {
+    let create = |dns: FakeDns, s3_url: &str| {
+        let (manager, _log_rx) = MappingManager::new(FakeStorage, dns);
+        let app = s3_buddy::server::create_router(Arc::new(manager));
+        let request = Request::post("/mappings")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "s3_url": s3_url,
+                    "short_url": "short.example.com",
+                    "hosted_zone_id": "Z1234567890ABC",
+                })
+                .to_string(),
+            ))
+            .unwrap();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (status, String::from_utf8_lossy(&body).into_owned())
+        }
+    };
+
+    // A missing object is the request's fault, and says why in full
+    let (status, body) = create(FakeDns::default(), "s3://my-bucket/missing.pdf").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.starts_with("S3 object not found"), "{}", body);
+    assert!(
+        body.ends_with("No object at s3://my-bucket/missing.pdf"),
+        "{}",
+        body
+    );
+
+    let (status, body) = create(FakeDns::default(), "s3://missing-bucket/file.pdf").await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert!(body.ends_with("NoSuchBucket: missing-bucket"), "{}", body);
+    let (status, _) = create(FakeDns::default(), "s3://throttled-bucket/file.pdf").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    let unreachable = FakeDns {
+        unreachable: true,
+        ..Default::default()
+    };
+    let (status, body) = create(unreachable, "s3://my-bucket/file.pdf").await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert!(body.contains("Failed to look up hosted zone"), "{}", body);
+}
+
+#[tokio::test]
+async fn test_create_and_update_reject_taken_short_url() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+    let create = |short_url: &str| {
+        Request::post("/mappings")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "s3_url": "s3://my-bucket/file.pdf",
+                    "short_url": short_url,
+                    "hosted_zone_id": "Z1234567890ABC",
+                })
+                .to_string(),
+            ))
+            .unwrap()
+    };
+    let update = |id: Uuid, short_url: &str| {
+        Request::put(format!("/mappings/{}", id))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({ "short_url": short_url }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(create("a.example.com")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let first: Mapping = body_json(response.into_body()).await;
+    let response = app.clone().oneshot(create("b.example.com")).await.unwrap();
+    let second: Mapping = body_json(response.into_body()).await;
+
+    let response = app.clone().oneshot(create("A.example.com")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .clone()
+        .oneshot(update(first.id, "a.example.com"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .oneshot(update(second.id, "a.example.com"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_update_is_validated_like_create() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let manager = Arc::new(manager);
+    let id = manager
+        .add_mapping(Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        ))
+        .await
+        .unwrap();
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+
+    for body in [
+        serde_json::json!({ "s3_url": "https://my-bucket/file.pdf" }),
+        serde_json::json!({ "access_point_arn": "arn:aws:s3:::not-an-access-point" }),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::put(format!("/mappings/{}", id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(!response.status().is_success(), "{}", body);
+    }
+    let stored = manager.get_mapping(&id).await.unwrap();
+    assert_eq!(stored.s3_url, "s3://my-bucket/file.pdf");
+    assert_eq!(stored.access_point_arn, None);
+}
+
+#[tokio::test]
+async fn test_update_checks_the_new_zone() {
+    let dns = FakeDns::default();
+    dns.zone_names
+        .lock()
+        .unwrap()
+        .insert("ZONEB".to_string(), Some("example.org.".to_string()));
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns);
+    let id = manager
+        .add_mapping(Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            "short.example.com".to_string(),
+            "ZONEA".to_string(),
+        ))
+        .await
+        .unwrap();
+
+    let mut moved = manager.get_mapping(&id).await.unwrap();
+    moved.hosted_zone_id = "ZONEB".to_string();
+    let err = manager
+        .update_mapping(&id, moved.clone())
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("not in hosted zone"),
+        "{:#}",
+        err
+    );
+    assert_eq!(
+        manager.get_mapping(&id).await.unwrap().hosted_zone_id,
+        "ZONEA"
+    );
+
+    moved.short_url = "short.example.org".to_string();
+    manager.update_mapping(&id, moved).await.unwrap();
+    assert_eq!(
+        manager.get_mapping(&id).await.unwrap().hosted_zone_id,
+        "ZONEB"
+    );
+}
+
+#[tokio::test]
+async fn test_mappings_are_addressable_by_slug() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+    let create = |short_url: &str, slug: Option<&str>| {
+        let mut body = serde_json::json!({
+            "s3_url": "s3://my-bucket/file.pdf",
+            "short_url": short_url,
+            "hosted_zone_id": "Z1234567890ABC",
+        });
+        if let Some(slug) = slug {
+            body["slug"] = slug.into();
+        }
+        Request::post("/mappings")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // Derived from the short URL, numbered when two short URLs derive alike
+    let response = app
+        .clone()
+        .oneshot(create("files.eu.example.com", None))
+        .await
+        .unwrap();
+    let first: Mapping = body_json(response.into_body()).await;
+    assert_eq!(first.slug, "files-eu-example-com");
+    let response = app
+        .clone()
+        .oneshot(create("files-eu.example.com", None))
+        .await
+        .unwrap();
+    let second: Mapping = body_json(response.into_body()).await;
+    assert_eq!(second.slug, "files-eu-example-com-2");
+
+    // Explicit slugs must be valid and free
+    let response = app
+        .clone()
+        .oneshot(create("report.example.com", Some("q3-report")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: Mapping = body_json(response.into_body()).await;
+    assert_eq!(report.slug, "q3-report");
+    let response = app
+        .clone()
+        .oneshot(create("other.example.com", Some("q3-report")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app
+        .clone()
+        .oneshot(create("other.example.com", Some("Q3 Report")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::put(format!("/mappings/{}", second.id))
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "slug": "q3-report" }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Looked up by slug, or by slug in place of the ID
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/mappings/by-slug/q3-report")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let found: Mapping = body_json(response.into_body()).await;
+    assert_eq!(found.id, report.id);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/mappings/q3-report/pause")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let paused: Mapping = body_json(response.into_body()).await;
+    assert_eq!(paused.id, report.id);
+    assert_eq!(paused.status, MappingStatus::Paused);
+
+    for path in ["/mappings/by-slug/missing", "/mappings/missing"] {
+        let response = app
+            .clone()
+            .oneshot(Request::get(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{}", path);
+    }
+}
+
+#[tokio::test]
+async fn test_update_changes_only_the_fields_sent() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+    let put = |id: Uuid, body: serde_json::Value| {
+        Request::put(format!("/mappings/{}", id))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let request = Request::post("/mappings")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            serde_json::json!({
+                "s3_url": "s3://my-bucket/file.pdf",
+                "short_url": "short.example.com",
+                "hosted_zone_id": "Z1234567890ABC",
+                "presign_duration_secs": 6 * 60 * 60,
+                "notes": "keep me",
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let created: Mapping = body_json(response.into_body()).await;
+
+    let response = app
+        .clone()
+        .oneshot(put(
+            created.id,
+            serde_json::json!({ "refresh_interval_secs": 3600 }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: Mapping = body_json(response.into_body()).await;
+    assert_eq!(updated.refresh_interval_secs, 3600);
+    let mut expected = created.to_create_request();
+    expected.refresh_interval_secs = Some(3600);
+    assert_eq!(updated.to_create_request(), expected);
+
+    let response = app
+        .oneshot(put(
+            created.id,
+            serde_json::json!({ "hosted_zone_id": "/hostedzone/Z0987654321XYZ" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: Mapping = body_json(response.into_body()).await;
+    assert_eq!(updated.hosted_zone_id, "Z0987654321XYZ");
+    assert_eq!(updated.refresh_interval_secs, 3600);
+    assert_eq!(updated.short_url, "short.example.com");
+}
+
+#[tokio::test]
+async fn test_pause_and_resume_all() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let manager = Arc::new(manager);
+    let mut ids = Vec::new();
+    for name in ["a", "b", "c"] {
+        let mapping = Mapping::new(
+            format!("s3://my-bucket/{}.pdf", name),
+            format!("{}.example.com", name),
+            "Z1234567890ABC".to_string(),
+        );
+        ids.push(manager.add_mapping(mapping).await.unwrap());
+    }
+    manager.pause_mapping(&ids[1]).await.unwrap();
+    let app = s3_buddy::server::create_router(manager.clone());
+    let post = |path: &str| Request::post(path).body(Body::empty()).unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(post("/mappings/pause-all"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: BulkStatusReport = body_json(response.into_body()).await;
+    assert_eq!(report.changed, 2);
+    assert_eq!(report.changed_ids, vec![ids[0], ids[2]]);
+    for mapping in manager.list_mappings().await {
+        assert_eq!(
+            mapping.status,
+            MappingStatus::Paused,
+            "{}",
+            mapping.short_url
+        );
+    }
+    assert_eq!(manager.stats().await.active_tasks, 0);
+
+    // Already paused mappings are left alone
+    let response = app
+        .clone()
+        .oneshot(post("/mappings/pause-all"))
+        .await
+        .unwrap();
+    let report: BulkStatusReport = body_json(response.into_body()).await;
+    assert_eq!(report.changed, 0);
+
+    // Only what pause-all paused is resumed
+    let response = app.oneshot(post("/mappings/resume-all")).await.unwrap();
+    let report: BulkStatusReport = body_json(response.into_body()).await;
+    assert_eq!(report.changed_ids, vec![ids[0], ids[2]]);
+    assert_eq!(
+        manager.get_mapping(&ids[1]).await.unwrap().status,
+        MappingStatus::Paused
+    );
+    assert_eq!(manager.stats().await.active_tasks, 2);
+}
+
+#[tokio::test]
+async fn test_export_import_round_trip() {
+    let (source, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mut report = Mapping::new(
+        "s3://my-bucket/report.pdf".to_string(),
+        "report.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    report.presign_duration_secs = 6 * 60 * 60;
+    report.refresh_interval_secs = 5 * 60 * 60;
+    report.notes = Some("Quarterly report".to_string());
+    source.add_mapping(report).await.unwrap();
+    let archive = source
+        .add_mapping(Mapping::new(
+            "s3://my-bucket/archive.zip".to_string(),
+            "archive.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        ))
+        .await
+        .unwrap();
+    source.pause_mapping(&archive).await.unwrap();
+    let source = s3_buddy::server::create_router(Arc::new(source));
+
+    let export = |app: axum::Router| async move {
+        let request = Request::get("/export").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        body_json::<MappingExport>(response.into_body()).await
+    };
+    let import = |app: axum::Router, mode: &str, export: &MappingExport| {
+        let request = Request::post(format!("/import?mode={}", mode))
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(export).unwrap()))
+            .unwrap();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            (
+                status,
+                body_json::<ImportReport>(response.into_body()).await,
+            )
+        }
+    };
+
+    let exported = export(source).await;
+    assert_eq!(exported.mappings.len(), 2);
+    assert_eq!(
+        exported.mappings[0].request.short_url,
+        "archive.example.com"
+    );
+    assert!(exported.mappings[0].paused);
+
+    // Feeding the export into another server reproduces the set
+    let (target, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let target = Arc::new(target);
+    let app = s3_buddy::server::create_router(Arc::clone(&target));
+    let (status, report) = import(app.clone(), "merge", &exported).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report.created, 2);
+    assert_eq!(export(app.clone()).await.mappings, exported.mappings);
+    let archive = report.results[0].id.unwrap();
+    assert_eq!(
+        target.get_mapping(&archive).await.unwrap().status,
+        MappingStatus::Paused
+    );
+
+    // Merging the same short URLs again is rejected without changes
+    let (status, report) = import(app.clone(), "merge", &exported).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(!report.applied);
+    assert!(report.results.iter().all(|r| r.error.is_some()));
+    assert_eq!(target.list_mappings().await.len(), 2);
+
+    // Replacing swaps them for new copies
+    let (status, report) = import(app.clone(), "replace", &exported).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((report.deleted, report.created), (2, 2));
+    assert!(target.get_mapping(&archive).await.is_none());
+    assert_eq!(export(app).await.mappings, exported.mappings);
+}
+
+#[tokio::test]
+async fn test_import_is_validated_before_any_change() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let existing = manager
+        .add_mapping(Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            "existing.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        ))
+        .await
+        .unwrap();
+    let slug = manager.get_mapping(&existing).await.unwrap().slug;
+    let import = |mode: ImportMode, entries: serde_json::Value| {
+        let export: MappingExport =
+            serde_json::from_value(serde_json::json!({ "mappings": entries })).unwrap();
+        manager.import_all(export, mode)
+    };
+    let entry = |s3_url: &str, short_url: &str, slug: Option<&str>| {
+        serde_json::json!({
+            "s3_url": s3_url,
+            "short_url": short_url,
+            "hosted_zone_id": "Z1234567890ABC",
+            "slug": slug,
+        })
+    };
+
+    // A missing object fails validation, so a replace deletes nothing
+    let report = import(
+        ImportMode::Replace,
+        serde_json::json!([
+            entry("s3://my-bucket/file.pdf", "a.example.com", None),
+            entry("s3://my-bucket/missing.pdf", "b.example.com", None),
+        ]),
+    )
+    .await;
+    assert!(!report.applied);
+    assert_eq!(report.deleted, 0);
+    assert!(report.results[1]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("No object at s3://my-bucket/missing.pdf"));
+    assert!(manager.get_mapping(&existing).await.is_some());
+
+    // Slugs are unique within the import and against the server
+    let report = import(
+        ImportMode::Merge,
+        serde_json::json!([
+            entry("s3://my-bucket/file.pdf", "a.example.com", Some("docs")),
+            entry("s3://my-bucket/file.pdf", "b.example.com", Some("docs")),
+            entry("s3://my-bucket/file.pdf", "c.example.com", Some(&slug)),
+        ]),
+    )
+    .await;
+    assert!(!report.applied);
+    let errors: Vec<Option<&str>> = report.results.iter().map(|r| r.error.as_deref()).collect();
+    assert_eq!(errors[0], None);
+    assert!(errors[1].unwrap().contains("more than once"));
+    assert!(
+        errors[2].unwrap().contains("already used"),
+        "{:?}",
+        errors[2]
+    );
+    assert_eq!(manager.list_mappings().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_delete_list_deleted_restore() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let request = Request::delete(format!("/mappings/{}", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let request = Request::get("/mappings/deleted")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ListDeletedResponse = body_json(response.into_body()).await;
+    assert_eq!(body.deleted.len(), 1);
+    assert_eq!(body.deleted[0].mapping.id, id);
+
+    let request = Request::post(format!("/mappings/{}/restore", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = Request::get(format!("/mappings/{}", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = Request::get("/mappings/deleted")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let body: ListDeletedResponse = body_json(response.into_body()).await;
+    assert!(body.deleted.is_empty());
+}
+
+#[tokio::test]
+async fn test_restored_pending_mapping_is_scheduled_again() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let manager = manager.with_initial_refresh_delay(Duration::from_secs(3600));
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    assert_eq!(
+        manager.get_mapping(&id).await.unwrap().status,
+        MappingStatus::Pending
+    );
+
+    manager.delete_mapping(&id).await.unwrap();
+    assert_eq!(manager.stats().await.active_tasks, 0);
+    let restored = manager.restore_mapping(&id).await.unwrap();
+    assert_eq!(restored.status, MappingStatus::Pending);
+    assert_eq!(manager.stats().await.active_tasks, 1);
+
+    // Updating it keeps it scheduled too
+    let mut updated = restored;
+    updated.refresh_interval_secs /= 2;
+    manager.update_mapping(&id, updated).await.unwrap();
+    assert_eq!(manager.stats().await.active_tasks, 1);
+}
+
+#[tokio::test]
+async fn test_restore_rejects_a_reused_short_url() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mapping = || {
+        Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        )
+    };
+    let id = manager.add_mapping(mapping()).await.unwrap();
+    manager.delete_mapping(&id).await.unwrap();
+    let replacement = manager.add_mapping(mapping()).await.unwrap();
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let request = Request::post(format!("/mappings/{}/restore", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Still deleted, and restorable once the short URL is free again
+    let request = Request::delete(format!("/mappings/{}", replacement))
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap();
+    let request = Request::post(format!("/mappings/{}/restore", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_failed_delete_keeps_the_mapping() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let manager = manager.with_recycle_ttl(Duration::MAX);
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+
+    assert!(manager.delete_mapping(&id).await.is_err());
+    assert!(manager.get_mapping(&id).await.is_some());
+    assert!(manager.list_deleted().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_deleted_mappings_purged_after_ttl() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let manager = manager.with_recycle_ttl(Duration::from_millis(50));
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    manager.delete_mapping(&id).await.unwrap();
+    assert_eq!(manager.list_deleted().await.len(), 1);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(manager.list_deleted().await.is_empty());
+    assert!(manager.restore_mapping(&id).await.is_err());
+}
+
+#[tokio::test]
+async fn test_info_reports_motd() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let manager = manager.with_motd(Some("Staging".to_string()));
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let request = Request::get("/info").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let info: ServerInfo = body_json(response.into_body()).await;
+    assert_eq!(info.motd.as_deref(), Some("Staging"));
+}
+
+#[tokio::test]
+async fn test_version_reports_package_version() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let request = Request::get("/version").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let build: BuildInfo = body_json(response.into_body()).await;
+    assert_eq!(build.version, env!("CARGO_PKG_VERSION"));
+    assert!(!build.git_sha.is_empty());
+    assert!(build.build_timestamp.is_some());
+}
+
+#[tokio::test]
+async fn test_simultaneous_manual_refreshes_refresh_once() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let manager = Arc::new(manager.with_refresh_debounce(Duration::from_millis(300)));
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+
+    // Let the initial scheduled refresh finish and its debounce window pass
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert_eq!(dns.upserts.lock().unwrap().len(), 1);
+
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+    let refresh = || {
+        let request = Request::post(format!("/mappings/{}/refresh", id))
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(request)
+    };
+    let (first, second) = tokio::join!(refresh(), refresh());
+    let first: ManualRefresh = body_json(first.unwrap().into_body()).await;
+    let second: ManualRefresh = body_json(second.unwrap().into_body()).await;
+
+    assert!(first.refreshed != second.refreshed);
+    assert_eq!(dns.upserts.lock().unwrap().len(), 2);
+    assert!(first.mapping.last_refresh.is_some());
+    assert!(second.mapping.last_refresh.is_some());
+}
+
+#[tokio::test]
+async fn test_refresh_records_last_and_next_refresh() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mut mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.refresh_interval_secs = 3600;
+    assert!(mapping.last_refresh.is_none() && mapping.next_refresh.is_none());
+
+    let before = chrono::Utc::now();
+    let id = manager.add_mapping(mapping).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let after = chrono::Utc::now();
+
+    let stored = manager.get_mapping(&id).await.unwrap();
+    let last_refresh = stored.last_refresh.unwrap();
+    assert!(before <= last_refresh && last_refresh <= after);
+    let interval = chrono::Duration::hours(1);
+    let next_refresh = stored.next_refresh.unwrap();
+    assert!(before + interval <= next_refresh && next_refresh <= after + interval);
+
+    // Both are part of the API's JSON
+    let json = serde_json::to_value(&stored).unwrap();
+    assert!(json["last_refresh"].is_string());
+    assert!(json["next_refresh"].is_string());
+}
+
+#[tokio::test]
+async fn test_manual_refresh_keeps_periodic_schedule() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let manager = manager.with_refresh_debounce(Duration::ZERO);
+    let id = manager
+        .add_mapping(Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            "short.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        ))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let scheduled = manager.get_mapping(&id).await.unwrap();
+    assert!(scheduled.next_refresh.is_some());
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let result = manager.refresh_now(&id).await.unwrap();
+    assert!(result.refreshed);
+    assert_eq!(dns.upserts.lock().unwrap().len(), 2);
+    assert!(result.mapping.last_refresh > scheduled.last_refresh);
+    assert_eq!(result.mapping.next_refresh, scheduled.next_refresh);
+
+    let stats = manager.stats().await;
+    assert_eq!((stats.active_tasks, stats.active_mappings), (1, 1));
+}
+
+#[tokio::test]
+async fn test_grouped_mappings_by_status() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let new_mapping = |s3_url: &str, short_url: &str| {
+        Mapping::new(
+            s3_url.to_string(),
+            short_url.to_string(),
+            "Z1234567890ABC".to_string(),
+        )
+    };
+    let mut ids = Vec::new();
+    for short_url in ["c.example.com", "a.example.com", "b.example.com"] {
+        let mapping = new_mapping("s3://my-bucket/file.pdf", short_url);
+        ids.push(manager.add_mapping(mapping).await.unwrap());
+    }
+    manager.pause_mapping(&ids[2]).await.unwrap();
+    manager
+        .load_mappings(vec![new_mapping("not-s3", "d.example.com")])
+        .await;
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let request = Request::get("/mappings/grouped")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let grouped: GroupedMappings = body_json(response.into_body()).await;
+
+    let short_urls = |group: &[Mapping]| {
+        group
+            .iter()
+            .map(|m| m.short_url.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        short_urls(&grouped.active),
+        vec!["a.example.com", "c.example.com"]
+    );
+    assert_eq!(short_urls(&grouped.paused), vec!["b.example.com"]);
+    assert_eq!(short_urls(&grouped.quarantined), vec!["d.example.com"]);
+    assert!(grouped.error.is_empty());
+    assert!(grouped.pending.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_refresh_log_warns_when_credentials_expire_first() {
+    let (manager, _log_rx) = MappingManager::new(
+        ScriptedStorage {
+            credentials_lifetime: Some(Duration::from_secs(600)),
+            ..Default::default()
+        },
+        FakeDns::default(),
+    );
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "short.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    manager.add_mapping(mapping).await.unwrap();
+    advance(Duration::from_millis(10)).await;
+
+    let logs = manager.logs(&Default::default()).await.unwrap().logs;
+    assert_eq!(logs.len(), 1);
+    assert!(logs[0].success);
+    assert!(logs[0].message.contains("credentials expire in 600s"));
+}
+
+#[tokio::test]
+async fn test_notes_only_update_keeps_refresh_task() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "notes.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        manager.logs(&Default::default()).await.unwrap().logs.len(),
+        1
+    );
+
+    let mut updated = manager.get_mapping(&id).await.unwrap();
+    updated.notes = Some("temporary for Q3 launch, remove after".to_string());
+    assert!(
+        !manager
+            .get_mapping(&id)
+            .await
+            .unwrap()
+            .diff(&updated)
+            .restart_required
+    );
+    manager.update_mapping(&id, updated).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // No restart, so no second immediate refresh
+    assert_eq!(
+        manager.logs(&Default::default()).await.unwrap().logs.len(),
+        1
+    );
+    let stored = manager.get_mapping(&id).await.unwrap();
+    assert_eq!(
+        stored.notes.as_deref(),
+        Some("temporary for Q3 launch, remove after")
+    );
+    assert_eq!(stored.status, MappingStatus::Active);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_failed_first_refresh_retries_quickly() {
+    let (manager, _log_rx) = MappingManager::new(flaky_storage(), FakeDns::default());
+    let manager = manager.with_initial_retry_backoff(vec![Duration::from_millis(50)]);
+    let mut mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "flaky.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.refresh_interval_secs = 3600;
+    let id = manager.add_mapping(mapping).await.unwrap();
+
+    advance(Duration::from_millis(10)).await;
+    let logs = manager.logs(&Default::default()).await.unwrap().logs;
+    assert_eq!(logs.len(), 1);
+
+    // Retried after the short backoff rather than the hour-long interval
+    advance(Duration::from_millis(50)).await;
+    let logs = manager.logs(&Default::default()).await.unwrap().logs;
+    assert_eq!(logs.len(), 2);
+    assert!(!logs[0].success);
+    assert!(logs[1].success);
+    assert_eq!(
+        manager.get_mapping(&id).await.unwrap().status,
+        MappingStatus::Active
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_failed_refresh_after_success_retries_quickly() {
+    let (manager, _log_rx) = MappingManager::new(
+        ScriptedStorage {
+            failures: vec![(2, "SlowDown: please reduce your request rate")],
+            ..Default::default()
+        },
+        FakeDns::default(),
+    );
+    let manager = manager.with_failure_retry_backoff(vec![Duration::from_millis(100)]);
+    let mut mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "intermittent.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.refresh_interval_secs = 1;
+    let id = manager.add_mapping(mapping).await.unwrap();
+
+    // Succeeds, fails a second later, then recovers after the short backoff
+    // rather than another full interval
+    advance(Duration::from_millis(10)).await;
+    advance(Duration::from_secs(1)).await;
+    advance(Duration::from_millis(100)).await;
+    let logs = manager.logs(&Default::default()).await.unwrap().logs;
+    let outcomes: Vec<bool> = logs.iter().map(|log| log.success).collect();
+    assert_eq!(outcomes, [true, false, true]);
+    assert_eq!(
+        manager.get_mapping(&id).await.unwrap().status,
+        MappingStatus::Active
+    );
+
+    // Back on the normal cadence: the next refresh is a full interval after
+    // the retry
+    advance(Duration::from_millis(900)).await;
+    assert_eq!(
+        manager.logs(&Default::default()).await.unwrap().logs.len(),
+        3
+    );
+    advance(Duration::from_millis(100)).await;
+    assert_eq!(
+        manager.logs(&Default::default()).await.unwrap().logs.len(),
+        4
+    );
+
+    manager.delete_mapping(&id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_refresh_fails_over_to_readable_replica() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let skip_validation = AddOptions {
+        skip_validation: true,
+        ..Default::default()
+    };
+    let wait_for_log = |id: Uuid| {
+        let manager = &manager;
+        async move {
+            for _ in 0..50 {
+                let logs = manager.mapping_logs(&id).await.unwrap();
+                if let Some(log) = logs.into_iter().last() {
+                    return log;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            panic!("no refresh logged");
+        }
+    };
+
+    // The primary bucket is unreadable; the replica serves
+    let mut mapping = Mapping::new(
+        "s3://missing-bucket/file.pdf".to_string(),
+        "replicated.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.fallback_s3_urls = vec![
+        "s3://my-bucket/missing.pdf".to_string(),
+        "s3://replica-bucket/file.pdf".to_string(),
+    ];
+    let id = manager
+        .add_mapping_with(mapping, skip_validation)
+        .await
+        .unwrap();
+    let log = wait_for_log(id).await;
+    assert!(log.success);
+    assert_eq!(
+        log.message,
+        "Successfully refreshed presigned URL from fallback s3://replica-bucket/file.pdf"
+    );
+    let stored = manager.get_mapping(&id).await.unwrap();
+    assert_eq!(
+        stored.serving_s3_url.as_deref(),
+        Some("s3://replica-bucket/file.pdf")
+    );
+    let published = dns.upserts.lock().unwrap().last().unwrap().2.clone();
+    assert!(published.starts_with("https://replica-bucket."));
+    // Checks and on-demand presigns follow the replica too
+    assert!(manager.dns_status(&id).await.unwrap().matches);
+    let presigned = manager
+        .presign_now(&id, PresignMethod::Get, None, None)
+        .await
+        .unwrap();
+    assert!(presigned.url.starts_with("https://replica-bucket."));
+    assert_eq!(manager.reconcile().await.fixed, 0);
+
+    // With no readable target the refresh fails, naming each one
+    let mut mapping = Mapping::new(
+        "s3://missing-bucket/file.pdf".to_string(),
+        "unreplicated.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.fallback_s3_urls = vec!["s3://my-bucket/missing.pdf".to_string()];
+    let id = manager
+        .add_mapping_with(mapping, skip_validation)
+        .await
+        .unwrap();
+    let log = wait_for_log(id).await;
+    assert!(!log.success);
+    assert!(log
+        .message
+        .contains("s3://missing-bucket/file.pdf: NoSuchBucket"));
+    assert!(log
+        .message
+        .contains("s3://my-bucket/missing.pdf: No object at s3://my-bucket/missing.pdf"));
+    assert_eq!(
+        manager.get_mapping(&id).await.unwrap().status,
+        MappingStatus::Error
+    );
+}
+
+#[tokio::test]
+async fn test_override_publishes_target_until_expiry() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "override.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    let manager = Arc::new(manager);
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+    let last_target = || dns.upserts.lock().unwrap().last().unwrap().2.clone();
+
+    let request = Request::post(format!("/mappings/{}/override", id))
+        .header("content-type", "application/json")
+        .body(Body::from(
+            serde_json::json!({
+                "s3_url": "s3://status-bucket/down.html",
+                "duration_secs": 1
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let mapping: Mapping = body_json(response.into_body()).await;
+    assert_eq!(
+        mapping.override_s3_url.as_deref(),
+        Some("s3://status-bucket/down.html")
+    );
+    assert_eq!(mapping.s3_url, "s3://my-bucket/file.pdf");
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(last_target().starts_with("https://status-bucket.s3.amazonaws.com/down.html"));
+
+    // Reverted automatically once the override expires
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert!(last_target().starts_with("https://my-bucket.s3.amazonaws.com/file.pdf"));
+    let stored = manager.get_mapping(&id).await.unwrap();
+    assert_eq!(stored.override_s3_url, None);
+    assert_eq!(stored.override_expires_at, None);
+}
+
+#[tokio::test]
+async fn test_override_can_be_cleared() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "override.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let manager = Arc::new(manager);
+    let id = manager.add_mapping(mapping).await.unwrap();
+
+    // Invalid targets and durations are rejected up front
+    assert!(manager
+        .set_override(
+            &id,
+            "https://elsewhere".to_string(),
+            Duration::from_secs(60)
+        )
+        .await
+        .is_err());
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+    for duration_secs in [0, 31 * 24 * 60 * 60, u64::MAX] {
+        let request = Request::post(format!("/mappings/{}/override", id))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "s3_url": "s3://status-bucket/down.html",
+                    "duration_secs": duration_secs,
+                })
+                .to_string(),
+            ))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    assert_eq!(
+        manager.get_mapping(&id).await.unwrap().override_s3_url,
+        None
+    );
+
+    manager
+        .set_override(
+            &id,
+            "s3://status-bucket/down.html".to_string(),
+            Duration::from_secs(3600),
+        )
+        .await
+        .unwrap();
+    let request = Request::delete(format!("/mappings/{}/override", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let mapping: Mapping = body_json(response.into_body()).await;
+    assert_eq!(mapping.override_s3_url, None);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let last = dns.upserts.lock().unwrap().last().unwrap().2.clone();
+    assert!(last.starts_with("https://my-bucket.s3.amazonaws.com/file.pdf"));
+}
+
+#[tokio::test]
+async fn test_dns_status_reports_match_and_drift() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "dns.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let dns_status = |app: axum::Router| async move {
+        let request = Request::get(format!("/mappings/{}/dns", id))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        body_json::<DnsStatus>(response.into_body()).await
+    };
+
+    let status = dns_status(app.clone()).await;
+    assert!(status.matches);
+    assert_eq!(status.expected, "my-bucket.s3.amazonaws.com");
+    let record = status.record.unwrap();
+    assert_eq!(record.record_type, "CNAME");
+    assert_eq!(record.ttl, Some(300));
+
+    // Someone pointed the record elsewhere
+    dns.upserts.lock().unwrap().push((
+        "Z1234567890ABC".to_string(),
+        "dns.example.com".to_string(),
+        "https://elsewhere.example.com/".to_string(),
+    ));
+    let status = dns_status(app).await;
+    assert!(!status.matches);
+    assert_eq!(status.record.unwrap().values, ["elsewhere.example.com."]);
+}
+
+#[tokio::test]
+async fn test_presign_ttl_is_capped_at_mapping_maximum() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mut mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "ttl.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.max_presign_duration_secs = Some(86400);
+    let id = manager.add_mapping(mapping).await.unwrap();
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let presign = |app: axum::Router, query: &'static str| async move {
+        let request = Request::get(format!("/mappings/{}/presign{}", id, query))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        body_json::<PresignedUrl>(response.into_body()).await
+    };
+
+    assert_eq!(presign(app.clone(), "").await.expires_in_secs, 12 * 3600);
+    assert_eq!(presign(app.clone(), "?ttl=600").await.expires_in_secs, 600);
+    assert_eq!(presign(app, "?ttl=604800").await.expires_in_secs, 86400);
+}
+
+#[tokio::test]
+async fn test_presign_method_must_match_mapping_operation() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let download = manager
+        .add_mapping(Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            "download.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        ))
+        .await
+        .unwrap();
+    // The object to upload doesn't exist yet, which is fine for uploads
+    let mut mapping = Mapping::new(
+        "s3://missing-bucket/inbox/upload.zip".to_string(),
+        "upload.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.operation = Operation::Put;
+    let upload = manager.add_mapping(mapping).await.unwrap();
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    for (id, method, expected) in [
+        (download, "get", StatusCode::OK),
+        (download, "head", StatusCode::OK),
+        (download, "put", StatusCode::METHOD_NOT_ALLOWED),
+        (upload, "put", StatusCode::OK),
+        (upload, "get", StatusCode::METHOD_NOT_ALLOWED),
+        (upload, "head", StatusCode::METHOD_NOT_ALLOWED),
+    ] {
+        let request = Request::get(format!("/mappings/{}/presign?method={}", id, method))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), expected, "{} {}", id, method);
+    }
+}
+
+#[tokio::test]
+async fn test_unmanaged_dns_refreshes_without_route53() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let mut mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "terraform.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.manage_dns = false;
+    let id = manager.add_mapping(mapping).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let stored = manager.get_mapping(&id).await.unwrap();
+    assert!(stored.last_refresh.is_some());
+    assert_eq!(stored.status, MappingStatus::Active);
+    let logs = manager.logs(&Default::default()).await.unwrap().logs;
+    assert_eq!(logs.len(), 1);
+    assert!(logs[0].success);
+
+    // Neither the refresh nor reconciliation touched Route53
+    assert_eq!(manager.reconcile().await.checked, 0);
+    assert!(dns.upserts.lock().unwrap().is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_ready_after_first_refresh_of_all_mappings() {
+    let (manager, _log_rx) = MappingManager::new(flaky_storage(), FakeDns::default());
+    let manager = manager.with_initial_retry_backoff(vec![Duration::from_millis(100)]);
+    let manager = Arc::new(manager);
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+    let ready = |app: axum::Router| async move {
+        let request = Request::get("/health/ready").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        (status, body_json::<Readiness>(response.into_body()).await)
+    };
+
+    // Nothing to wait for
+    assert_eq!(ready(app.clone()).await.0, StatusCode::OK);
+
+    // The first refresh fails; the retry succeeds
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "ready.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    advance(Duration::from_millis(50)).await;
+    let (status, readiness) = ready(app.clone()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(readiness.waiting, [id]);
+
+    advance(Duration::from_millis(150)).await;
+    let (status, readiness) = ready(app).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(readiness.ready);
+}
+
+#[tokio::test]
+async fn test_ready_only_when_aws_is_reachable() {
+    let ready = |dns: FakeDns| async move {
+        let (manager, _log_rx) = MappingManager::new(FakeStorage, dns);
+        let app = s3_buddy::server::create_router(Arc::new(manager));
+        let request = Request::get("/health/aws").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        (
+            status,
+            body_json::<AwsReadiness>(response.into_body()).await,
+        )
+    };
+
+    let (status, readiness) = ready(FakeDns::default()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(readiness.s3.ok && readiness.route53.ok);
+
+    let (status, readiness) = ready(FakeDns {
+        unreachable: true,
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(!readiness.ready);
+    assert!(readiness.s3.ok);
+    assert_eq!(
+        readiness.route53.error.as_deref(),
+        Some("Route53 unreachable")
+    );
+}
+
+#[tokio::test]
+async fn test_aws_readiness_is_cached() {
+    let dns = FakeDns::default();
+    let access_checks = dns.access_checks.clone();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns);
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    // Concurrent and repeated polls share one probe
+    let poll = || {
+        let request = Request::get("/health/aws").body(Body::empty()).unwrap();
+        app.clone().oneshot(request)
+    };
+    let (first, second) = tokio::join!(poll(), poll());
+    assert_eq!(first.unwrap().status(), StatusCode::OK);
+    assert_eq!(second.unwrap().status(), StatusCode::OK);
+    assert_eq!(poll().await.unwrap().status(), StatusCode::OK);
+    assert_eq!(*access_checks.lock().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_pretty_json_on_request() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "pretty.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    manager.add_mapping(mapping).await.unwrap();
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let body = |app: axum::Router, uri: &'static str| async move {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    };
+
+    let compact = body(app.clone(), "/mappings").await;
+    let pretty = body(app, "/mappings?pretty=true").await;
+
+    // Same document, with compact as the default
+    assert!(!compact.contains('\n'));
+    assert!(pretty.contains("\n  \"mappings\": ["));
+    let value = |s: &str| serde_json::from_str::<serde_json::Value>(s).unwrap();
+    assert_eq!(value(&compact), value(&pretty));
+
+    // Field order is kept
+    let position = |s: &str, key: &str| s.find(&format!("\"{}\"", key)).unwrap();
+    assert!(position(&pretty, "id") < position(&pretty, "s3_url"));
+    assert!(position(&pretty, "s3_url") < position(&pretty, "short_url"));
+}
+
+#[tokio::test]
+async fn test_cors_headers_only_for_configured_origins() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let router = s3_buddy::server::create_router(Arc::new(manager));
+    let cors = s3_buddy::server::cors_layer("https://dash.example.com, https://ops.example.com")
+        .unwrap()
+        .unwrap();
+    let app = router.clone().layer(cors);
+
+    let get = |app: axum::Router, origin: &'static str| async move {
+        let request = Request::get("/mappings")
+            .header("origin", origin)
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap()
+    };
+
+    let response = get(app.clone(), "https://dash.example.com").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "https://dash.example.com"
+    );
+    let response = get(app.clone(), "https://evil.example.com").await;
+    assert!(!response
+        .headers()
+        .contains_key("access-control-allow-origin"));
+
+    // Preflight for a JSON update
+    let request = Request::options("/mappings/00000000-0000-0000-0000-000000000000")
+        .header("origin", "https://ops.example.com")
+        .header("access-control-request-method", "PUT")
+        .header("access-control-request-headers", "content-type")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "https://ops.example.com"
+    );
+    let methods = response.headers()["access-control-allow-methods"]
+        .to_str()
+        .unwrap();
+    assert!(methods.contains("PUT") && methods.contains("DELETE"));
+
+    // Any origin
+    let any = router
+        .clone()
+        .layer(s3_buddy::server::cors_layer("*").unwrap().unwrap());
+    let response = get(any, "https://anywhere.example.com").await;
+    assert_eq!(response.headers()["access-control-allow-origin"], "*");
+
+    // Unconfigured: no CORS headers
+    assert!(s3_buddy::server::cors_layer(" ").unwrap().is_none());
+    let response = get(router, "https://dash.example.com").await;
+    assert!(!response
+        .headers()
+        .contains_key("access-control-allow-origin"));
+}
+
+#[tokio::test]
+async fn test_hosted_zone_id_is_normalized_or_rejected() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "zone.example.com".to_string(),
+        "/hostedzone/Z1234567890ABC".to_string(),
+    );
+    let id = manager.add_mapping(mapping).await.unwrap();
+    let mut stored = manager.get_mapping(&id).await.unwrap();
+    assert_eq!(stored.hosted_zone_id, "Z1234567890ABC");
+
+    stored.hosted_zone_id = "example.com".to_string();
+    let err = manager.update_mapping(&id, stored).await.unwrap_err();
+    assert!(err.to_string().contains("Invalid hosted zone ID"));
+    assert_eq!(
+        manager.get_mapping(&id).await.unwrap().hosted_zone_id,
+        "Z1234567890ABC"
+    );
+}
+
+/// Notifier double that records every notification
+#[derive(Clone, Default)]
+struct RecordingNotifier {
+    sent: Arc<Mutex<Vec<Notification>>>,
+}
+
+#[async_trait]
+impl Notifier for RecordingNotifier {
+    async fn notify(&self, event: &Notification) -> Result<()> {
+        self.sent.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_notifies_on_failure_and_recovery() {
+    let notifier = RecordingNotifier::default();
+    let (manager, _log_rx) = MappingManager::new(flaky_storage(), FakeDns::default());
+    let manager = manager
+        .with_initial_retry_backoff(vec![Duration::from_millis(50)])
+        .with_notifiers(Notifiers::new(vec![Arc::new(notifier.clone())]));
+    let mut mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "alerts.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.refresh_interval_secs = 3600;
+    let id = manager.add_mapping(mapping).await.unwrap();
+    advance(Duration::from_millis(10)).await;
+    advance(Duration::from_millis(50)).await;
+
+    let sent = notifier.sent.lock().unwrap().clone();
+    let transitions: Vec<_> = sent.iter().map(|n| n.transition).collect();
+    assert_eq!(transitions, [Transition::Failed, Transition::Recovered]);
+    assert!(sent.iter().all(|n| n.mapping_id == id));
+    assert!(sent[0].message.contains("AccessDenied"));
+}
+
+#[tokio::test]
+async fn test_webhook_receives_failure_notification() {
+    // A local endpoint capturing every webhook body
+    let received: Arc<Mutex<Vec<serde_json::Value>>> = Arc::default();
+    let hook = axum::Router::new().route(
+        "/hook",
+        axum::routing::post({
+            let received = Arc::clone(&received);
+            move |axum::Json(body): axum::Json<serde_json::Value>| async move {
+                received.lock().unwrap().push(body);
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, hook).await });
+
+    let webhook = WebhookNotifier::new(url, Arc::new(ReqwestTransport::default()));
+    let (manager, _log_rx) = MappingManager::new(flaky_storage(), FakeDns::default());
+    let manager = manager
+        .with_initial_retry_backoff(vec![Duration::from_millis(50)])
+        .with_notifiers(Notifiers::new(vec![Arc::new(webhook)]));
+    let mut mapping = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "hook.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.refresh_interval_secs = 3600;
+    let id = manager.add_mapping(mapping).await.unwrap();
+
+    for _ in 0..100 {
+        if received.lock().unwrap().len() >= 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let received = received.lock().unwrap().clone();
+    let failures: Vec<_> = received
+        .iter()
+        .filter(|body| body["transition"] == "failed")
+        .collect();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0]["mapping_id"], id.to_string());
+    assert_eq!(failures[0]["short_url"], "hook.example.com");
+    assert!(failures[0]["timestamp"].is_string());
+    assert!(failures[0]["error"]
+        .as_str()
+        .unwrap()
+        .contains("AccessDenied"));
+}
+
+#[tokio::test]
+async fn test_dns_update_deferred_outside_allowed_window() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let now = chrono::Utc::now().time();
+    let hour = chrono::Duration::hours(1);
+    let mapping_with_window = |short_url: &str, start, end| {
+        let mut mapping = Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            short_url.to_string(),
+            "Z1234567890ABC".to_string(),
+        );
+        mapping.refresh_interval_secs = 3600;
+        // Outlasts the window being closed
+        mapping.presign_duration_secs = 24 * 60 * 60;
+        mapping.allowed_window = Some(RefreshWindow {
+            start,
+            end,
+            days: Vec::new(),
+        });
+        mapping
+    };
+
+    // A window that opens in an hour defers the update
+    let closed = manager
+        .add_mapping(mapping_with_window(
+            "closed.example.com",
+            now + hour,
+            now + hour * 2,
+        ))
+        .await
+        .unwrap();
+    // One that opened an hour ago lets it proceed
+    let open = manager
+        .add_mapping(mapping_with_window(
+            "open.example.com",
+            now - hour,
+            now + hour,
+        ))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let upserts = dns.upserts.lock().unwrap().clone();
+    assert_eq!(upserts.len(), 1);
+    assert_eq!(upserts[0].1, "open.example.com");
+
+    let logs = manager.logs(&Default::default()).await.unwrap().logs;
+    let event_of = |id| logs.iter().find(|log| log.mapping_id == id).unwrap().event;
+    assert_eq!(logs.len(), 2);
+    assert_eq!(event_of(closed), RefreshEvent::Deferred);
+    assert_eq!(event_of(open), RefreshEvent::Success);
+    let deferred = manager.get_mapping(&closed).await.unwrap();
+    assert!(deferred.last_refresh.is_none());
+    assert_eq!(deferred.consecutive_failures, 0);
+
+    // A URL that would expire while the window is closed is rejected
+    let mut short_lived = mapping_with_window("short.example.com", now + hour, now + hour * 2);
+    short_lived.presign_duration_secs = 12 * 60 * 60;
+    let err = manager.add_mapping(short_lived).await.unwrap_err();
+    assert!(err.to_string().contains("stays closed"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_initial_refresh_delay_skips_immediate_refresh() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let manager = manager.with_initial_refresh_delay(Duration::from_secs(3600));
+    let new_mapping = |short_url: &str| {
+        Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            short_url.to_string(),
+            "Z1234567890ABC".to_string(),
+        )
+    };
+
+    let delayed = manager
+        .add_mapping(new_mapping("bulk.example.com"))
+        .await
+        .unwrap();
+    // A per-create delay of zero overrides the server-wide setting
+    let immediate = manager
+        .add_mapping_with(
+            new_mapping("now.example.com"),
+            AddOptions {
+                initial_delay: Some(Duration::ZERO),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let upserts = dns.upserts.lock().unwrap().clone();
+    assert_eq!(upserts.len(), 1);
+    assert_eq!(upserts[0].1, "now.example.com");
+
+    let stored = manager.get_mapping(&delayed).await.unwrap();
+    assert_eq!(stored.status, MappingStatus::Pending);
+    assert!(stored.last_refresh.is_none());
+    assert!(stored.next_refresh.unwrap() <= chrono::Utc::now() + chrono::Duration::hours(1));
+    assert_eq!(
+        manager.get_mapping(&immediate).await.unwrap().status,
+        MappingStatus::Active
+    );
+
+    // No delay outlasts the refresh interval: a requested one is rejected,
+    // and the server-wide one capped
+    let mut frequent = new_mapping("frequent.example.com");
+    frequent.refresh_interval_secs = 600;
+    let err = manager
+        .add_mapping_with(
+            frequent.clone(),
+            AddOptions {
+                initial_delay: Some(Duration::from_secs(601)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("exceeds the refresh interval"),
+        "{}",
+        err
+    );
+    let id = manager.add_mapping(frequent).await.unwrap();
+    let next_refresh = manager
+        .get_mapping(&id)
+        .await
+        .unwrap()
+        .next_refresh
+        .unwrap();
+    assert!(next_refresh <= chrono::Utc::now() + chrono::Duration::seconds(600));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_concurrent_presigns_share_one_generation() {
+    let storage = slow_storage();
+    let (manager, _log_rx) = MappingManager::new(storage.clone(), FakeDns::default());
+    let manager = manager.with_initial_refresh_delay(Duration::from_secs(3600));
+    let id = manager
+        .add_mapping(Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            "burst.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        ))
+        .await
+        .unwrap();
+    let manager = Arc::new(manager);
+
+    let handles: Vec<_> = (0..10)
+        .map(|_| {
+            let manager = Arc::clone(&manager);
+            tokio::spawn(async move {
+                manager
+                    .presign_now(&id, PresignMethod::Get, None, None)
+                    .await
+                    .unwrap()
+            })
+        })
+        .collect();
+    let mut urls = Vec::new();
+    for handle in handles {
+        urls.push(handle.await.unwrap().url);
+    }
+
+    assert_eq!(*storage.calls.lock().unwrap(), 1);
+    assert!(urls.iter().all(|url| *url == urls[0]));
+
+    // A later request presigns again
+    manager
+        .presign_now(&id, PresignMethod::Get, None, None)
+        .await
+        .unwrap();
+    assert_eq!(*storage.calls.lock().unwrap(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_presign_concurrency_is_limited() {
+    let storage = slow_storage();
+    let (manager, _log_rx) = MappingManager::new(storage.clone(), FakeDns::default());
+    let manager = manager
+        .with_initial_refresh_delay(Duration::from_secs(3600))
+        .with_presign_limit(2, Duration::from_secs(10));
+    let id = manager
+        .add_mapping(Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            "burst.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        ))
+        .await
+        .unwrap();
+    let manager = Arc::new(manager);
+
+    // Distinct TTLs, so nothing is coalesced
+    let handles: Vec<_> = (0..6u64)
+        .map(|i| {
+            let manager = Arc::clone(&manager);
+            tokio::spawn(async move {
+                manager
+                    .presign_now(&id, PresignMethod::Get, Some(3600 + i), None)
+                    .await
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+    assert_eq!(*storage.calls.lock().unwrap(), 6);
+    assert_eq!(storage.running.lock().unwrap().1, 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_saturated_presigns_fail_with_503() {
+    let (manager, _log_rx) = MappingManager::new(slow_storage(), FakeDns::default());
+    let manager = manager
+        .with_initial_refresh_delay(Duration::from_secs(3600))
+        .with_presign_limit(1, Duration::from_millis(10));
+    let id = manager
+        .add_mapping(Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            "burst.example.com".to_string(),
+            "Z1234567890ABC".to_string(),
+        ))
+        .await
+        .unwrap();
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    let presign = |ttl: u64| {
+        let app = app.clone();
+        tokio::spawn(async move {
+            let request = Request::get(format!("/mappings/{}/presign?ttl={}", id, ttl))
+                .body(Body::empty())
+                .unwrap();
+            app.oneshot(request).await.unwrap().status()
+        })
+    };
+    let first = presign(3600);
+    advance(Duration::from_millis(20)).await;
+    let second = presign(7200);
+
+    assert_eq!(second.await.unwrap(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(first.await.unwrap(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_bulk_patch_scoped_by_zone() {
+    let dns = FakeDns::default();
+    dns.zone_names.lock().unwrap().insert(
+        "ZBBBBBBBBBBBBB".to_string(),
+        Some("example.org.".to_string()),
+    );
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, dns.clone());
+    let manager = Arc::new(manager);
+    let mut ids = Vec::new();
+    for (short_url, zone) in [
+        ("a.example.com", "ZAAAAAAAAAAAAA"),
+        ("b.example.com", "ZAAAAAAAAAAAAA"),
+        ("c.example.org", "ZBBBBBBBBBBBBB"),
+    ] {
+        let mapping = Mapping::new(
+            "s3://my-bucket/file.pdf".to_string(),
+            short_url.to_string(),
+            zone.to_string(),
+        );
+        ids.push(manager.add_mapping(mapping).await.unwrap());
+    }
+    // Closed for 12 hours a day, as long as the default presign duration
+    let mut windowed = Mapping::new(
+        "s3://my-bucket/file.pdf".to_string(),
+        "d.example.org".to_string(),
+        "ZBBBBBBBBBBBBB".to_string(),
+    );
+    windowed.allowed_window = Some(RefreshWindow {
+        start: chrono::NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+        end: chrono::NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+        days: Vec::new(),
+    });
+    let windowed = manager.add_mapping(windowed).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+    let patch = |app: axum::Router, uri: &str, body: serde_json::Value| {
+        let request = Request::patch(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        async move { app.oneshot(request).await.unwrap() }
+    };
+
+    // Invalid durations change nothing
+    let response = patch(
+        app.clone(),
+        "/mappings?zone=/hostedzone/ZAAAAAAAAAAAAA",
+        serde_json::json!({"refresh_interval_secs": 0}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = patch(
+        app.clone(),
+        "/mappings",
+        serde_json::json!({"presign_duration_secs": 7200}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    // Too short for one match's allowed window, so the other isn't changed either
+    let response = patch(
+        app.clone(),
+        "/mappings?zone=ZBBBBBBBBBBBBB",
+        serde_json::json!({"presign_duration_secs": 7200}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    for id in [ids[2], windowed] {
+        let mapping = manager.get_mapping(&id).await.unwrap();
+        assert_eq!(mapping.presign_duration_secs, 12 * 3600);
+    }
+
+    let response = patch(
+        app.clone(),
+        "/mappings?zone=/hostedzone/ZAAAAAAAAAAAAA",
+        serde_json::json!({"presign_duration_secs": 7200, "refresh_interval_secs": 3600}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: BulkUpdateReport = body_json(response.into_body()).await;
+    assert_eq!(report.matched, 2);
+    assert_eq!(report.updated, 2);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    for id in &ids[..2] {
+        let mapping = manager.get_mapping(id).await.unwrap();
+        assert_eq!(mapping.presign_duration_secs, 7200);
+        assert_eq!(mapping.refresh_interval_secs, 3600);
+    }
+    let untouched = manager.get_mapping(&ids[2]).await.unwrap();
+    assert_eq!(untouched.presign_duration_secs, 12 * 3600);
+
+    // The respawned tasks refreshed straight away; the other one didn't
+    let upserts = dns.upserts.lock().unwrap().clone();
+    let count = |short_url: &str| upserts.iter().filter(|u| u.1 == short_url).count();
+    assert_eq!(count("a.example.com"), 2);
+    assert_eq!(count("b.example.com"), 2);
+    assert_eq!(count("c.example.org"), 1);
+
+    // Durations over the AWS maximum are capped, as on create and update
+    let response = patch(
+        app,
+        "/mappings?zone=ZBBBBBBBBBBBBB",
+        serde_json::json!({"presign_duration_secs": 30 * 24 * 60 * 60}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: BulkUpdateReport = body_json(response.into_body()).await;
+    assert_eq!(report.updated, 2);
+    for id in [ids[2], windowed] {
+        let mapping = manager.get_mapping(&id).await.unwrap();
+        assert_eq!(mapping.presign_duration_secs, 7 * 24 * 60 * 60);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_add_refresh_delete_leaks_nothing() {
+    let dns = FakeDns::default();
+    let (manager, _log_rx) = MappingManager::new(slow_storage(), dns.clone());
+    let manager = Arc::new(manager.with_refresh_debounce(Duration::ZERO));
+
+    let rounds: Vec<_> = (0..200u64)
+        .map(|i| {
+            let manager = Arc::clone(&manager);
+            tokio::spawn(async move {
+                let mapping = Mapping::new(
+                    "s3://my-bucket/file.pdf".to_string(),
+                    format!("stress-{}.example.com", i),
+                    "Z1234567890ABC".to_string(),
+                );
+                let id = mapping.id;
+                // Deletes land before, during and after the add and its refreshes,
+                // on other worker threads
+                let add = tokio::spawn({
+                    let manager = Arc::clone(&manager);
+                    async move { manager.add_mapping(mapping).await }
+                });
+                let refresh = tokio::spawn({
+                    let manager = Arc::clone(&manager);
+                    async move {
+                        tokio::time::sleep(Duration::from_millis(i % 5 * 10)).await;
+                        let _ = manager.refresh_now(&id).await;
+                    }
+                });
+                let delete = tokio::spawn({
+                    let manager = Arc::clone(&manager);
+                    async move {
+                        for _ in 0..i % 4 {
+                            tokio::task::yield_now().await;
+                        }
+                        tokio::time::sleep(Duration::from_millis(i % 3 * 60)).await;
+                        let _ = manager.delete_mapping(&id).await;
+                    }
+                });
+                let added = add.await.unwrap().is_ok();
+                refresh.await.unwrap();
+                delete.await.unwrap();
+                // Whichever order they ran in, the mapping ends up deleted
+                if added {
+                    let _ = manager.delete_mapping(&id).await;
+                }
+            })
+        })
+        .collect();
+    for round in rounds {
+        round.await.unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert!(manager.list_mappings().await.is_empty());
+    // No task outlived its mapping
+    let report = manager.gc().await;
+    assert_eq!((report.aborted, report.started), (0, 0));
+
+    // And nothing refreshes any more
+    let upserts = dns.upserts.lock().unwrap().len();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(dns.upserts.lock().unwrap().len(), upserts);
+}
+
+#[tokio::test]
+async fn test_presign_download_filename_from_mapping_or_query() {
+    let storage = ScriptedStorage::default();
+    let (manager, _log_rx) = MappingManager::new(storage.clone(), FakeDns::default());
+    let manager = manager.with_initial_refresh_delay(Duration::from_secs(3600));
+    let mut mapping = Mapping::new(
+        "s3://my-bucket/2024/q3/a81f.pdf".to_string(),
+        "report.example.com".to_string(),
+        "Z1234567890ABC".to_string(),
+    );
+    mapping.download_filename = Some("Q3 report.pdf".to_string());
+    let id = manager.add_mapping(mapping).await.unwrap();
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+
+    for (query, expected) in [
+        ("", "attachment; filename=\"Q3 report.pdf\""),
+        (
+            "?download=summary.pdf",
+            "attachment; filename=\"summary.pdf\"",
+        ),
+        (
+            "?download=x%22.pdf%0D%0AX-Injected%3A%201",
+            "attachment; filename=\"x_.pdfX-Injected: 1\"",
+        ),
+    ] {
+        let request = Request::get(format!("/mappings/{}/presign{}", id, query))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let _: PresignedUrl = body_json(response.into_body()).await;
+        assert_eq!(
+            storage
+                .dispositions
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .as_deref(),
+            Some(expected)
+        );
+    }
+
+    // A filename with nothing usable left is rejected
+    let request = Request::get(format!("/mappings/{}/presign?download=%0A", id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_stats_task_count_matches_active_mappings() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let manager = Arc::new(manager);
+    let mut ids = Vec::new();
+    for short_url in ["a.example.com", "b.example.com", "c.example.com"] {
+        let id = manager
+            .add_mapping(Mapping::new(
+                "s3://my-bucket/file.pdf".to_string(),
+                short_url.to_string(),
+                "Z1234567890ABC".to_string(),
+            ))
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    let stats = manager.stats().await;
+    assert_eq!((stats.active_tasks, stats.active_mappings), (3, 3));
+
+    manager.pause_mapping(&ids[0]).await.unwrap();
+    manager.delete_mapping(&ids[1]).await.unwrap();
+    let stats = manager.stats().await;
+    assert_eq!((stats.active_tasks, stats.active_mappings), (1, 1));
+
+    manager.resume_mapping(&ids[0]).await.unwrap();
+    let app = s3_buddy::server::create_router(Arc::clone(&manager));
+    let request = Request::get("/stats").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let stats: Stats = body_json(response.into_body()).await;
+    assert_eq!((stats.active_tasks, stats.active_mappings), (2, 2));
+    assert_eq!(stats.mappings.len(), 2);
+}
+
+#[tokio::test]
+async fn test_create_rejects_missing_object_unless_skipped() {
+    let (manager, _log_rx) = MappingManager::new(FakeStorage, FakeDns::default());
+    let app = s3_buddy::server::create_router(Arc::new(manager));
+    let create = |s3_url: &str, short_url: &str, skip_validation: bool| {
+        Request::post("/mappings")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "s3_url": s3_url,
+                    "short_url": short_url,
+                    "hosted_zone_id": "Z1234567890ABC",
+                    "skip_validation": skip_validation
+                })
+                .to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(create("s3://my-bucket/missing.pdf", "a.example.com", false))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(String::from_utf8_lossy(&bytes).contains("S3 object not found"));
+
+    let response = app
+        .clone()
+        .oneshot(create("s3://my-bucket/missing.pdf", "a.example.com", true))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Prefixes are checked by listing
+    let response = app
+        .clone()
+        .oneshot(create("s3://my-bucket/reports/", "b.example.com", false))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .oneshot(create("s3://my-bucket/missing/", "c.example.com", false))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}